@@ -1,397 +1,1100 @@
+//! Roll usage entries up into daily, weekly, monthly, session and block rows.
+
 use crate::types::*;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, Timelike, Utc};
 use indexmap::IndexMap;
 use itertools::Itertools;
 use rust_decimal::prelude::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Length of a billing block
+const BLOCK_DURATION_HOURS: i64 = 5;
+
+/// A block stays active while its last entry is more recent than this
+const BLOCK_ACTIVITY_MINUTES: i64 = 30;
+
+/// Gaps between session messages longer than this don't count as active time
+pub const DEFAULT_IDLE_MINUTES: i64 = 15;
 
 /// Aggregate usage entries by day
 pub fn aggregate_daily(entries: Vec<LoadedUsageEntry>, order: SortOrder) -> Vec<DailyUsage> {
-    let mut daily_map: IndexMap<DailyDate, Vec<LoadedUsageEntry>> = IndexMap::new();
+    let daily_map = group_entries(&entries, |e| DailyDate::from_datetime(e.timestamp));
+    daily_rows(&daily_map, order)
+}
 
-    // Group entries by date
-    for entry in entries {
-        let date = DailyDate::from_datetime(entry.timestamp);
-        daily_map.entry(date).or_default().push(entry);
+/// Aggregate usage entries by month
+pub fn aggregate_monthly(entries: Vec<LoadedUsageEntry>, order: SortOrder) -> Vec<MonthlyUsage> {
+    let monthly_map = group_entries(&entries, |e| MonthlyDate::from_datetime(e.timestamp));
+    monthly_rows(&monthly_map, order)
+}
+
+/// Aggregate usage entries by week
+pub fn aggregate_weekly(entries: Vec<LoadedUsageEntry>, order: SortOrder) -> Vec<WeeklyUsage> {
+    let weekly_map = group_entries(&entries, |e| WeeklyDate::from_datetime(e.timestamp));
+    weekly_rows(&weekly_map, order)
+}
+
+/// Aggregate usage entries by session
+pub fn aggregate_sessions(entries: Vec<LoadedUsageEntry>, order: SortOrder) -> Vec<SessionUsage> {
+    aggregate_sessions_with_idle(entries, order, Duration::minutes(DEFAULT_IDLE_MINUTES))
+}
+
+/// Aggregate usage entries by session, excluding gaps longer than `idle_threshold` from active time
+pub fn aggregate_sessions_with_idle(
+    entries: Vec<LoadedUsageEntry>,
+    order: SortOrder,
+    idle_threshold: Duration,
+) -> Vec<SessionUsage> {
+    let mut session_map: IndexMap<(SessionId, ProjectPath), SessionAccumulator> = IndexMap::new();
+
+    // Group entries by session and project
+    for entry in &entries {
+        if let Some(key) = session_key(entry) {
+            session_map
+                .entry(key)
+                .or_insert_with(|| SessionAccumulator::new(idle_threshold))
+                .add(entry);
+        }
     }
 
-    // Convert to DailyUsage structs
-    let mut results: Vec<_> = daily_map
-        .into_iter()
-        .map(|(date, entries)| aggregate_entries_to_daily(date, entries))
-        .collect();
+    session_rows(&session_map, order, idle_threshold)
+}
 
-    // Sort by date
-    match order {
-        SortOrder::Asc => results.sort_by_key(|d| d.date.clone()),
-        SortOrder::Desc => results.sort_by_key(|d| std::cmp::Reverse(d.date.clone())),
+/// Identify 5-hour billing blocks from entries
+pub fn identify_session_blocks(
+    mut entries: Vec<LoadedUsageEntry>,
+    token_limit: Option<u64>,
+) -> Vec<SessionBlock> {
+    if entries.is_empty() {
+        return Vec::new();
     }
 
-    results
+    // Sort by timestamp
+    entries.sort_by_key(|e| e.timestamp);
+
+    let mut tracker = BlockTracker::default();
+    for entry in &entries {
+        tracker.add(entry);
+    }
+
+    tracker.session_blocks(Utc::now(), token_limit)
 }
 
-/// Aggregate usage entries by month
-pub fn aggregate_monthly(entries: Vec<LoadedUsageEntry>, order: SortOrder) -> Vec<MonthlyUsage> {
-    let mut monthly_map: IndexMap<MonthlyDate, Vec<LoadedUsageEntry>> = IndexMap::new();
+/// Relative error of request percentiles above [`EXACT_SAMPLES`] requests
+const PERCENTILE_ACCURACY: f64 = 0.01;
 
-    // Group entries by month
-    for entry in entries {
-        let date = MonthlyDate::from_datetime(entry.timestamp);
-        monthly_map.entry(date).or_default().push(entry);
+/// Most requests whose percentiles are computed exactly
+const EXACT_SAMPLES: usize = 256;
+
+/// Streaming per-request statistics: exact count, sums and maximums, with
+/// exact percentiles up to [`EXACT_SAMPLES`] requests and estimates from a
+/// quantile sketch beyond that
+///
+/// Memory does not grow with the number of requests added.
+#[derive(Debug, Clone, Default)]
+pub struct RequestSamples {
+    count: u64,
+    token_sum: u64,
+    cost_sum: Decimal,
+    max_tokens: u64,
+    max_cost: Decimal,
+    /// Every request's tokens and cost until there are too many to keep
+    exact: Vec<(u64, Decimal)>,
+    tokens: QuantileSketch,
+    costs: QuantileSketch,
+}
+
+impl RequestSamples {
+    pub fn add(&mut self, entry: &LoadedUsageEntry) {
+        let tokens = entry.tokens.total();
+
+        self.count += 1;
+        self.token_sum += tokens;
+        self.cost_sum += entry.cost;
+        self.max_tokens = self.max_tokens.max(tokens);
+        self.max_cost = self.max_cost.max(entry.cost);
+
+        if self.count as usize <= EXACT_SAMPLES {
+            self.exact.push((tokens, entry.cost));
+            return;
+        }
+
+        // Past the limit, move the kept values into the sketches
+        for (tokens, cost) in std::mem::take(&mut self.exact)
+            .into_iter()
+            .chain([(tokens, entry.cost)])
+        {
+            self.tokens.add(tokens as f64);
+            self.costs.add(cost.to_f64().unwrap_or(0.0));
+        }
     }
 
-    // Convert to MonthlyUsage structs
-    let mut results: Vec<_> = monthly_map
-        .into_iter()
-        .map(|(date, entries)| aggregate_entries_to_monthly(date, entries))
-        .collect();
+    pub fn count(&self) -> u64 {
+        self.count
+    }
 
-    // Sort by date
-    match order {
-        SortOrder::Asc => results.sort_by_key(|m| m.date.clone()),
-        SortOrder::Desc => results.sort_by_key(|m| std::cmp::Reverse(m.date.clone())),
+    /// Summarize the requests; all zero when none were seen
+    pub fn stats(&self) -> RequestStats {
+        let count = self.count;
+        if count == 0 {
+            return RequestStats::default();
+        }
+
+        let ((p50_tokens, p95_tokens), (p50_cost, p95_cost)) = if self.exact.is_empty() {
+            let tokens = |percent| {
+                (self.tokens.percentile(percent, count).round() as u64).min(self.max_tokens)
+            };
+            let cost = |percent| {
+                Decimal::from_f64(self.costs.percentile(percent, count))
+                    .unwrap_or_default()
+                    .min(self.max_cost)
+                    .round_dp(6)
+            };
+            ((tokens(50), tokens(95)), (cost(50), cost(95)))
+        } else {
+            let mut tokens: Vec<u64> = self.exact.iter().map(|&(t, _)| t).collect();
+            let mut costs: Vec<Decimal> = self.exact.iter().map(|&(_, c)| c).collect();
+            tokens.sort_unstable();
+            costs.sort_unstable();
+            (
+                (nearest_rank(&tokens, 50), nearest_rank(&tokens, 95)),
+                (
+                    nearest_rank(&costs, 50).round_dp(6),
+                    nearest_rank(&costs, 95).round_dp(6),
+                ),
+            )
+        };
+
+        RequestStats {
+            count,
+            avg_tokens: self.token_sum / count,
+            p50_tokens,
+            p95_tokens,
+            max_tokens: self.max_tokens,
+            avg_cost: (self.cost_sum / Decimal::from(count)).round_dp(6),
+            p50_cost,
+            p95_cost,
+            max_cost: self.max_cost,
+        }
     }
+}
 
-    results
+/// Nearest-rank percentile of sorted, non-empty values
+fn nearest_rank<T: Copy>(sorted: &[T], percent: u64) -> T {
+    let rank = (percent as usize * sorted.len()).div_ceil(100);
+    sorted[rank.max(1) - 1]
 }
 
-/// Aggregate usage entries by week
-pub fn aggregate_weekly(entries: Vec<LoadedUsageEntry>, order: SortOrder) -> Vec<WeeklyUsage> {
-    let mut weekly_map: IndexMap<WeeklyDate, Vec<LoadedUsageEntry>> = IndexMap::new();
+/// Quantile sketch with bounded relative error (DDSketch)
+///
+/// Positive values are counted in logarithmic buckets, so memory depends on the
+/// range of values rather than how many were added, and every percentile is
+/// within [`PERCENTILE_ACCURACY`] of the nearest-rank value.
+#[derive(Debug, Clone, Default)]
+struct QuantileSketch {
+    zeros: u64,
+    buckets: BTreeMap<i32, u64>,
+}
 
-    // Group entries by week
-    for entry in entries {
-        let date = WeeklyDate::from_datetime(entry.timestamp);
-        weekly_map.entry(date).or_default().push(entry);
+impl QuantileSketch {
+    fn gamma() -> f64 {
+        (1.0 + PERCENTILE_ACCURACY) / (1.0 - PERCENTILE_ACCURACY)
     }
 
-    // Convert to WeeklyUsage structs
-    let mut results: Vec<_> = weekly_map
-        .into_iter()
-        .map(|(date, entries)| aggregate_entries_to_weekly(date, entries))
-        .collect();
+    fn add(&mut self, value: f64) {
+        if value <= f64::MIN_POSITIVE {
+            self.zeros += 1;
+            return;
+        }
 
-    // Sort by date
-    match order {
-        SortOrder::Asc => results.sort_by_key(|w| w.date.clone()),
-        SortOrder::Desc => results.sort_by_key(|w| std::cmp::Reverse(w.date.clone())),
+        let index = (value.ln() / Self::gamma().ln()).ceil() as i32;
+        *self.buckets.entry(index).or_default() += 1;
     }
 
-    results
+    /// Nearest-rank `percent` percentile of the `count` values added
+    fn percentile(&self, percent: u64, count: u64) -> f64 {
+        let rank = (percent * count).div_ceil(100).max(1);
+        if rank <= self.zeros {
+            return 0.0;
+        }
+
+        let gamma = Self::gamma();
+        let mut seen = self.zeros;
+        for (&index, &n) in &self.buckets {
+            seen += n;
+            if seen >= rank {
+                // Midpoint of the bucket (gamma^(i-1), gamma^i] in relative terms
+                return 2.0 * gamma.powi(index) / (gamma + 1.0);
+            }
+        }
+
+        0.0
+    }
 }
 
-/// Aggregate usage entries by session
-pub fn aggregate_sessions(entries: Vec<LoadedUsageEntry>, order: SortOrder) -> Vec<SessionUsage> {
-    let mut session_map: IndexMap<(SessionId, ProjectPath), Vec<LoadedUsageEntry>> =
-        IndexMap::new();
+/// Totals for one model within a group
+#[derive(Debug, Clone, Default)]
+struct ModelAccumulator {
+    tokens: AggregatedTokenCounts,
+    cost: Decimal,
+    requests: RequestSamples,
+}
 
-    // Group entries by session and project
-    for entry in entries {
-        if let Some(session_id) = entry.session_id.clone() {
-            let project_path = ProjectPath::new(
-                entry
-                    .project
-                    .clone()
-                    .unwrap_or_else(|| "unknown".to_string()),
-            );
-            let key = (session_id, project_path);
-            session_map.entry(key).or_default().push(entry);
+/// Running token and cost totals for a group of entries, updated one entry at a time
+#[derive(Debug, Clone, Default)]
+pub struct UsageAccumulator {
+    tokens: AggregatedTokenCounts,
+    cost: Decimal,
+    requests: RequestSamples,
+    models: HashMap<ModelName, ModelAccumulator>,
+}
+
+impl UsageAccumulator {
+    /// Add a single entry to the totals
+    pub fn add(&mut self, entry: &LoadedUsageEntry) {
+        // Add to totals
+        self.tokens.add_from_raw(&entry.tokens);
+        self.cost += entry.cost;
+        self.requests.add(entry);
+
+        // Add to model-specific totals
+        let model_entry = self.models.entry(entry.model.clone()).or_default();
+        model_entry.tokens.add_from_raw(&entry.tokens);
+        model_entry.cost += entry.cost;
+        model_entry.requests.add(entry);
+    }
+
+    pub fn tokens(&self) -> &AggregatedTokenCounts {
+        &self.tokens
+    }
+
+    pub fn cost(&self) -> Decimal {
+        self.cost
+    }
+
+    pub fn request_count(&self) -> u64 {
+        self.requests.count()
+    }
+
+    pub fn request_stats(&self) -> RequestStats {
+        self.requests.stats()
+    }
+
+    /// Per-model breakdowns, sorted by cost descending
+    pub fn model_breakdowns(&self) -> Vec<ModelBreakdown> {
+        let mut breakdowns: Vec<ModelBreakdown> = self
+            .models
+            .iter()
+            .map(|(model, usage)| ModelBreakdown {
+                model_name: model.clone(),
+                input_tokens: usage.tokens.input_tokens,
+                output_tokens: usage.tokens.output_tokens,
+                cache_creation_tokens: usage.tokens.cache_creation_tokens,
+                cache_read_tokens: usage.tokens.cache_read_tokens,
+                cost: usage.cost,
+                requests: usage.requests.stats(),
+            })
+            .collect();
+
+        breakdowns.sort_by_key(|b| std::cmp::Reverse(b.cost));
+        breakdowns
+    }
+}
+
+/// Running totals for a single session
+#[derive(Debug, Clone)]
+pub struct SessionAccumulator {
+    usage: UsageAccumulator,
+    main_thread: ThreadUsage,
+    subagents: ThreadUsage,
+    versions: Vec<String>,
+    /// Message times in ascending order
+    timestamps: Vec<DateTime<Utc>>,
+    /// Gaps longer than this don't count towards `active`
+    idle_threshold: Duration,
+    /// Active time, kept up to date as entries are added
+    active: Duration,
+}
+
+impl Default for SessionAccumulator {
+    fn default() -> Self {
+        Self::new(Duration::minutes(DEFAULT_IDLE_MINUTES))
+    }
+}
+
+impl SessionAccumulator {
+    /// Start a session that tracks active time for `idle_threshold`
+    pub fn new(idle_threshold: Duration) -> Self {
+        Self {
+            usage: UsageAccumulator::default(),
+            main_thread: ThreadUsage::default(),
+            subagents: ThreadUsage::default(),
+            versions: Vec::new(),
+            timestamps: Vec::new(),
+            idle_threshold,
+            active: Duration::zero(),
         }
     }
 
-    // Convert to SessionUsage structs
-    let mut results: Vec<_> = session_map
-        .into_iter()
-        .map(|((session_id, project_path), entries)| {
-            aggregate_entries_to_session(session_id, project_path, entries)
-        })
-        .collect();
+    pub fn add(&mut self, entry: &LoadedUsageEntry) {
+        self.usage.add(entry);
 
-    // Sort by last activity
-    match order {
-        SortOrder::Asc => results.sort_by_key(|s| s.last_activity),
-        SortOrder::Desc => results.sort_by_key(|s| std::cmp::Reverse(s.last_activity)),
+        if entry.is_sidechain {
+            self.subagents.add(entry);
+        } else {
+            self.main_thread.add(entry);
+        }
+
+        // Keep unique versions in order of first appearance
+        if let Some(version) = &entry.version {
+            if !self.versions.contains(version) {
+                self.versions.push(version.clone());
+            }
+        }
+
+        match self.timestamps.last() {
+            // Transcripts are mostly in order, so re-sorting is rare
+            Some(&last) if entry.timestamp < last => {
+                self.timestamps.push(entry.timestamp);
+                self.timestamps.sort();
+                self.active = active_time(&self.timestamps, self.idle_threshold);
+            }
+            last => {
+                if let Some(&last) = last {
+                    self.active += active_gap(entry.timestamp - last, self.idle_threshold);
+                }
+                self.timestamps.push(entry.timestamp);
+            }
+        }
     }
 
-    results
+    fn first_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamps.first().copied()
+    }
+
+    fn last_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamps.last().copied()
+    }
+
+    /// Time between consecutive messages, skipping gaps longer than `idle_threshold`
+    fn active_time(&self, idle_threshold: Duration) -> Duration {
+        if idle_threshold == self.idle_threshold {
+            self.active
+        } else {
+            active_time(&self.timestamps, idle_threshold)
+        }
+    }
 }
 
-/// Identify 5-hour billing blocks from entries
-pub fn identify_session_blocks(
-    mut entries: Vec<LoadedUsageEntry>,
-    token_limit: Option<u64>,
-) -> Vec<SessionBlock> {
-    if entries.is_empty() {
-        return Vec::new();
+/// Sum the gaps between sorted timestamps, skipping gaps longer than `idle_threshold`
+fn active_time(timestamps: &[DateTime<Utc>], idle_threshold: Duration) -> Duration {
+    timestamps
+        .windows(2)
+        .map(|pair| active_gap(pair[1] - pair[0], idle_threshold))
+        .fold(Duration::zero(), |total, gap| total + gap)
+}
+
+/// The part of a gap between messages that counts as active time
+fn active_gap(gap: Duration, idle_threshold: Duration) -> Duration {
+    if gap <= idle_threshold {
+        gap
+    } else {
+        Duration::zero()
     }
+}
 
-    // Sort by timestamp
-    entries.sort_by_key(|e| e.timestamp);
+/// Messages per active hour, or `None` when the session has no active time
+fn messages_per_hour(messages: u64, active: Duration) -> Option<f64> {
+    let hours = active.num_seconds() as f64 / 3600.0;
+    (hours > 0.0).then(|| ((messages as f64 / hours) * 10.0).round() / 10.0)
+}
+
+/// Running totals for a single 5-hour billing block
+#[derive(Debug, Clone)]
+pub struct BlockAccumulator {
+    pub start_time: DateTime<Utc>,
+    /// Five hours after the start, or earlier when a later block starts first
+    end_time: DateTime<Utc>,
+    pub first_timestamp: DateTime<Utc>,
+    pub last_timestamp: DateTime<Utc>,
+    pub usage: UsageAccumulator,
+}
 
-    let mut blocks = Vec::new();
-    let block_duration = Duration::hours(5);
-    let now = Utc::now();
+impl BlockAccumulator {
+    fn new(start_time: DateTime<Utc>, entry: &LoadedUsageEntry) -> Self {
+        let mut usage = UsageAccumulator::default();
+        usage.add(entry);
 
-    let mut current_block_entries = Vec::new();
-    let mut current_block_start: Option<DateTime<Utc>> = None;
+        Self {
+            start_time,
+            end_time: start_time + Duration::hours(BLOCK_DURATION_HOURS),
+            first_timestamp: entry.timestamp,
+            last_timestamp: entry.timestamp,
+            usage,
+        }
+    }
 
-    for entry in entries {
-        // Floor timestamp to hour
-        use chrono::Timelike;
-        let entry_hour = entry
-            .timestamp
-            .with_minute(0)
-            .unwrap()
-            .with_second(0)
-            .unwrap()
-            .with_nanosecond(0)
-            .unwrap();
-
-        if let Some(block_start) = current_block_start {
-            // Check if entry is within current block
-            if entry_hour < block_start + block_duration {
-                current_block_entries.push(entry);
+    fn add(&mut self, entry: &LoadedUsageEntry) {
+        self.usage.add(entry);
+        self.first_timestamp = self.first_timestamp.min(entry.timestamp);
+        self.last_timestamp = self.last_timestamp.max(entry.timestamp);
+    }
+
+    pub fn end_time(&self) -> DateTime<Utc> {
+        self.end_time
+    }
+
+    /// Whether the block has seen activity recently enough to still be running
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        now - self.last_timestamp < Duration::minutes(BLOCK_ACTIVITY_MINUTES)
+    }
+
+    /// Build the reported block as of `now`
+    pub fn to_session_block(&self, now: DateTime<Utc>, token_limit: Option<u64>) -> SessionBlock {
+        let end_time = self.end_time();
+        let is_active = self.is_active(now);
+        let token_counts = TokenCounts::from(self.usage.tokens());
+
+        // Calculate usage limit reset time if near limit
+        let usage_limit_reset_time = match token_limit {
+            Some(limit) if token_counts.total() >= (limit * 80 / 100) => Some(end_time),
+            _ => None,
+        };
+
+        SessionBlock {
+            id: self.start_time.to_rfc3339(),
+            start_time: self.start_time,
+            end_time,
+            actual_end_time: if is_active {
+                None
             } else {
-                // Create block for current entries
-                if !current_block_entries.is_empty() {
-                    blocks.push(create_session_block(
-                        block_start,
-                        std::mem::take(&mut current_block_entries),
-                        now,
-                        token_limit,
-                    ));
+                Some(self.last_timestamp)
+            },
+            is_active,
+            is_gap: Some(false),
+            token_counts,
+            cost_usd: self.usage.cost(),
+            models: self
+                .usage
+                .models
+                .keys()
+                .map(|m| m.as_str().to_string())
+                .sorted()
+                .collect(),
+            requests: self.usage.request_stats(),
+            usage_limit_reset_time,
+        }
+    }
+}
+
+/// Groups entries into 5-hour billing blocks as they arrive
+#[derive(Debug, Clone, Default)]
+pub struct BlockTracker {
+    blocks: Vec<BlockAccumulator>,
+}
+
+impl BlockTracker {
+    /// Add an entry, starting a new block when it falls outside the latest one
+    ///
+    /// Entries are expected in timestamp order. A late entry that predates the latest
+    /// block joins the block covering its hour, or starts a block of its own that
+    /// ends no later than the next block starts, so blocks never overlap.
+    pub fn add(&mut self, entry: &LoadedUsageEntry) {
+        let entry_hour = floor_to_hour(entry.timestamp);
+
+        match self.blocks.last_mut() {
+            Some(last) if last.start_time <= entry_hour => {
+                if entry_hour < last.end_time() {
+                    last.add(entry);
+                } else {
+                    self.blocks.push(BlockAccumulator::new(entry_hour, entry));
+                }
+            }
+            Some(_) => {
+                let idx = self.blocks.partition_point(|b| b.start_time <= entry_hour);
+                if idx > 0 && entry_hour < self.blocks[idx - 1].end_time() {
+                    self.blocks[idx - 1].add(entry);
+                } else {
+                    let mut block = BlockAccumulator::new(entry_hour, entry);
+                    block.end_time = block.end_time.min(self.blocks[idx].start_time);
+                    self.blocks.insert(idx, block);
                 }
+            }
+            None => self.blocks.push(BlockAccumulator::new(entry_hour, entry)),
+        }
+    }
+
+    pub fn accumulators(&self) -> &[BlockAccumulator] {
+        &self.blocks
+    }
 
-                // Check for gap
-                if entry_hour > block_start + block_duration {
-                    // Create gap block
-                    let gap_start = block_start + block_duration;
-                    let gap_end = entry_hour;
+    /// Build reported blocks as of `now`, with gap blocks between idle periods
+    pub fn session_blocks(
+        &self,
+        now: DateTime<Utc>,
+        token_limit: Option<u64>,
+    ) -> Vec<SessionBlock> {
+        let mut blocks = Vec::with_capacity(self.blocks.len());
+
+        for (idx, block) in self.blocks.iter().enumerate() {
+            // Check for gap since the previous block
+            if let Some(prev) = idx.checked_sub(1).map(|i| &self.blocks[i]) {
+                if block.start_time > prev.end_time() {
+                    let gap_start = prev.end_time();
                     blocks.push(SessionBlock {
                         id: gap_start.to_rfc3339(),
                         start_time: gap_start,
-                        end_time: gap_end,
+                        end_time: block.start_time,
                         actual_end_time: None,
                         is_active: false,
                         is_gap: Some(true),
                         token_counts: TokenCounts::default(),
                         cost_usd: Decimal::ZERO,
                         models: Vec::new(),
+                        requests: RequestStats::default(),
                         usage_limit_reset_time: None,
                     });
                 }
-
-                // Start new block
-                current_block_start = Some(entry_hour);
-                current_block_entries.push(entry);
             }
-        } else {
-            // First block
-            current_block_start = Some(entry_hour);
-            current_block_entries.push(entry);
+
+            blocks.push(block.to_session_block(now, token_limit));
         }
+
+        blocks
     }
+}
 
-    // Create final block
-    if let Some(block_start) = current_block_start {
-        if !current_block_entries.is_empty() {
-            blocks.push(create_session_block(
-                block_start,
-                current_block_entries,
-                now,
-                token_limit,
-            ));
+/// Usage aggregates that can be updated with newly appended entries
+///
+/// Used by live mode so each refresh only costs as much as the lines added since
+/// the previous one, instead of re-aggregating the full history.
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalAggregate {
+    daily: IndexMap<DailyDate, UsageAccumulator>,
+    weekly: IndexMap<WeeklyDate, UsageAccumulator>,
+    monthly: IndexMap<MonthlyDate, UsageAccumulator>,
+    sessions: IndexMap<(SessionId, ProjectPath), SessionAccumulator>,
+    blocks: BlockTracker,
+}
+
+impl IncrementalAggregate {
+    /// Add a batch of new entries
+    pub fn extend(&mut self, mut entries: Vec<LoadedUsageEntry>) {
+        // Keep blocks on their in-order fast path
+        entries.sort_by_key(|e| e.timestamp);
+
+        for entry in &entries {
+            self.add(entry);
         }
     }
 
-    blocks
+    /// Add a single new entry to every aggregate
+    pub fn add(&mut self, entry: &LoadedUsageEntry) {
+        self.daily
+            .entry(DailyDate::from_datetime(entry.timestamp))
+            .or_default()
+            .add(entry);
+        self.weekly
+            .entry(WeeklyDate::from_datetime(entry.timestamp))
+            .or_default()
+            .add(entry);
+        self.monthly
+            .entry(MonthlyDate::from_datetime(entry.timestamp))
+            .or_default()
+            .add(entry);
+
+        if let Some(key) = session_key(entry) {
+            self.sessions.entry(key).or_default().add(entry);
+        }
+
+        self.blocks.add(entry);
+    }
+
+    pub fn daily(&self, order: SortOrder) -> Vec<DailyUsage> {
+        daily_rows(&self.daily, order)
+    }
+
+    /// Running totals of one day, if it has usage
+    pub fn day(&self, date: DailyDate) -> Option<&UsageAccumulator> {
+        self.daily.get(&date)
+    }
+
+    pub fn weekly(&self, order: SortOrder) -> Vec<WeeklyUsage> {
+        weekly_rows(&self.weekly, order)
+    }
+
+    pub fn monthly(&self, order: SortOrder) -> Vec<MonthlyUsage> {
+        monthly_rows(&self.monthly, order)
+    }
+
+    pub fn sessions(&self, order: SortOrder) -> Vec<SessionUsage> {
+        self.sessions_with_idle(order, Duration::minutes(DEFAULT_IDLE_MINUTES))
+    }
+
+    pub fn sessions_with_idle(
+        &self,
+        order: SortOrder,
+        idle_threshold: Duration,
+    ) -> Vec<SessionUsage> {
+        session_rows(&self.sessions, order, idle_threshold)
+    }
+
+    pub fn blocks(&self, token_limit: Option<u64>) -> Vec<SessionBlock> {
+        self.blocks.session_blocks(Utc::now(), token_limit)
+    }
+
+    pub fn block_tracker(&self) -> &BlockTracker {
+        &self.blocks
+    }
+}
+
+/// Group entries by key into running totals
+pub fn group_entries<K, F>(entries: &[LoadedUsageEntry], key: F) -> IndexMap<K, UsageAccumulator>
+where
+    K: Hash + Eq,
+    F: Fn(&LoadedUsageEntry) -> K,
+{
+    let mut map: IndexMap<K, UsageAccumulator> = IndexMap::new();
+    for entry in entries {
+        map.entry(key(entry)).or_default().add(entry);
+    }
+    map
 }
 
-/// Helper function to create a session block
-fn create_session_block(
-    start_time: DateTime<Utc>,
-    entries: Vec<LoadedUsageEntry>,
-    now: DateTime<Utc>,
-    token_limit: Option<u64>,
-) -> SessionBlock {
-    let end_time = start_time + Duration::hours(5);
-    let is_active = entries
-        .last()
-        .map(|e| now - e.timestamp < Duration::minutes(30))
-        .unwrap_or(false);
-
-    let actual_end_time = if !is_active {
-        entries.last().map(|e| e.timestamp)
-    } else {
-        None
-    };
+/// Split entries by [`LoadedUsageEntry::user_label`], users in alphabetical order
+pub fn split_by_user(entries: Vec<LoadedUsageEntry>) -> IndexMap<String, Vec<LoadedUsageEntry>> {
+    let mut users: IndexMap<String, Vec<LoadedUsageEntry>> = IndexMap::new();
+    for entry in entries {
+        users
+            .entry(entry.user_label().to_string())
+            .or_default()
+            .push(entry);
+    }
+    users.sort_keys();
+    users
+}
 
-    // Aggregate tokens and costs
-    let mut token_counts = TokenCounts::default();
-    let mut total_cost = Decimal::ZERO;
-    let mut models = HashSet::new();
+/// Session grouping key; entries without a session ID are not attributed
+fn session_key(entry: &LoadedUsageEntry) -> Option<(SessionId, ProjectPath)> {
+    let session_id = entry.session_id.clone()?;
+    let project_path = ProjectPath::new(
+        entry
+            .project
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string()),
+    );
+    Some((session_id, project_path))
+}
 
-    for entry in &entries {
-        token_counts.add(&entry.tokens);
-        total_cost += entry.cost;
-        models.insert(entry.model.as_str().to_string());
+/// Floor a timestamp to the start of its hour
+fn floor_to_hour(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    timestamp
+        .with_minute(0)
+        .unwrap()
+        .with_second(0)
+        .unwrap()
+        .with_nanosecond(0)
+        .unwrap()
+}
+
+/// Convert daily totals to sorted DailyUsage rows
+fn daily_rows(map: &IndexMap<DailyDate, UsageAccumulator>, order: SortOrder) -> Vec<DailyUsage> {
+    let mut results: Vec<_> = map
+        .iter()
+        .map(|(date, usage)| {
+            let tokens = usage.tokens();
+            let breakdowns = usage.model_breakdowns();
+
+            DailyUsage {
+                date: date.clone(),
+                input_tokens: tokens.input_tokens,
+                output_tokens: tokens.output_tokens,
+                cache_creation_tokens: tokens.cache_creation_tokens,
+                cache_read_tokens: tokens.cache_read_tokens,
+                total_cost: usage.cost(),
+                models_used: models_used(&breakdowns),
+                model_breakdowns: breakdowns,
+                requests: usage.request_stats(),
+                project: None,
+            }
+        })
+        .collect();
+
+    // Sort by date
+    match order {
+        SortOrder::Asc => results.sort_by_key(|d| d.date.clone()),
+        SortOrder::Desc => results.sort_by_key(|d| std::cmp::Reverse(d.date.clone())),
     }
 
-    // Calculate usage limit reset time if near limit
-    let usage_limit_reset_time = if let Some(limit) = token_limit {
-        if token_counts.total() >= (limit * 80 / 100) {
-            Some(end_time)
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    SessionBlock {
-        id: start_time.to_rfc3339(),
-        start_time,
-        end_time,
-        actual_end_time,
-        is_active,
-        is_gap: Some(false),
-        token_counts,
-        cost_usd: total_cost,
-        models: models.into_iter().sorted().collect(),
-        usage_limit_reset_time,
-    }
-}
-
-/// Helper to aggregate entries to DailyUsage
-fn aggregate_entries_to_daily(date: DailyDate, entries: Vec<LoadedUsageEntry>) -> DailyUsage {
-    let (tokens, cost, models, breakdowns) = aggregate_tokens_and_cost(entries);
-
-    DailyUsage {
-        date,
-        input_tokens: tokens.input_tokens,
-        output_tokens: tokens.output_tokens,
-        cache_creation_tokens: tokens.cache_creation_tokens,
-        cache_read_tokens: tokens.cache_read_tokens,
-        total_cost: cost,
-        models_used: models,
-        model_breakdowns: breakdowns,
-        project: None,
-    }
-}
-
-/// Helper to aggregate entries to MonthlyUsage
-fn aggregate_entries_to_monthly(date: MonthlyDate, entries: Vec<LoadedUsageEntry>) -> MonthlyUsage {
-    let (tokens, cost, models, breakdowns) = aggregate_tokens_and_cost(entries);
-
-    MonthlyUsage {
-        date,
-        input_tokens: tokens.input_tokens,
-        output_tokens: tokens.output_tokens,
-        cache_creation_tokens: tokens.cache_creation_tokens,
-        cache_read_tokens: tokens.cache_read_tokens,
-        total_cost: cost,
-        models_used: models,
-        model_breakdowns: breakdowns,
-        project: None,
-    }
-}
-
-/// Helper to aggregate entries to WeeklyUsage
-fn aggregate_entries_to_weekly(date: WeeklyDate, entries: Vec<LoadedUsageEntry>) -> WeeklyUsage {
-    let (tokens, cost, models, breakdowns) = aggregate_tokens_and_cost(entries);
-
-    WeeklyUsage {
-        date,
-        input_tokens: tokens.input_tokens,
-        output_tokens: tokens.output_tokens,
-        cache_creation_tokens: tokens.cache_creation_tokens,
-        cache_read_tokens: tokens.cache_read_tokens,
-        total_cost: cost,
-        models_used: models,
-        model_breakdowns: breakdowns,
-        project: None,
-    }
-}
-
-/// Helper to aggregate entries to SessionUsage
-fn aggregate_entries_to_session(
-    session_id: SessionId,
-    project_path: ProjectPath,
-    entries: Vec<LoadedUsageEntry>,
-) -> SessionUsage {
-    let (tokens, cost, models, breakdowns) = aggregate_tokens_and_cost(entries.clone());
+    results
+}
 
-    // Get unique versions
-    let versions: Vec<String> = entries
+/// Convert monthly totals to sorted MonthlyUsage rows
+fn monthly_rows(
+    map: &IndexMap<MonthlyDate, UsageAccumulator>,
+    order: SortOrder,
+) -> Vec<MonthlyUsage> {
+    let mut results: Vec<_> = map
         .iter()
-        .filter_map(|e| e.version.clone())
-        .unique()
+        .map(|(date, usage)| {
+            let tokens = usage.tokens();
+            let breakdowns = usage.model_breakdowns();
+
+            MonthlyUsage {
+                date: date.clone(),
+                input_tokens: tokens.input_tokens,
+                output_tokens: tokens.output_tokens,
+                cache_creation_tokens: tokens.cache_creation_tokens,
+                cache_read_tokens: tokens.cache_read_tokens,
+                total_cost: usage.cost(),
+                models_used: models_used(&breakdowns),
+                model_breakdowns: breakdowns,
+                requests: usage.request_stats(),
+                project: None,
+            }
+        })
         .collect();
 
-    // Get last activity date
-    let last_activity = entries
+    // Sort by date
+    match order {
+        SortOrder::Asc => results.sort_by_key(|m| m.date.clone()),
+        SortOrder::Desc => results.sort_by_key(|m| std::cmp::Reverse(m.date.clone())),
+    }
+
+    results
+}
+
+/// Convert weekly totals to sorted WeeklyUsage rows
+fn weekly_rows(map: &IndexMap<WeeklyDate, UsageAccumulator>, order: SortOrder) -> Vec<WeeklyUsage> {
+    let mut results: Vec<_> = map
         .iter()
-        .map(|e| e.timestamp.date_naive())
-        .max()
-        .unwrap_or_else(|| Utc::now().date_naive());
-
-    SessionUsage {
-        session_id,
-        project_path,
-        input_tokens: tokens.input_tokens,
-        output_tokens: tokens.output_tokens,
-        cache_creation_tokens: tokens.cache_creation_tokens,
-        cache_read_tokens: tokens.cache_read_tokens,
-        total_cost: cost,
-        last_activity,
-        versions,
-        models_used: models,
-        model_breakdowns: breakdowns,
-    }
-}
-
-/// Helper to aggregate tokens and costs from entries
-fn aggregate_tokens_and_cost(
-    entries: Vec<LoadedUsageEntry>,
-) -> (
-    AggregatedTokenCounts,
-    Decimal,
-    Vec<ModelName>,
-    Vec<ModelBreakdown>,
-) {
-    let mut total_tokens = AggregatedTokenCounts::default();
-    let mut total_cost = Decimal::ZERO;
-    let mut model_map: HashMap<ModelName, (AggregatedTokenCounts, Decimal)> = HashMap::new();
+        .map(|(date, usage)| {
+            let tokens = usage.tokens();
+            let breakdowns = usage.model_breakdowns();
 
-    for entry in entries {
-        // Add to totals
-        total_tokens.add_from_raw(&entry.tokens);
-        total_cost += entry.cost;
+            WeeklyUsage {
+                date: date.clone(),
+                input_tokens: tokens.input_tokens,
+                output_tokens: tokens.output_tokens,
+                cache_creation_tokens: tokens.cache_creation_tokens,
+                cache_read_tokens: tokens.cache_read_tokens,
+                total_cost: usage.cost(),
+                models_used: models_used(&breakdowns),
+                model_breakdowns: breakdowns,
+                requests: usage.request_stats(),
+                project: None,
+            }
+        })
+        .collect();
 
-        // Add to model-specific totals
-        let model_entry = model_map
-            .entry(entry.model.clone())
-            .or_insert_with(|| (AggregatedTokenCounts::default(), Decimal::ZERO));
-        model_entry.0.add_from_raw(&entry.tokens);
-        model_entry.1 += entry.cost;
-    }
-
-    // Create model breakdowns, sorted by cost descending
-    let mut breakdowns: Vec<ModelBreakdown> = model_map
-        .into_iter()
-        .map(|(model, (tokens, cost))| ModelBreakdown {
-            model_name: model,
-            input_tokens: tokens.input_tokens,
-            output_tokens: tokens.output_tokens,
-            cache_creation_tokens: tokens.cache_creation_tokens,
-            cache_read_tokens: tokens.cache_read_tokens,
-            cost,
+    // Sort by date
+    match order {
+        SortOrder::Asc => results.sort_by_key(|w| w.date.clone()),
+        SortOrder::Desc => results.sort_by_key(|w| std::cmp::Reverse(w.date.clone())),
+    }
+
+    results
+}
+
+/// Convert session totals to SessionUsage rows sorted by last activity
+fn session_rows(
+    map: &IndexMap<(SessionId, ProjectPath), SessionAccumulator>,
+    order: SortOrder,
+    idle_threshold: Duration,
+) -> Vec<SessionUsage> {
+    let mut results: Vec<_> = map
+        .iter()
+        .map(|((session_id, project_path), session)| {
+            let tokens = session.usage.tokens();
+            let breakdowns = session.usage.model_breakdowns();
+            let now = Utc::now();
+            let first_timestamp = session.first_timestamp().unwrap_or(now);
+            let last_timestamp = session.last_timestamp().unwrap_or(now);
+            let active = session.active_time(idle_threshold);
+
+            SessionUsage {
+                session_id: session_id.clone(),
+                project_path: project_path.clone(),
+                input_tokens: tokens.input_tokens,
+                output_tokens: tokens.output_tokens,
+                cache_creation_tokens: tokens.cache_creation_tokens,
+                cache_read_tokens: tokens.cache_read_tokens,
+                total_cost: session.usage.cost(),
+                last_activity: last_timestamp.date_naive(),
+                first_timestamp,
+                last_timestamp,
+                duration_seconds: (last_timestamp - first_timestamp).num_seconds(),
+                active_seconds: active.num_seconds(),
+                messages_per_hour: messages_per_hour(session.usage.request_count(), active),
+                versions: session.versions.clone(),
+                models_used: models_used(&breakdowns),
+                model_breakdowns: breakdowns,
+                requests: session.usage.request_stats(),
+                main_thread: session.main_thread.clone(),
+                subagents: session.subagents.clone(),
+            }
         })
         .collect();
 
-    breakdowns.sort_by(|a, b| b.cost.cmp(&a.cost));
+    // Sort by last activity
+    match order {
+        SortOrder::Asc => results.sort_by_key(|s| s.last_timestamp),
+        SortOrder::Desc => results.sort_by_key(|s| std::cmp::Reverse(s.last_timestamp)),
+    }
+
+    results
+}
+
+/// Models ordered as in their breakdowns (by cost descending)
+fn models_used(breakdowns: &[ModelBreakdown]) -> Vec<ModelName> {
+    breakdowns.iter().map(|b| b.model_name.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(tokens: u64, cost: Decimal) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap(),
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: tokens,
+                ..TokenCounts::default()
+            },
+            cost,
+            session_id: Some(SessionId::new("session-1")),
+            request_id: None,
+            message_id: None,
+            project: None,
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    /// Nearest-rank percentile of sorted values
+    fn exact_percentile<T: Copy>(sorted: &[T], percent: usize) -> T {
+        sorted[(percent * sorted.len()).div_ceil(100) - 1]
+    }
+
+    #[test]
+    fn request_stats_are_exact_or_within_accuracy() {
+        let mut samples = RequestSamples::default();
+        let mut tokens = Vec::new();
+        let mut costs = Vec::new();
+
+        // A skewed spread with a few runaway requests
+        for i in 1..=1000u64 {
+            let t = if i % 97 == 0 {
+                900_000 + i
+            } else {
+                1_000 + (i * i) % 50_000
+            };
+            let c = Decimal::new((t * 3) as i64, 6);
+            samples.add(&entry(t, c));
+            tokens.push(t);
+            costs.push(c);
+        }
+        tokens.sort_unstable();
+        costs.sort_unstable();
+
+        let stats = samples.stats();
+        assert_eq!(stats.count, 1000);
+        assert_eq!(stats.avg_tokens, tokens.iter().sum::<u64>() / 1000);
+        assert_eq!(stats.max_tokens, *tokens.last().unwrap());
+        assert_eq!(stats.max_cost, *costs.last().unwrap());
+        assert_eq!(
+            stats.avg_cost,
+            (costs.iter().sum::<Decimal>() / Decimal::from(1000)).round_dp(6)
+        );
 
-    let models: Vec<ModelName> = breakdowns.iter().map(|b| b.model_name.clone()).collect();
+        let close = |estimate: f64, exact: f64| {
+            assert!(
+                (estimate - exact).abs() <= exact * PERCENTILE_ACCURACY + 1e-6,
+                "{} not within 1% of {}",
+                estimate,
+                exact
+            )
+        };
+        for percent in [50, 95] {
+            let exact_tokens = exact_percentile(&tokens, percent) as f64;
+            let exact_cost = exact_percentile(&costs, percent).to_f64().unwrap();
+            let (p_tokens, p_cost) = match percent {
+                50 => (stats.p50_tokens, stats.p50_cost),
+                _ => (stats.p95_tokens, stats.p95_cost),
+            };
+            close(p_tokens as f64, exact_tokens);
+            close(p_cost.to_f64().unwrap(), exact_cost);
+        }
+    }
+
+    #[test]
+    fn request_stats_are_exact_for_few_requests() {
+        let mut samples = RequestSamples::default();
+        samples.add(&entry(12_345, Decimal::new(123_457, 6)));
+
+        let stats = samples.stats();
+        assert_eq!(stats.p50_tokens, 12_345);
+        assert_eq!(stats.p95_tokens, 12_345);
+        assert_eq!(stats.p50_cost, Decimal::new(123_457, 6));
+        assert_eq!(stats.p95_cost, Decimal::new(123_457, 6));
+
+        let mut tokens = vec![12_345];
+        for i in 1..EXACT_SAMPLES as u64 {
+            let t = 1_000 + (i * 7_919) % 30_011;
+            samples.add(&entry(t, Decimal::ZERO));
+            tokens.push(t);
+        }
+        tokens.sort_unstable();
 
-    (total_tokens, total_cost, models, breakdowns)
+        let stats = samples.stats();
+        assert_eq!(stats.p50_tokens, exact_percentile(&tokens, 50));
+        assert_eq!(stats.p95_tokens, exact_percentile(&tokens, 95));
+
+        // One more moves every request into the sketch
+        samples.add(&entry(1_000, Decimal::ZERO));
+        assert!(samples.exact.is_empty());
+        assert_eq!(
+            samples.tokens.buckets.values().sum::<u64>(),
+            samples.count()
+        );
+    }
+
+    #[test]
+    fn request_stats_memory_does_not_grow_with_requests() {
+        let mut samples = RequestSamples::default();
+        for i in 0..100_000u64 {
+            samples.add(&entry(
+                1_000 + i % 20_000,
+                Decimal::new(i as i64 % 5_000, 4),
+            ));
+        }
+
+        assert_eq!(samples.count(), 100_000);
+        assert!(samples.tokens.buckets.len() < 200);
+        assert!(samples.costs.buckets.len() < 500);
+    }
+
+    #[test]
+    fn request_stats_handle_zero_and_empty() {
+        assert_eq!(RequestSamples::default().stats(), RequestStats::default());
+
+        let mut samples = RequestSamples::default();
+        samples.add(&entry(500, Decimal::ZERO));
+        samples.add(&entry(500, Decimal::ZERO));
+        samples.add(&entry(700, Decimal::new(1, 2)));
+
+        let stats = samples.stats();
+        assert_eq!(stats.p50_cost, Decimal::ZERO);
+        assert!((stats.p95_cost - Decimal::new(1, 2)).abs() <= Decimal::new(1, 4));
+        assert!(stats.p95_tokens.abs_diff(700) <= 7);
+        assert_eq!(stats.max_tokens, 700);
+    }
+
+    /// An entry `minutes` after 09:00
+    fn entry_at(minutes: i64) -> LoadedUsageEntry {
+        let mut entry = entry(100, Decimal::ONE);
+        entry.timestamp += Duration::minutes(minutes);
+        entry
+    }
+
+    #[test]
+    fn session_active_time_accumulates_in_order() {
+        let mut session = SessionAccumulator::default();
+        for minutes in [0, 5, 12, 60, 70] {
+            session.add(&entry_at(minutes));
+        }
+
+        // The 48-minute gap is idle
+        assert_eq!(session.active, Duration::minutes(22));
+        assert_eq!(
+            session.active_time(Duration::minutes(15)),
+            Duration::minutes(22)
+        );
+        assert_eq!(session.first_timestamp(), Some(entry_at(0).timestamp));
+        assert_eq!(session.last_timestamp(), Some(entry_at(70).timestamp));
+    }
+
+    #[test]
+    fn session_active_time_handles_out_of_order_entries() {
+        let mut in_order = SessionAccumulator::default();
+        let mut shuffled = SessionAccumulator::default();
+        for minutes in [0, 5, 12, 60, 70] {
+            in_order.add(&entry_at(minutes));
+        }
+        for minutes in [60, 5, 70, 0, 12] {
+            shuffled.add(&entry_at(minutes));
+        }
+
+        assert_eq!(shuffled.active, in_order.active);
+        assert_eq!(shuffled.timestamps, in_order.timestamps);
+        assert_eq!(shuffled.first_timestamp(), Some(entry_at(0).timestamp));
+    }
+
+    #[test]
+    fn session_active_time_for_other_thresholds() {
+        let mut session = SessionAccumulator::new(Duration::minutes(5));
+        for minutes in [0, 5, 12, 60, 70] {
+            session.add(&entry_at(minutes));
+        }
+
+        assert_eq!(
+            session.active_time(Duration::minutes(5)),
+            Duration::minutes(5)
+        );
+        assert_eq!(
+            session.active_time(Duration::minutes(15)),
+            Duration::minutes(22)
+        );
+        assert_eq!(
+            session.active_time(Duration::hours(1)),
+            Duration::minutes(70)
+        );
+    }
+
+    #[test]
+    fn late_block_ends_where_the_next_block_starts() {
+        let mut tracker = BlockTracker::default();
+        // Blocks from 09:00 and 19:00, then late entries at 17:00 and 17:30
+        for minutes in [0, 600, 480, 510] {
+            tracker.add(&entry_at(minutes));
+        }
+
+        let hour = |h| Utc.with_ymd_and_hms(2026, 10, 18, h, 0, 0).unwrap();
+        let spans: Vec<_> = tracker
+            .accumulators()
+            .iter()
+            .map(|b| (b.start_time, b.end_time(), b.usage.request_count()))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (hour(9), hour(14), 1),
+                (hour(17), hour(19), 2),
+                (hour(19), hour(0) + Duration::days(1), 1),
+            ]
+        );
+
+        let blocks = tracker.session_blocks(hour(23), None);
+        for pair in blocks.windows(2) {
+            assert!(pair[0].end_time <= pair[1].start_time);
+        }
+        assert_eq!(blocks.iter().filter(|b| b.is_gap == Some(true)).count(), 1);
+    }
+
+    #[test]
+    fn aggregate_sessions_use_the_requested_idle_threshold() {
+        let entries: Vec<_> = [0, 5, 12, 60, 70].into_iter().map(entry_at).collect();
+
+        let sessions = aggregate_sessions_with_idle(entries, SortOrder::Asc, Duration::hours(1));
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].active_seconds, 70 * 60);
+        assert_eq!(sessions[0].duration_seconds, 70 * 60);
+    }
 }
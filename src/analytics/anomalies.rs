@@ -0,0 +1,418 @@
+use crate::aggregation::{aggregate_daily, aggregate_sessions, group_entries};
+use crate::types::*;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Scale factor making the median absolute deviation comparable to a standard deviation
+const MAD_SCALE: f64 = 0.6745;
+
+/// Most sessions or models listed as responsible for one anomaly
+const MAX_CONTRIBUTORS: usize = 5;
+
+/// Fewest trailing values needed before a day or session is scored
+pub const MIN_HISTORY: usize = 5;
+
+/// Outlier score used to compare a value with its trailing history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AnomalyMethod {
+    /// Modified z-score from the median and median absolute deviation
+    #[default]
+    Mad,
+    /// Standard z-score from the mean and standard deviation
+    Zscore,
+}
+
+impl AnomalyMethod {
+    /// Score above which a value is reported
+    pub fn default_threshold(self) -> f64 {
+        match self {
+            Self::Mad => 3.5,
+            Self::Zscore => 3.0,
+        }
+    }
+
+    /// Center of `history` and how far `value` lies above it, or `None` when
+    /// the history has no spread to measure against
+    fn score(self, value: f64, history: &[f64]) -> Option<(f64, f64)> {
+        match self {
+            Self::Mad => {
+                let median = median(history);
+                let deviations: Vec<f64> = history.iter().map(|v| (v - median).abs()).collect();
+                let mad = median_of(deviations);
+                (mad > 0.0).then(|| (median, MAD_SCALE * (value - median) / mad))
+            }
+            Self::Zscore => {
+                let n = history.len() as f64;
+                let mean = history.iter().sum::<f64>() / n;
+                let variance = history.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+                let std_dev = variance.sqrt();
+                (std_dev > 0.0).then(|| (mean, (value - mean) / std_dev))
+            }
+        }
+    }
+}
+
+/// Aggregate an anomaly was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnomalyKind {
+    Day,
+    Session,
+}
+
+/// A session or model and its part of an anomalous cost
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contributor {
+    pub name: String,
+    pub cost: Decimal,
+
+    /// Percentage of the anomaly's cost
+    pub share: f64,
+}
+
+/// A day or session whose cost is far above its trailing history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+
+    /// Date for days, session ID for sessions
+    pub key: String,
+
+    /// Start of the day or first message of the session
+    pub time: DateTime<Utc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+
+    pub cost: Decimal,
+
+    /// Median or mean cost of the trailing history
+    pub baseline: Decimal,
+
+    pub score: f64,
+
+    /// Largest sessions within an anomalous day
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<Contributor>,
+
+    pub models: Vec<Contributor>,
+}
+
+/// Settings for [`detect_anomalies`]
+#[derive(Debug, Clone, Copy)]
+pub struct AnomalyOptions {
+    pub method: AnomalyMethod,
+    pub threshold: f64,
+
+    /// Trailing days (for days) or sessions (for sessions) to compare against
+    pub history: usize,
+
+    /// Costs below this are never reported
+    pub min_cost: Decimal,
+}
+
+/// Days and sessions whose cost stands out from the ones before them
+///
+/// Each day is scored against the preceding `history` days with usage, and each
+/// session against the preceding `history` sessions by start time. Only unusually
+/// high costs are reported, highest score first.
+pub fn detect_anomalies(entries: &[LoadedUsageEntry], options: AnomalyOptions) -> Vec<Anomaly> {
+    let days = aggregate_daily(entries.to_vec(), SortOrder::Asc);
+    let sessions = aggregate_sessions(entries.to_vec(), SortOrder::Asc);
+    let mut anomalies = detect_anomalies_in(&days, sessions, options);
+
+    // Name the largest sessions within each anomalous day
+    for anomaly in anomalies.iter_mut().filter(|a| a.kind == AnomalyKind::Day) {
+        let date = anomaly.time.date_naive();
+        let day_entries: Vec<LoadedUsageEntry> = entries
+            .iter()
+            .filter(|e| e.timestamp.date_naive() == date)
+            .cloned()
+            .collect();
+        let sessions = group_entries(&day_entries, |e| {
+            e.session_id
+                .as_ref()
+                .map_or_else(|| "unknown".to_string(), |s| s.0.clone())
+        })
+        .into_iter()
+        .map(|(name, usage)| (name, usage.cost()));
+
+        anomaly.sessions = contributors(sessions, anomaly.cost);
+    }
+
+    anomalies
+}
+
+/// [`detect_anomalies`] over rows that are already aggregated, such as those of an
+/// [`IncrementalAggregate`](crate::aggregation::IncrementalAggregate)
+///
+/// `days` must be in date order. Day anomalies carry no session contributors,
+/// since the rows don't say which sessions made up each day.
+pub fn detect_anomalies_in(
+    days: &[DailyUsage],
+    mut sessions: Vec<SessionUsage>,
+    options: AnomalyOptions,
+) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+
+    let day_costs: Vec<f64> = days.iter().map(|d| to_f64(d.total_cost)).collect();
+    for (i, baseline, score) in outliers(&day_costs, options) {
+        let day = &days[i];
+        if day.total_cost < options.min_cost {
+            continue;
+        }
+
+        anomalies.push(Anomaly {
+            kind: AnomalyKind::Day,
+            key: day.date.to_string(),
+            time: start_of_day(day.date.0),
+            project: None,
+            cost: day.total_cost,
+            baseline: from_f64(baseline),
+            score,
+            sessions: Vec::new(),
+            models: model_contributors(&day.model_breakdowns, day.total_cost),
+        });
+    }
+
+    sessions.sort_by_key(|s| s.first_timestamp);
+    let session_costs: Vec<f64> = sessions.iter().map(|s| to_f64(s.total_cost)).collect();
+    for (i, baseline, score) in outliers(&session_costs, options) {
+        let session = &sessions[i];
+        if session.total_cost < options.min_cost {
+            continue;
+        }
+
+        anomalies.push(Anomaly {
+            kind: AnomalyKind::Session,
+            key: session.session_id.0.clone(),
+            time: session.first_timestamp,
+            project: Some(session.project_path.0.display().to_string()),
+            cost: session.total_cost,
+            baseline: from_f64(baseline),
+            score,
+            sessions: Vec::new(),
+            models: model_contributors(&session.model_breakdowns, session.total_cost),
+        });
+    }
+
+    anomalies.sort_by(|a, b| b.score.total_cmp(&a.score));
+    anomalies
+}
+
+/// Index, baseline and score of each value scoring above the threshold
+fn outliers(values: &[f64], options: AnomalyOptions) -> Vec<(usize, f64, f64)> {
+    values
+        .iter()
+        .enumerate()
+        .skip(MIN_HISTORY)
+        .filter_map(|(i, &value)| {
+            let history = &values[i.saturating_sub(options.history)..i];
+            options
+                .method
+                .score(value, history)
+                .filter(|&(_, score)| score > options.threshold)
+                .map(|(baseline, score)| (i, baseline, score))
+        })
+        .collect()
+}
+
+fn model_contributors(breakdowns: &[ModelBreakdown], total: Decimal) -> Vec<Contributor> {
+    contributors(
+        breakdowns
+            .iter()
+            .map(|b| (b.model_name.as_str().to_string(), b.cost)),
+        total,
+    )
+}
+
+/// Largest contributors first, capped at [`MAX_CONTRIBUTORS`]
+fn contributors(
+    costs: impl Iterator<Item = (String, Decimal)>,
+    total: Decimal,
+) -> Vec<Contributor> {
+    let mut contributors: Vec<Contributor> = costs
+        .map(|(name, cost)| Contributor {
+            name,
+            cost,
+            share: if total.is_zero() {
+                0.0
+            } else {
+                to_f64(cost / total * Decimal::ONE_HUNDRED)
+            },
+        })
+        .collect();
+    contributors.sort_by_key(|c| Reverse(c.cost));
+    contributors.truncate(MAX_CONTRIBUTORS);
+    contributors
+}
+
+fn median(values: &[f64]) -> f64 {
+    median_of(values.to_vec())
+}
+
+fn median_of(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.is_empty() {
+        0.0
+    } else if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+fn from_f64(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default().round_dp(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    const OPUS: &str = "claude-opus-4-1-20250805";
+    const SONNET: &str = "claude-sonnet-4-5-20250929";
+
+    fn options(method: AnomalyMethod) -> AnomalyOptions {
+        AnomalyOptions {
+            method,
+            threshold: method.default_threshold(),
+            history: 7,
+            min_cost: Decimal::ZERO,
+        }
+    }
+
+    fn entry(day: u32, hour: u32, session: &str, model: &str, cost: Decimal) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap(),
+            model: ModelName::new(model),
+            tokens: TokenCounts::default(),
+            cost,
+            session_id: Some(SessionId::new(session)),
+            request_id: None,
+            message_id: None,
+            project: Some("-home-dev-alpha".to_string()),
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    /// A week of one $9-$12 session a day, then a $100 day of a $75 Opus
+    /// session and a $25 Sonnet session
+    fn spike() -> Vec<LoadedUsageEntry> {
+        let mut entries: Vec<LoadedUsageEntry> = [10, 12, 11, 9, 10, 11, 12]
+            .into_iter()
+            .zip(1..)
+            .map(|(cost, day)| entry(day, 9, &format!("day-{}", day), SONNET, Decimal::from(cost)))
+            .collect();
+        entries.push(entry(8, 10, "big", OPUS, dec!(75)));
+        entries.push(entry(8, 11, "small", SONNET, dec!(25)));
+        entries
+    }
+
+    #[test]
+    fn mad_finds_the_spike_and_its_contributors() {
+        let options = AnomalyOptions {
+            // Keeps the $25 session out
+            min_cost: dec!(30),
+            ..options(AnomalyMethod::Mad)
+        };
+
+        let anomalies = detect_anomalies(&spike(), options);
+        let found: Vec<(AnomalyKind, &str)> =
+            anomalies.iter().map(|a| (a.kind, a.key.as_str())).collect();
+        assert_eq!(
+            found,
+            [
+                (AnomalyKind::Day, "2026-10-08"),
+                (AnomalyKind::Session, "big")
+            ]
+        );
+
+        // Median $11 with a median absolute deviation of $1
+        let day = &anomalies[0];
+        assert_eq!(day.cost, dec!(100));
+        assert_eq!(day.baseline, dec!(11));
+        assert!((day.score - MAD_SCALE * 89.0).abs() < 1e-9);
+
+        let sessions: Vec<(&str, Decimal, f64)> = day
+            .sessions
+            .iter()
+            .map(|c| (c.name.as_str(), c.cost, c.share))
+            .collect();
+        assert_eq!(
+            sessions,
+            [("big", dec!(75), 75.0), ("small", dec!(25), 25.0)]
+        );
+        let models: Vec<(&str, f64)> = day
+            .models
+            .iter()
+            .map(|c| (c.name.as_str(), c.share))
+            .collect();
+        assert_eq!(models, [(OPUS, 75.0), (SONNET, 25.0)]);
+
+        let session = &anomalies[1];
+        assert_eq!(session.project.as_deref(), Some("-home-dev-alpha"));
+        assert!(session.sessions.is_empty());
+        assert_eq!(session.models[0].name, OPUS);
+    }
+
+    #[test]
+    fn zscore_finds_the_spike_day() {
+        let days = aggregate_daily(spike(), SortOrder::Asc);
+
+        let anomalies = detect_anomalies_in(&days, Vec::new(), options(AnomalyMethod::Zscore));
+        assert_eq!(anomalies.len(), 1);
+
+        // Mean $75 / 7 and sample standard deviation sqrt(26 / 21)
+        let mean = 75.0 / 7.0;
+        let std_dev = (26.0f64 / 21.0).sqrt();
+        let day = &anomalies[0];
+        assert_eq!(day.key, "2026-10-08");
+        assert_eq!(day.baseline, dec!(10.71));
+        assert!((day.score - (100.0 - mean) / std_dev).abs() < 1e-9);
+        // Days carry no sessions without the entries
+        assert!(day.sessions.is_empty());
+    }
+
+    #[test]
+    fn values_need_min_history_before_scoring() {
+        let options = options(AnomalyMethod::Mad);
+        let history = [10.0, 12.0, 11.0, 9.0, 10.0];
+        assert_eq!(history.len(), MIN_HISTORY);
+
+        // A spike with too little before it is not scored
+        let mut early = history[..MIN_HISTORY - 1].to_vec();
+        early.push(100.0);
+        assert!(outliers(&early, options).is_empty());
+
+        let mut scored = history.to_vec();
+        scored.push(100.0);
+        let found = outliers(&scored, options);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].0, found[0].1), (MIN_HISTORY, 10.0));
+
+        // No spread in the history, nothing to compare against
+        assert!(outliers(&[5.0, 5.0, 5.0, 5.0, 5.0, 100.0], options).is_empty());
+    }
+}
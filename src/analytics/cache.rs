@@ -0,0 +1,259 @@
+use super::{sort_groups, GroupBy};
+use crate::pricing::{ModelPricing, PricingFetcher};
+use crate::types::*;
+use indexmap::IndexMap;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Prompt cache effectiveness for one group of entries
+///
+/// Savings are measured against an uncached baseline where every cache read and
+/// cache write had been billed as plain input tokens.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub input_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+
+    /// Cache reads as a share of all prompt tokens
+    pub hit_ratio: f64,
+
+    /// Cost with caching, at list prices
+    pub cost: Decimal,
+
+    /// Cost if no prompt caching had been used
+    pub uncached_cost: Decimal,
+
+    /// Extra paid for cache writes over plain input
+    pub write_premium: Decimal,
+
+    /// Saved on cache reads compared to plain input
+    pub read_savings: Decimal,
+
+    /// `read_savings - write_premium`, equal to `uncached_cost - cost`
+    pub net_savings: Decimal,
+
+    /// How many times read savings repaid the write premium
+    pub payback: Option<f64>,
+}
+
+impl CacheStats {
+    fn add(&mut self, tokens: &TokenCounts, pricing: Option<&ModelPricing>) {
+        self.input_tokens += tokens.input_tokens;
+        self.cache_creation_tokens += tokens.cache_creation_input_tokens;
+        self.cache_read_tokens += tokens.cache_read_input_tokens;
+
+        // Tokens for unknown models still count toward the hit ratio
+        let Some(pricing) = pricing else {
+            return;
+        };
+
+        let million = dec!(1_000_000);
+        let creation = Decimal::from(tokens.cache_creation_input_tokens) / million;
+        let read = Decimal::from(tokens.cache_read_input_tokens) / million;
+
+        let uncached = TokenCounts {
+            input_tokens: tokens.input_tokens
+                + tokens.cache_creation_input_tokens
+                + tokens.cache_read_input_tokens,
+            output_tokens: tokens.output_tokens,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+        };
+
+        self.cost += pricing.calculate_cost(tokens);
+        self.uncached_cost += pricing.calculate_cost(&uncached);
+        self.write_premium += creation * (pricing.cache_creation_price - pricing.input_price);
+        self.read_savings += read * (pricing.input_price - pricing.cache_read_price);
+    }
+
+    /// Fill in the ratios once all entries have been added
+    fn finish(mut self) -> Self {
+        let prompt_tokens = self.input_tokens + self.cache_creation_tokens + self.cache_read_tokens;
+        self.hit_ratio = if prompt_tokens > 0 {
+            self.cache_read_tokens as f64 / prompt_tokens as f64
+        } else {
+            0.0
+        };
+
+        self.net_savings = self.read_savings - self.write_premium;
+        self.payback = (!self.write_premium.is_zero())
+            .then(|| (self.read_savings / self.write_premium).to_f64())
+            .flatten();
+
+        self
+    }
+}
+
+/// Cache statistics for one day, session or project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+    pub group: String,
+
+    #[serde(flatten)]
+    pub stats: CacheStats,
+}
+
+/// Compute cache statistics per group
+///
+/// Costs are recomputed from token counts with `pricing_fetcher` so cached and
+/// uncached figures use the same prices. Days follow `order`; sessions and
+/// projects are sorted by uncached cost, largest first.
+pub fn analyze_cache(
+    entries: &[LoadedUsageEntry],
+    pricing_fetcher: &PricingFetcher,
+    group_by: GroupBy,
+    order: SortOrder,
+) -> Vec<CacheUsage> {
+    let mut groups: IndexMap<String, CacheStats> = IndexMap::new();
+    let mut prices: IndexMap<ModelName, Option<ModelPricing>> = IndexMap::new();
+
+    for entry in entries {
+        let group = group_by.key(entry);
+
+        let pricing = prices
+            .entry(entry.model.clone())
+            .or_insert_with(|| pricing_fetcher.pricing_for(&entry.model));
+
+        groups
+            .entry(group)
+            .or_default()
+            .add(&entry.tokens, pricing.as_ref());
+    }
+
+    let mut rows: Vec<CacheUsage> = groups
+        .into_iter()
+        .map(|(group, stats)| CacheUsage {
+            group,
+            stats: stats.finish(),
+        })
+        .collect();
+
+    sort_groups(
+        &mut rows,
+        group_by,
+        order,
+        |r| &r.group,
+        |r| r.stats.uncached_cost,
+    );
+
+    rows
+}
+
+/// Combined statistics across all rows
+pub fn cache_totals(rows: &[CacheUsage]) -> CacheStats {
+    rows.iter()
+        .fold(CacheStats::default(), |mut total, row| {
+            total.input_tokens += row.stats.input_tokens;
+            total.cache_creation_tokens += row.stats.cache_creation_tokens;
+            total.cache_read_tokens += row.stats.cache_read_tokens;
+            total.cost += row.stats.cost;
+            total.uncached_cost += row.stats.uncached_cost;
+            total.write_premium += row.stats.write_premium;
+            total.read_savings += row.stats.read_savings;
+            total
+        })
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+
+    /// Pricing for a made-up model at $10 input, $50 output, $12.50 cache
+    /// write and $1 cache read per million tokens
+    fn pricing_fetcher() -> PricingFetcher {
+        let pricing = ModelPricing {
+            input_price: dec!(10),
+            output_price: dec!(50),
+            cache_creation_price: dec!(12.5),
+            cache_read_price: dec!(1),
+        };
+        PricingFetcher::new(true)
+            .with_custom_pricing(HashMap::from([("test-model".to_string(), pricing)]))
+    }
+
+    /// An entry with the given input, cache write and cache read tokens
+    fn entry(model: &str, project: &str, input: u64, creation: u64, read: u64) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap(),
+            model: ModelName::new(model),
+            tokens: TokenCounts {
+                input_tokens: input,
+                output_tokens: 0,
+                cache_creation_input_tokens: creation,
+                cache_read_input_tokens: read,
+            },
+            cost: Decimal::ZERO,
+            session_id: None,
+            request_id: None,
+            message_id: None,
+            project: Some(project.to_string()),
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    #[test]
+    fn cache_savings_and_payback_from_known_prices() {
+        let entries = [
+            entry("test-model", "alpha", 1_000_000, 2_000_000, 7_000_000),
+            entry("test-model", "beta", 1_000_000, 0, 0),
+            // Unpriced: counts toward the hit ratio only
+            entry("mystery-model", "beta", 0, 1_000_000, 1_000_000),
+        ];
+
+        let rows = analyze_cache(
+            &entries,
+            &pricing_fetcher(),
+            GroupBy::Project,
+            SortOrder::Desc,
+        );
+        let groups: Vec<&str> = rows.iter().map(|r| r.group.as_str()).collect();
+        assert_eq!(groups, ["alpha", "beta"]);
+
+        // $10 input + $25 writes + $7 reads, against 10M input tokens at $100
+        let alpha = &rows[0].stats;
+        assert_eq!(alpha.hit_ratio, 0.7);
+        assert_eq!(alpha.cost, dec!(42));
+        assert_eq!(alpha.uncached_cost, dec!(100));
+        assert_eq!(alpha.write_premium, dec!(5));
+        assert_eq!(alpha.read_savings, dec!(63));
+        assert_eq!(alpha.net_savings, alpha.uncached_cost - alpha.cost);
+        assert_eq!(alpha.payback, Some(12.6));
+
+        // No cache writes, so nothing to pay back
+        let beta = &rows[1].stats;
+        assert_eq!(beta.hit_ratio, 1.0 / 3.0);
+        assert_eq!(beta.cost, dec!(10));
+        assert_eq!(beta.net_savings, Decimal::ZERO);
+        assert_eq!(beta.payback, None);
+
+        let totals = cache_totals(&rows);
+        assert_eq!(totals.input_tokens, 2_000_000);
+        assert_eq!(totals.cache_creation_tokens, 3_000_000);
+        assert_eq!(totals.cache_read_tokens, 8_000_000);
+        assert_eq!(totals.hit_ratio, 8.0 / 13.0);
+        assert_eq!(totals.cost, dec!(52));
+        assert_eq!(totals.uncached_cost, dec!(110));
+        assert_eq!(totals.net_savings, dec!(58));
+        assert_eq!(totals.payback, Some(12.6));
+    }
+
+    #[test]
+    fn cache_totals_without_rows_are_zero() {
+        let totals = cache_totals(&[]);
+        assert_eq!(totals.hit_ratio, 0.0);
+        assert_eq!(totals.cost, Decimal::ZERO);
+        assert_eq!(totals.payback, None);
+    }
+}
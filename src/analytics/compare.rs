@@ -0,0 +1,188 @@
+use crate::aggregation::{group_entries, UsageAccumulator};
+use crate::types::*;
+use chrono::{Duration, NaiveDate};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Inclusive range of days
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DateRange {
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+}
+
+impl DateRange {
+    pub fn new(since: NaiveDate, until: NaiveDate) -> Self {
+        Self { since, until }
+    }
+
+    pub fn days(&self) -> i64 {
+        (self.until - self.since).num_days() + 1
+    }
+
+    /// Range of the same length ending the day before this one starts
+    pub fn preceding(&self) -> Self {
+        let until = self.since - Duration::days(1);
+        Self {
+            since: until - Duration::days(self.days() - 1),
+            until,
+        }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.since <= date && date <= self.until
+    }
+}
+
+/// Change in cost and tokens for one model or project, or the total
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonRow {
+    pub group: String,
+    pub previous_cost: Decimal,
+    pub current_cost: Decimal,
+    pub cost_change: Decimal,
+
+    /// `None` when the previous period had no cost
+    pub cost_change_percent: Option<f64>,
+
+    pub previous_tokens: u64,
+    pub current_tokens: u64,
+    pub token_change: i64,
+
+    /// `None` when the previous period had no tokens
+    pub token_change_percent: Option<f64>,
+}
+
+impl ComparisonRow {
+    fn new(
+        group: String,
+        previous: Option<&UsageAccumulator>,
+        current: Option<&UsageAccumulator>,
+    ) -> Self {
+        let cost = |usage: Option<&UsageAccumulator>| usage.map_or(Decimal::ZERO, |u| u.cost());
+        let tokens = |usage: Option<&UsageAccumulator>| {
+            usage.map_or(0, |u| {
+                let t = u.tokens();
+                t.input_tokens + t.output_tokens + t.cache_creation_tokens + t.cache_read_tokens
+            })
+        };
+
+        let previous_cost = cost(previous);
+        let current_cost = cost(current);
+        let previous_tokens = tokens(previous);
+        let current_tokens = tokens(current);
+
+        let cost_change = current_cost - previous_cost;
+        let token_change = current_tokens as i64 - previous_tokens as i64;
+
+        Self {
+            group,
+            previous_cost,
+            current_cost,
+            cost_change,
+            cost_change_percent: (!previous_cost.is_zero())
+                .then(|| (cost_change / previous_cost * Decimal::ONE_HUNDRED).to_f64())
+                .flatten(),
+            previous_tokens,
+            current_tokens,
+            token_change,
+            token_change_percent: (previous_tokens > 0)
+                .then(|| token_change as f64 / previous_tokens as f64 * 100.0),
+        }
+    }
+}
+
+/// A date range compared against the preceding range of equal length
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comparison {
+    pub current: DateRange,
+    pub previous: DateRange,
+    pub totals: ComparisonRow,
+
+    /// Per model, largest cost change first
+    pub models: Vec<ComparisonRow>,
+
+    /// Per project, largest cost change first
+    pub projects: Vec<ComparisonRow>,
+}
+
+/// Compare usage in `current` with the range of equal length before it
+///
+/// Entries outside both ranges are ignored.
+pub fn compare_periods(entries: &[LoadedUsageEntry], current: DateRange) -> Comparison {
+    let previous = current.preceding();
+
+    let (current_entries, previous_entries): (Vec<_>, Vec<_>) = entries
+        .iter()
+        .filter(|e| {
+            let date = e.timestamp.date_naive();
+            current.contains(date) || previous.contains(date)
+        })
+        .cloned()
+        .partition(|e| current.contains(e.timestamp.date_naive()));
+
+    let by_model = |e: &LoadedUsageEntry| e.model.as_str().to_string();
+    let by_project =
+        |e: &LoadedUsageEntry| e.project.clone().unwrap_or_else(|| "unknown".to_string());
+
+    let total = |_: &LoadedUsageEntry| "TOTAL".to_string();
+
+    Comparison {
+        current,
+        previous,
+        totals: compare_groups(&previous_entries, &current_entries, total)
+            .pop()
+            .unwrap_or_else(|| ComparisonRow::new("TOTAL".to_string(), None, None)),
+        models: compare_groups(&previous_entries, &current_entries, by_model),
+        projects: compare_groups(&previous_entries, &current_entries, by_project),
+    }
+}
+
+/// One row per group seen in either period, largest absolute cost change first
+fn compare_groups(
+    previous: &[LoadedUsageEntry],
+    current: &[LoadedUsageEntry],
+    key: impl Fn(&LoadedUsageEntry) -> String + Copy,
+) -> Vec<ComparisonRow> {
+    let previous: IndexMap<String, UsageAccumulator> = group_entries(previous, key);
+    let current: IndexMap<String, UsageAccumulator> = group_entries(current, key);
+
+    let mut rows: Vec<ComparisonRow> = previous
+        .keys()
+        .chain(current.keys())
+        .unique()
+        .map(|group| ComparisonRow::new(group.clone(), previous.get(group), current.get(group)))
+        .collect();
+
+    rows.sort_by_key(|r| Reverse(r.cost_change.abs()));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    #[test]
+    fn comparison_without_usage_has_empty_rows_and_zero_totals() {
+        let current = DateRange::new(
+            NaiveDate::from_ymd_opt(2026, 9, 25).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+        );
+
+        let comparison = serde_json::to_value(compare_periods(&[], current)).unwrap();
+        assert_eq!(comparison["previous"]["since"], "2026-09-18");
+        assert_eq!(comparison["models"], json!([]));
+        assert_eq!(comparison["projects"], json!([]));
+        assert_eq!(comparison["totals"]["group"], "TOTAL");
+        assert_eq!(comparison["totals"]["currentCost"], "0");
+        assert_eq!(comparison["totals"]["previousTokens"], 0);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::types::DailyUsage;
+use chrono::{Datelike, Duration, NaiveDate};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// z-score for a two-sided 95% confidence band
+const Z_95: f64 = 1.96;
+
+/// How daily spend is extrapolated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ForecastMethod {
+    /// Mean daily cost over the window
+    #[value(name = "average")]
+    #[serde(rename = "average")]
+    MovingAverage,
+    /// Least-squares trend line over the window
+    #[default]
+    Linear,
+    /// Mean cost per weekday over the window
+    Weekday,
+}
+
+/// Daily cost model fitted over the history window
+struct Model {
+    method: ForecastMethod,
+    /// First day of the window, day 0 of the trend line
+    start: NaiveDate,
+    mean: f64,
+    slope: f64,
+    intercept: f64,
+    weekday_means: HashMap<u32, f64>,
+    /// Standard deviation of daily cost around the fitted values
+    residual_std_dev: f64,
+}
+
+impl Model {
+    fn fit(method: ForecastMethod, start: NaiveDate, history: &[f64]) -> Self {
+        let n = history.len() as f64;
+        let mean = history.iter().sum::<f64>() / n.max(1.0);
+
+        let (slope, intercept) = if history.len() > 1 {
+            let x_mean = (n - 1.0) / 2.0;
+            let (covariance, variance) =
+                history
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(covariance, variance), (x, y)| {
+                        let dx = x as f64 - x_mean;
+                        (covariance + dx * (y - mean), variance + dx * dx)
+                    });
+            let slope = covariance / variance;
+            (slope, mean - slope * x_mean)
+        } else {
+            (0.0, mean)
+        };
+
+        let mut weekday_totals: HashMap<u32, (f64, f64)> = HashMap::new();
+        for (i, cost) in history.iter().enumerate() {
+            let weekday = (start + Duration::days(i as i64))
+                .weekday()
+                .num_days_from_monday();
+            let total = weekday_totals.entry(weekday).or_default();
+            total.0 += cost;
+            total.1 += 1.0;
+        }
+        let weekday_means = weekday_totals
+            .into_iter()
+            .map(|(weekday, (sum, count))| (weekday, sum / count))
+            .collect();
+
+        let mut model = Self {
+            method,
+            start,
+            mean,
+            slope,
+            intercept,
+            weekday_means,
+            residual_std_dev: 0.0,
+        };
+
+        if history.len() > 1 {
+            let squared: f64 = history
+                .iter()
+                .enumerate()
+                .map(|(i, cost)| (cost - model.predict(start + Duration::days(i as i64))).powi(2))
+                .sum();
+            model.residual_std_dev = (squared / (n - 1.0)).sqrt();
+        }
+
+        model
+    }
+
+    /// Expected cost on `date`, never negative
+    fn predict(&self, date: NaiveDate) -> f64 {
+        let value = match self.method {
+            ForecastMethod::MovingAverage => self.mean,
+            ForecastMethod::Linear => {
+                self.intercept + self.slope * (date - self.start).num_days() as f64
+            }
+            ForecastMethod::Weekday => self
+                .weekday_means
+                .get(&date.weekday().num_days_from_monday())
+                .copied()
+                .unwrap_or(self.mean),
+        };
+        value.max(0.0)
+    }
+}
+
+/// Kind of period projected to its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForecastPeriod {
+    Month,
+    Quarter,
+}
+
+impl ForecastPeriod {
+    /// First and last day of the period containing `date`
+    pub fn bounds(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let first_month = match self {
+            Self::Month => date.month(),
+            Self::Quarter => (date.month() - 1) / 3 * 3 + 1,
+        };
+        let months = match self {
+            Self::Month => 1,
+            Self::Quarter => 3,
+        };
+
+        let start = NaiveDate::from_ymd_opt(date.year(), first_month, 1).unwrap_or(date);
+        let end = start
+            .checked_add_months(chrono::Months::new(months))
+            .map_or(date, |next| next - Duration::days(1));
+        (start, end)
+    }
+
+    /// Display label such as "2026-10" or "2026-Q4"
+    pub fn label(self, date: NaiveDate) -> String {
+        match self {
+            Self::Month => date.format("%Y-%m").to_string(),
+            Self::Quarter => format!("{}-Q{}", date.year(), (date.month() - 1) / 3 + 1),
+        }
+    }
+}
+
+/// Projected spend at the end of a month or quarter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodForecast {
+    pub period: ForecastPeriod,
+    pub label: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+
+    /// Cost so far, through the forecast date
+    pub spent: Decimal,
+
+    /// Days left after the forecast date
+    pub remaining_days: i64,
+
+    pub projected: Decimal,
+
+    /// Lower and upper bounds of the 95% confidence band
+    pub low: Decimal,
+    pub high: Decimal,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<Decimal>,
+
+    /// Whether the projection exceeds the budget
+    pub over_budget: bool,
+}
+
+/// Month-end and quarter-end spend projected from recent daily cost
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Forecast {
+    pub method: ForecastMethod,
+
+    /// Date the forecast is made on; it counts as spent so far
+    pub as_of: NaiveDate,
+
+    /// Completed days the model was fitted over
+    pub history_days: i64,
+
+    /// Expected cost of the day after `as_of`
+    pub next_day: Decimal,
+
+    /// Standard deviation of daily cost around the fitted values
+    pub daily_std_dev: Decimal,
+
+    pub periods: Vec<PeriodForecast>,
+}
+
+/// Project month-end and quarter-end spend from `daily` usage
+///
+/// The model is fitted over the `window` completed days before `as_of`, with
+/// missing days counted as zero. `as_of` itself is usually partial, so it counts
+/// as the larger of its cost so far and its predicted cost. The confidence band
+/// treats daily deviations from the model as independent.
+pub fn forecast(
+    daily: &[DailyUsage],
+    as_of: NaiveDate,
+    method: ForecastMethod,
+    window: i64,
+    budgets: &HashMap<ForecastPeriod, Decimal>,
+) -> Forecast {
+    let costs: HashMap<NaiveDate, Decimal> =
+        daily.iter().map(|d| (d.date.0, d.total_cost)).collect();
+    let cost_on = |date: NaiveDate| costs.get(&date).copied().unwrap_or(Decimal::ZERO);
+
+    // Start the window at the first day with usage when history is shorter
+    let window_start = as_of - Duration::days(window);
+    let start = costs
+        .keys()
+        .filter(|&&date| date >= window_start && date < as_of)
+        .min()
+        .copied()
+        .unwrap_or(window_start);
+    let history: Vec<f64> = start
+        .iter_days()
+        .take_while(|&date| date < as_of)
+        .map(|date| cost_on(date).to_f64().unwrap_or(0.0))
+        .collect();
+
+    let model = Model::fit(method, start, &history);
+
+    let today_cost = cost_on(as_of);
+    let today_predicted = Decimal::from_f64(model.predict(as_of)).unwrap_or_default();
+    let today_extra = (today_predicted - today_cost).max(Decimal::ZERO);
+
+    let periods = [ForecastPeriod::Month, ForecastPeriod::Quarter]
+        .into_iter()
+        .map(|period| {
+            let (period_start, period_end) = period.bounds(as_of);
+            let spent: Decimal = period_start
+                .iter_days()
+                .take_while(|&date| date <= as_of)
+                .map(cost_on)
+                .sum();
+
+            let remaining_days = (period_end - as_of).num_days();
+            let remaining: f64 = (1..=remaining_days)
+                .map(|day| model.predict(as_of + Duration::days(day)))
+                .sum();
+            let margin = Z_95 * model.residual_std_dev * (remaining_days as f64).sqrt();
+
+            let to_cost = |value: f64| Decimal::from_f64(value).unwrap_or_default();
+            let projected = spent + today_extra + to_cost(remaining);
+            let low = (projected - to_cost(margin)).max(spent);
+            let high = projected + to_cost(margin);
+
+            let budget = budgets.get(&period).copied();
+
+            PeriodForecast {
+                period,
+                label: period.label(as_of),
+                start: period_start,
+                end: period_end,
+                spent,
+                remaining_days,
+                projected: projected.round_dp(2),
+                low: low.round_dp(2),
+                high: high.round_dp(2),
+                budget,
+                over_budget: budget.is_some_and(|b| projected > b),
+            }
+        })
+        .collect();
+
+    Forecast {
+        method,
+        as_of,
+        history_days: history.len() as i64,
+        next_day: Decimal::from_f64(model.predict(as_of + Duration::days(1)))
+            .unwrap_or_default()
+            .round_dp(2),
+        daily_std_dev: Decimal::from_f64(model.residual_std_dev)
+            .unwrap_or_default()
+            .round_dp(2),
+        periods,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DailyDate, RequestStats};
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+    }
+
+    /// Daily rows with the given cost per date
+    fn daily(costs: &[(NaiveDate, i64)]) -> Vec<DailyUsage> {
+        costs
+            .iter()
+            .map(|&(date, cost)| DailyUsage {
+                date: DailyDate(date),
+                input_tokens: 0,
+                output_tokens: 0,
+                cache_creation_tokens: 0,
+                cache_read_tokens: 0,
+                total_cost: Decimal::from(cost),
+                models_used: Vec::new(),
+                model_breakdowns: Vec::new(),
+                requests: RequestStats::default(),
+                project: None,
+            })
+            .collect()
+    }
+
+    fn month(result: &Forecast) -> &PeriodForecast {
+        &result.periods[0]
+    }
+
+    #[test]
+    fn linear_forecast_extends_the_trend() {
+        // $1, $2, $3, $4 over the four days before Saturday 2026-10-10
+        let usage = daily(&[
+            (date(10, 6), 1),
+            (date(10, 7), 2),
+            (date(10, 8), 3),
+            (date(10, 9), 4),
+        ]);
+
+        let result = forecast(
+            &usage,
+            date(10, 10),
+            ForecastMethod::Linear,
+            4,
+            &HashMap::new(),
+        );
+
+        assert_eq!(result.history_days, 4);
+        assert_eq!(result.next_day, Decimal::from(6));
+        assert_eq!(result.daily_std_dev, Decimal::ZERO);
+
+        // $10 spent, $5 predicted for today, then $6 + $7 + ... + $26
+        let month = month(&result);
+        assert_eq!(month.spent, Decimal::from(10));
+        assert_eq!(month.remaining_days, 21);
+        assert_eq!(month.projected, Decimal::from(351));
+        assert_eq!(month.low, month.projected);
+        assert_eq!(month.high, month.projected);
+    }
+
+    #[test]
+    fn average_forecast_has_a_95_percent_band() {
+        let usage = daily(&[
+            (date(10, 6), 1),
+            (date(10, 7), 2),
+            (date(10, 8), 3),
+            (date(10, 9), 4),
+        ]);
+        let budgets = HashMap::from([(ForecastPeriod::Month, Decimal::from(70))]);
+
+        let result = forecast(
+            &usage,
+            date(10, 10),
+            ForecastMethod::MovingAverage,
+            4,
+            &budgets,
+        );
+
+        assert_eq!(result.next_day, Decimal::new(25, 1));
+        // Sample standard deviation of 1..4 around 2.5: sqrt(5 / 3)
+        assert_eq!(result.daily_std_dev, Decimal::new(129, 2));
+
+        // $10 spent, $2.50 for today and 21 days at $2.50, give or take
+        // 1.96 * sqrt(5 / 3) * sqrt(21) = $11.60
+        let month = month(&result);
+        assert_eq!(month.projected, Decimal::from(65));
+        assert_eq!(month.low, Decimal::new(5340, 2));
+        assert_eq!(month.high, Decimal::new(7660, 2));
+        // Only the top of the band is over budget
+        assert!(!month.over_budget);
+        assert_eq!(month.budget, Some(Decimal::from(70)));
+    }
+
+    #[test]
+    fn weekday_forecast_repeats_the_weekly_rhythm() {
+        // Two weeks from Sunday 2026-09-27 at $1 a day and $10 on Mondays
+        let usage: Vec<(NaiveDate, i64)> = date(9, 27)
+            .iter_days()
+            .take(14)
+            .map(|day| {
+                let cost = if day.weekday() == chrono::Weekday::Mon {
+                    10
+                } else {
+                    1
+                };
+                (day, cost)
+            })
+            .collect();
+
+        let result = forecast(
+            &daily(&usage),
+            date(10, 11),
+            ForecastMethod::Weekday,
+            14,
+            &HashMap::new(),
+        );
+
+        // The day after Sunday 2026-10-11 is a Monday
+        assert_eq!(result.next_day, Decimal::from(10));
+        assert_eq!(result.daily_std_dev, Decimal::ZERO);
+
+        // $19 spent, $1 for today, then three Mondays and 17 other days
+        let month = month(&result);
+        assert_eq!(month.spent, Decimal::from(19));
+        assert_eq!(month.projected, Decimal::from(67));
+    }
+
+    #[test]
+    fn forecast_without_usage_projects_zero_spend() {
+        let as_of = NaiveDate::from_ymd_opt(2026, 10, 10).unwrap();
+        let budgets = HashMap::from([(ForecastPeriod::Month, Decimal::new(100, 0))]);
+
+        let result = forecast(&[], as_of, ForecastMethod::Linear, 28, &budgets);
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(json["asOf"], "2026-10-10");
+        assert_eq!(json["nextDay"], "0");
+        assert_eq!(json["periods"].as_array().unwrap().len(), 2);
+        assert_eq!(json["periods"][0]["label"], "2026-10");
+        assert_eq!(json["periods"][0]["projected"], "0");
+        assert_eq!(json["periods"][0]["budget"], "100");
+        assert_eq!(json["periods"][0]["overBudget"], false);
+        assert_eq!(json["periods"][1]["spent"], "0");
+    }
+}
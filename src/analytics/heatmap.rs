@@ -0,0 +1,199 @@
+use crate::types::LoadedUsageEntry;
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use chrono_tz::Tz;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Weekday labels in row order
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Value shown in the heatmap cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HeatmapMetric {
+    #[default]
+    Cost,
+    Tokens,
+    Requests,
+}
+
+/// Usage summed by weekday and hour of day
+///
+/// Each matrix has one row per weekday, Monday first, and one column per hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Heatmap {
+    /// IANA name of the time zone the entries were bucketed in, or "local"
+    pub timezone: String,
+    pub weekdays: Vec<String>,
+    pub cost: Vec<Vec<Decimal>>,
+    pub tokens: Vec<Vec<u64>>,
+    pub requests: Vec<Vec<u64>>,
+}
+
+impl Heatmap {
+    fn new(timezone: String) -> Self {
+        Self {
+            timezone,
+            weekdays: WEEKDAYS.iter().map(|d| d.to_string()).collect(),
+            cost: vec![vec![Decimal::ZERO; 24]; 7],
+            tokens: vec![vec![0; 24]; 7],
+            requests: vec![vec![0; 24]; 7],
+        }
+    }
+
+    fn add(&mut self, weekday: usize, hour: usize, entry: &LoadedUsageEntry) {
+        self.cost[weekday][hour] += entry.cost;
+        self.tokens[weekday][hour] += entry.tokens.total();
+        self.requests[weekday][hour] += 1;
+    }
+
+    /// Cell values for `metric` as floats, for scaling
+    pub fn values(&self, metric: HeatmapMetric) -> Vec<Vec<f64>> {
+        match metric {
+            HeatmapMetric::Cost => self
+                .cost
+                .iter()
+                .map(|row| row.iter().map(|c| c.to_f64().unwrap_or(0.0)).collect())
+                .collect(),
+            HeatmapMetric::Tokens => self
+                .tokens
+                .iter()
+                .map(|row| row.iter().map(|&t| t as f64).collect())
+                .collect(),
+            HeatmapMetric::Requests => self
+                .requests
+                .iter()
+                .map(|row| row.iter().map(|&r| r as f64).collect())
+                .collect(),
+        }
+    }
+
+    /// Busiest weekday and hour for `metric`, if there is any usage
+    pub fn peak(&self, metric: HeatmapMetric) -> Option<(usize, usize)> {
+        let values = self.values(metric);
+        (0..7)
+            .flat_map(|d| (0..24).map(move |h| (d, h)))
+            .filter(|&(d, h)| values[d][h] > 0.0)
+            .max_by(|&(d1, h1), &(d2, h2)| values[d1][h1].total_cmp(&values[d2][h2]))
+    }
+
+    /// Hours of the day ordered from least to most total usage across the week
+    pub fn quietest_hours(&self, metric: HeatmapMetric) -> Vec<usize> {
+        let values = self.values(metric);
+        let mut hours: Vec<usize> = (0..24).collect();
+        hours.sort_by(|&a, &b| {
+            let total = |h: usize| values.iter().map(|row| row[h]).sum::<f64>();
+            total(a).total_cmp(&total(b))
+        });
+        hours
+    }
+}
+
+/// Bucket entries by weekday and hour in `timezone`, or the local time zone (`TZ`)
+pub fn usage_heatmap(entries: &[LoadedUsageEntry], timezone: Option<Tz>) -> Heatmap {
+    let label = match timezone {
+        Some(tz) => tz.name().to_string(),
+        None => std::env::var("TZ").unwrap_or_else(|_| "local".to_string()),
+    };
+
+    let mut heatmap = Heatmap::new(label);
+
+    for entry in entries {
+        let (weekday, hour) = weekday_hour(entry.timestamp, timezone);
+        heatmap.add(weekday, hour, entry);
+    }
+
+    heatmap
+}
+
+/// Weekday index (Monday = 0) and hour of `timestamp` in the given zone
+fn weekday_hour(timestamp: DateTime<Utc>, timezone: Option<Tz>) -> (usize, usize) {
+    match timezone {
+        Some(tz) => {
+            let t = timestamp.with_timezone(&tz);
+            (
+                t.weekday().num_days_from_monday() as usize,
+                t.hour() as usize,
+            )
+        }
+        None => {
+            let t = timestamp.with_timezone(&Local);
+            (
+                t.weekday().num_days_from_monday() as usize,
+                t.hour() as usize,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ModelName, TokenCounts};
+    use chrono::TimeZone;
+
+    fn entry(timestamp: DateTime<Utc>) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp,
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: 100,
+                ..TokenCounts::default()
+            },
+            cost: Decimal::ONE,
+            session_id: None,
+            request_id: None,
+            message_id: None,
+            project: None,
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    /// (weekday, hour) of every non-empty cell
+    fn cells(heatmap: &Heatmap) -> Vec<(usize, usize)> {
+        (0..7)
+            .flat_map(|d| (0..24).map(move |h| (d, h)))
+            .filter(|&(d, h)| heatmap.requests[d][h] > 0)
+            .collect()
+    }
+
+    #[test]
+    fn buckets_by_the_zone_weekday_and_hour() {
+        // Monday 2026-10-19 02:30 UTC is still Sunday evening in New York
+        let entries = [entry(Utc.with_ymd_and_hms(2026, 10, 19, 2, 30, 0).unwrap())];
+
+        let utc = usage_heatmap(&entries, Some(Tz::UTC));
+        assert_eq!(utc.timezone, "UTC");
+        assert_eq!(cells(&utc), [(0, 2)]);
+
+        let new_york = usage_heatmap(&entries, Some(Tz::America__New_York));
+        assert_eq!(new_york.timezone, "America/New_York");
+        assert_eq!(cells(&new_york), [(6, 22)]);
+        assert_eq!(new_york.cost[6][22], Decimal::ONE);
+        assert_eq!(new_york.tokens[6][22], 100);
+    }
+
+    #[test]
+    fn buckets_across_a_daylight_saving_change() {
+        // Berlin leaves summer time at 03:00 on Sunday 2026-10-25; the same
+        // UTC hour a day apart lands an hour earlier after the change
+        let entries = [
+            entry(Utc.with_ymd_and_hms(2026, 10, 24, 12, 0, 0).unwrap()),
+            entry(Utc.with_ymd_and_hms(2026, 10, 25, 12, 0, 0).unwrap()),
+            // 00:30 and 01:30 UTC are both 02:xx local, either side of the change
+            entry(Utc.with_ymd_and_hms(2026, 10, 25, 0, 30, 0).unwrap()),
+            entry(Utc.with_ymd_and_hms(2026, 10, 25, 1, 30, 0).unwrap()),
+        ];
+
+        let heatmap = usage_heatmap(&entries, Some(Tz::Europe__Berlin));
+        assert_eq!(cells(&heatmap), [(5, 14), (6, 2), (6, 13)]);
+        assert_eq!(heatmap.requests[6][2], 2);
+        assert_eq!(heatmap.peak(HeatmapMetric::Requests), Some((6, 2)));
+    }
+}
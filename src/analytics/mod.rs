@@ -0,0 +1,71 @@
+//! Derived analyses over loaded usage entries.
+
+pub mod anomalies;
+pub mod cache;
+pub mod compare;
+pub mod forecast;
+pub mod heatmap;
+pub mod plans;
+pub mod simulate;
+pub mod team;
+pub mod timeline;
+
+use crate::types::{DailyDate, LoadedUsageEntry, SortOrder};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// How entries are grouped in analysis reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    #[default]
+    Day,
+    Session,
+    Project,
+}
+
+impl GroupBy {
+    /// Column header for the group
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Day => "Date",
+            Self::Session => "Session",
+            Self::Project => "Project",
+        }
+    }
+
+    /// Group key for an entry
+    pub fn key(&self, entry: &LoadedUsageEntry) -> String {
+        match self {
+            Self::Day => DailyDate::from_datetime(entry.timestamp).to_string(),
+            Self::Session => entry
+                .session_id
+                .as_ref()
+                .map_or_else(|| "unknown".to_string(), |s| s.0.clone()),
+            Self::Project => entry
+                .project
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+        }
+    }
+}
+
+/// Sort report rows: days chronologically in `order`, other groups by `weight`, largest first
+fn sort_groups<T>(
+    rows: &mut [T],
+    group_by: GroupBy,
+    order: SortOrder,
+    group: impl Fn(&T) -> &str,
+    weight: impl Fn(&T) -> Decimal,
+) {
+    match group_by {
+        GroupBy::Day => {
+            rows.sort_by(|a, b| group(a).cmp(group(b)));
+            if order == SortOrder::Desc {
+                rows.reverse();
+            }
+        }
+        GroupBy::Session | GroupBy::Project => rows.sort_by_key(|r| Reverse(weight(r))),
+    }
+}
@@ -0,0 +1,219 @@
+use crate::types::{DailyUsage, MonthlyDate, MonthlyUsage};
+use chrono::Datelike;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Average days per month, used to prorate monthly plan prices
+const DAYS_PER_MONTH: Decimal = dec!(30.4375);
+
+/// A flat-rate subscription plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionPlan {
+    pub name: String,
+
+    /// Price per month in USD
+    pub monthly_cost: Decimal,
+}
+
+impl SubscriptionPlan {
+    pub fn new(name: impl Into<String>, monthly_cost: Decimal) -> Self {
+        Self {
+            name: name.into(),
+            monthly_cost,
+        }
+    }
+
+    /// Plan price for a period of `days` days
+    pub fn cost_for_days(&self, days: i64) -> Decimal {
+        self.monthly_cost * Decimal::from(days) / DAYS_PER_MONTH
+    }
+}
+
+/// Claude subscription plans at list price
+pub fn default_plans() -> Vec<SubscriptionPlan> {
+    vec![
+        SubscriptionPlan::new("Pro", dec!(20)),
+        SubscriptionPlan::new("Max 5x", dec!(100)),
+        SubscriptionPlan::new("Max 20x", dec!(200)),
+    ]
+}
+
+/// Normalized plan name for matching, so "Max 5x", "max5x" and "MAX-5X" are equal
+fn plan_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Default plans with configured plans applied
+///
+/// A configured plan with the same name as a default one replaces its price;
+/// other configured plans are added after the defaults.
+pub fn resolve_plans(configured: &[SubscriptionPlan]) -> Vec<SubscriptionPlan> {
+    let mut plans = default_plans();
+
+    for plan in configured {
+        match plans
+            .iter_mut()
+            .find(|p| plan_key(&p.name) == plan_key(&plan.name))
+        {
+            Some(existing) => existing.monthly_cost = plan.monthly_cost,
+            None => plans.push(plan.clone()),
+        }
+    }
+
+    plans
+}
+
+/// Find a plan by name, ignoring case, spaces and punctuation
+pub fn find_plan<'a>(plans: &'a [SubscriptionPlan], name: &str) -> Option<&'a SubscriptionPlan> {
+    plans.iter().find(|p| plan_key(&p.name) == plan_key(name))
+}
+
+/// Value of a plan for one month of usage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanValue {
+    pub month: MonthlyDate,
+    pub plan: String,
+    pub monthly_cost: Decimal,
+
+    /// Pay-as-you-go cost of the month's usage
+    pub api_cost: Decimal,
+
+    /// `api_cost - monthly_cost`
+    pub net_value: Decimal,
+
+    /// `api_cost / monthly_cost`
+    pub roi: Option<f64>,
+
+    /// Day of the month when API-equivalent spend reached the plan price
+    pub break_even_day: Option<u32>,
+}
+
+/// Compare each plan against the API-equivalent cost of every month
+///
+/// `daily` supplies the running spend within each month for the break-even day.
+pub fn plan_values(
+    monthly: &[MonthlyUsage],
+    daily: &[DailyUsage],
+    plans: &[SubscriptionPlan],
+) -> Vec<PlanValue> {
+    let mut values = Vec::with_capacity(monthly.len() * plans.len());
+
+    for month in monthly {
+        let mut days: Vec<&DailyUsage> = daily
+            .iter()
+            .filter(|d| MonthlyDate::from_date(d.date.0) == month.date)
+            .collect();
+        days.sort_by_key(|d| d.date.0);
+
+        for plan in plans {
+            let break_even_day = days
+                .iter()
+                .scan(Decimal::ZERO, |spent, day| {
+                    *spent += day.total_cost;
+                    Some((day.date.0.day(), *spent))
+                })
+                .find(|(_, spent)| *spent >= plan.monthly_cost)
+                .map(|(day, _)| day);
+
+            values.push(PlanValue {
+                month: month.date.clone(),
+                plan: plan.name.clone(),
+                monthly_cost: plan.monthly_cost,
+                api_cost: month.total_cost,
+                net_value: month.total_cost - plan.monthly_cost,
+                roi: (!plan.monthly_cost.is_zero())
+                    .then(|| (month.total_cost / plan.monthly_cost).to_f64())
+                    .flatten(),
+                break_even_day,
+            });
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregation::{aggregate_daily, aggregate_monthly};
+    use crate::types::{LoadedUsageEntry, ModelName, SortOrder, TokenCounts};
+    use chrono::{TimeZone, Utc};
+
+    fn entry(month: u32, day: u32, cost: Decimal) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, month, day, 12, 0, 0).unwrap(),
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts::default(),
+            cost,
+            session_id: None,
+            request_id: None,
+            message_id: None,
+            project: None,
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    #[test]
+    fn break_even_day_when_spend_reaches_the_plan_price() {
+        // $8 a week in October, $30 on one day in September
+        let entries = vec![
+            entry(9, 20, dec!(30)),
+            entry(10, 3, dec!(8)),
+            entry(10, 10, dec!(8)),
+            entry(10, 17, dec!(8)),
+        ];
+        let daily = aggregate_daily(entries.clone(), SortOrder::Asc);
+        let monthly = aggregate_monthly(entries, SortOrder::Asc);
+        let plans = [
+            SubscriptionPlan::new("Pro", dec!(20)),
+            SubscriptionPlan::new("Max 5x", dec!(100)),
+        ];
+
+        let values = plan_values(&monthly, &daily, &plans);
+        let summary: Vec<(String, &str, Option<u32>, Decimal)> = values
+            .iter()
+            .map(|v| {
+                (
+                    v.month.to_string(),
+                    v.plan.as_str(),
+                    v.break_even_day,
+                    v.net_value,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("2026-09".to_string(), "Pro", Some(20), dec!(10)),
+                ("2026-09".to_string(), "Max 5x", None, dec!(-70)),
+                // $16 by the 10th, $24 by the 17th
+                ("2026-10".to_string(), "Pro", Some(17), dec!(4)),
+                ("2026-10".to_string(), "Max 5x", None, dec!(-76)),
+            ]
+        );
+        assert_eq!(values[2].roi, Some(1.2));
+        assert_eq!(values[3].roi, Some(0.24));
+    }
+
+    #[test]
+    fn plan_price_is_prorated_by_average_month() {
+        // $60.875 over a 30.4375-day month is $2 a day
+        let plan = SubscriptionPlan::new("Custom", dec!(60.875));
+        assert_eq!(plan.cost_for_days(14), dec!(28));
+        assert_eq!(plan.cost_for_days(0), Decimal::ZERO);
+
+        let pro = SubscriptionPlan::new("Pro", dec!(20));
+        assert_eq!(pro.cost_for_days(7).round_dp(2), dec!(4.60));
+    }
+}
@@ -0,0 +1,332 @@
+use super::plans::SubscriptionPlan;
+use super::{sort_groups, GroupBy};
+use crate::aggregation::group_entries;
+use crate::pricing::PricingFetcher;
+use crate::types::*;
+use anyhow::{bail, Result};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A what-if pricing scenario
+///
+/// Entries are re-priced with the scenario's [`PricingFetcher`], after swapping
+/// their model according to the model mappings.
+#[derive(Clone)]
+pub struct Scenario {
+    pricing_fetcher: PricingFetcher,
+    model_map: Vec<(String, ModelName)>,
+}
+
+impl Scenario {
+    pub fn new(pricing_fetcher: PricingFetcher) -> Self {
+        Self {
+            pricing_fetcher,
+            model_map: Vec::new(),
+        }
+    }
+
+    /// Price every entry as `model`
+    pub fn all_as(self, model: ModelName) -> Self {
+        self.map_model("", model)
+    }
+
+    /// Price entries whose model name contains `pattern` (case-insensitive) as `model`
+    ///
+    /// Mappings are tried in the order they were added.
+    pub fn map_model(mut self, pattern: impl Into<String>, model: ModelName) -> Self {
+        self.model_map.push((pattern.into().to_lowercase(), model));
+        self
+    }
+
+    /// Check that every target model has pricing
+    pub fn validate(&self) -> Result<()> {
+        for (_, model) in &self.model_map {
+            if self.pricing_fetcher.pricing_for(model).is_none() {
+                bail!("No pricing found for simulated model '{}'", model);
+            }
+        }
+        Ok(())
+    }
+
+    /// Model an entry would have used under this scenario
+    pub fn model_for(&self, model: &ModelName) -> ModelName {
+        let name = model.as_str().to_lowercase();
+        self.model_map
+            .iter()
+            .find(|(pattern, _)| name.contains(pattern.as_str()))
+            .map_or_else(|| model.clone(), |(_, target)| target.clone())
+    }
+
+    /// Copy of the entry with the scenario's model and cost
+    ///
+    /// Entries whose model has no pricing keep their original cost.
+    pub fn reprice(&self, entry: &LoadedUsageEntry) -> LoadedUsageEntry {
+        let model = self.model_for(&entry.model);
+        let cost = self
+            .pricing_fetcher
+            .pricing_for(&model)
+            .map_or(entry.cost, |pricing| pricing.calculate_cost(&entry.tokens));
+
+        LoadedUsageEntry {
+            model,
+            cost,
+            ..entry.clone()
+        }
+    }
+}
+
+/// Actual and simulated cost for one day, session or project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedUsage {
+    pub group: String,
+    pub actual_cost: Decimal,
+    pub simulated_cost: Decimal,
+
+    /// `simulated_cost - actual_cost`
+    pub difference: Decimal,
+
+    /// Difference as a percentage of the actual cost
+    pub change_percent: Option<f64>,
+
+    /// Models used under the scenario, by simulated cost
+    pub simulated_models: Vec<ModelName>,
+}
+
+impl SimulatedUsage {
+    fn new(
+        group: String,
+        actual_cost: Decimal,
+        simulated_cost: Decimal,
+        simulated_models: Vec<ModelName>,
+    ) -> Self {
+        let difference = simulated_cost - actual_cost;
+        let change_percent = (!actual_cost.is_zero())
+            .then(|| (difference / actual_cost * Decimal::ONE_HUNDRED).to_f64())
+            .flatten();
+
+        Self {
+            group,
+            actual_cost,
+            simulated_cost,
+            difference,
+            change_percent,
+            simulated_models,
+        }
+    }
+}
+
+/// Re-price entries under a scenario and compare against their actual cost per group
+pub fn simulate(
+    entries: &[LoadedUsageEntry],
+    scenario: &Scenario,
+    group_by: GroupBy,
+    order: SortOrder,
+) -> Vec<SimulatedUsage> {
+    let repriced: Vec<LoadedUsageEntry> = entries.iter().map(|e| scenario.reprice(e)).collect();
+
+    let actual = group_entries(entries, |e| group_by.key(e));
+    let simulated = group_entries(&repriced, |e| group_by.key(e));
+
+    let mut rows: Vec<SimulatedUsage> = actual
+        .iter()
+        .map(|(group, usage)| {
+            let sim = &simulated[group];
+            SimulatedUsage::new(
+                group.clone(),
+                usage.cost(),
+                sim.cost(),
+                sim.model_breakdowns()
+                    .into_iter()
+                    .map(|b| b.model_name)
+                    .collect(),
+            )
+        })
+        .collect();
+
+    sort_groups(&mut rows, group_by, order, |r| &r.group, |r| r.actual_cost);
+    rows
+}
+
+/// Combined actual and simulated cost across all rows
+pub fn simulation_totals(rows: &[SimulatedUsage]) -> SimulatedUsage {
+    let actual = rows.iter().map(|r| r.actual_cost).sum();
+    let simulated = rows.iter().map(|r| r.simulated_cost).sum();
+    SimulatedUsage::new("TOTAL".to_string(), actual, simulated, Vec::new())
+}
+
+/// A subscription plan priced over the same period as the usage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanComparison {
+    pub plan: String,
+    pub monthly_cost: Decimal,
+
+    /// Plan price prorated to the period
+    pub plan_cost: Decimal,
+
+    /// Pay-as-you-go cost of the actual usage
+    pub api_cost: Decimal,
+
+    /// Pay-as-you-go cost under the scenario
+    pub simulated_cost: Decimal,
+}
+
+/// Price each plan over `days` and set it against pay-as-you-go costs
+pub fn compare_plans(
+    plans: &[SubscriptionPlan],
+    days: i64,
+    totals: &SimulatedUsage,
+) -> Vec<PlanComparison> {
+    plans
+        .iter()
+        .map(|plan| PlanComparison {
+            plan: plan.name.clone(),
+            monthly_cost: plan.monthly_cost,
+            plan_cost: plan.cost_for_days(days),
+            api_cost: totals.actual_cost,
+            simulated_cost: totals.simulated_cost,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::ModelPricing;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+    use std::collections::HashMap;
+
+    /// Flat pricing of `per_million` USD per million tokens of any kind
+    fn flat(per_million: Decimal) -> ModelPricing {
+        ModelPricing {
+            input_price: per_million,
+            output_price: per_million,
+            cache_creation_price: per_million,
+            cache_read_price: per_million,
+        }
+    }
+
+    /// A scenario priced with made-up models: `big` at $10 and `small` at $1
+    /// per million tokens
+    fn scenario() -> Scenario {
+        let pricing = HashMap::from([
+            ("big".to_string(), flat(dec!(10))),
+            ("small".to_string(), flat(dec!(1))),
+        ]);
+        Scenario::new(PricingFetcher::new(true).with_custom_pricing(pricing))
+    }
+
+    /// A million-token entry on 2026-10-`day` that cost `cost`
+    fn entry(model: &str, day: u32, cost: Decimal) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, day, 9, 0, 0).unwrap(),
+            model: ModelName::new(model),
+            tokens: TokenCounts {
+                input_tokens: 600_000,
+                output_tokens: 400_000,
+                ..TokenCounts::default()
+            },
+            cost,
+            session_id: None,
+            request_id: None,
+            message_id: None,
+            project: None,
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    #[test]
+    fn map_model_matches_case_insensitively_in_order() {
+        let scenario = scenario()
+            .map_model("OPUS", ModelName::new("big"))
+            .map_model("o", ModelName::new("small"));
+
+        assert_eq!(
+            scenario
+                .model_for(&ModelName::new("claude-Opus-4"))
+                .as_str(),
+            "big"
+        );
+        assert_eq!(
+            scenario
+                .model_for(&ModelName::new("claude-sonnet-4"))
+                .as_str(),
+            "small"
+        );
+        // Unmatched models are kept
+        assert_eq!(
+            scenario.model_for(&ModelName::new("haiku")).as_str(),
+            "haiku"
+        );
+    }
+
+    #[test]
+    fn all_as_maps_every_model() {
+        let scenario = scenario().all_as(ModelName::new("small"));
+
+        for model in ["claude-opus-4", "claude-haiku-4-5", "unknown"] {
+            assert_eq!(scenario.model_for(&ModelName::new(model)).as_str(), "small");
+        }
+        assert!(scenario.validate().is_ok());
+        assert!(Scenario::new(PricingFetcher::new(true))
+            .all_as(ModelName::new("no-such-model"))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn reprice_uses_the_target_price_or_keeps_the_cost() {
+        let scenario = scenario().map_model("opus", ModelName::new("big"));
+
+        let repriced = scenario.reprice(&entry("claude-opus-4", 1, dec!(50)));
+        assert_eq!(repriced.model.as_str(), "big");
+        assert_eq!(repriced.cost, dec!(10));
+        assert_eq!(repriced.tokens.output_tokens, 400_000);
+
+        // Neither mapped nor priced: unchanged
+        let kept = scenario.reprice(&entry("mystery-model", 1, dec!(3)));
+        assert_eq!(kept.model.as_str(), "mystery-model");
+        assert_eq!(kept.cost, dec!(3));
+    }
+
+    #[test]
+    fn simulate_compares_groups_and_prorates_plans() {
+        let scenario = scenario().all_as(ModelName::new("small"));
+        let entries = [
+            entry("claude-opus-4", 1, dec!(4)),
+            entry("claude-opus-4", 1, dec!(4)),
+            entry("claude-opus-4", 2, dec!(2)),
+        ];
+
+        let rows = simulate(&entries, &scenario, GroupBy::Day, SortOrder::Asc);
+        let summary: Vec<(&str, Decimal, Decimal)> = rows
+            .iter()
+            .map(|r| (r.group.as_str(), r.actual_cost, r.simulated_cost))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("2026-10-01", dec!(8), dec!(2)),
+                ("2026-10-02", dec!(2), dec!(1)),
+            ]
+        );
+        assert_eq!(rows[0].change_percent, Some(-75.0));
+
+        let totals = simulation_totals(&rows);
+        assert_eq!(totals.difference, dec!(-7));
+
+        // $60.875 a month is $2 a day over the 30.4375-day average month
+        let plans = [SubscriptionPlan::new("Test", dec!(60.875))];
+        let comparison = compare_plans(&plans, 14, &totals);
+        assert_eq!(comparison[0].plan_cost, dec!(28));
+        assert_eq!(comparison[0].api_cost, dec!(10));
+        assert_eq!(comparison[0].simulated_cost, dec!(3));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::aggregation::{split_by_user, UsageAccumulator};
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// One model's part of a user's cost
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelShare {
+    pub model: ModelName,
+    pub cost: Decimal,
+    pub total_tokens: u64,
+
+    /// Percentage of the user's cost
+    pub cost_share: f64,
+}
+
+/// Totals and model mix for one user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSummary {
+    pub user: String,
+
+    /// Machines the usage was recorded on; "local" for this machine's logs
+    pub machines: Vec<String>,
+
+    pub sessions: usize,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub total_cost: Decimal,
+
+    /// Percentage of the team's cost
+    pub cost_share: f64,
+
+    /// First and last request; `None` only for the totals of an empty team
+    pub first_activity: Option<DateTime<Utc>>,
+    pub last_activity: Option<DateTime<Utc>>,
+
+    /// Models by cost, largest first
+    pub models: Vec<ModelShare>,
+}
+
+/// Per-user summaries and the team total
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamSummary {
+    /// Users by cost, largest first
+    pub users: Vec<UserSummary>,
+    pub totals: UserSummary,
+}
+
+/// Summarize usage per user, by [`LoadedUsageEntry::user_label`]
+///
+/// Without entries there are no users and the totals are zero.
+pub fn team_summary(entries: Vec<LoadedUsageEntry>) -> TeamSummary {
+    let totals = summarize("TOTAL".to_string(), &entries, None);
+
+    let mut users: Vec<UserSummary> = split_by_user(entries)
+        .into_iter()
+        .map(|(user, entries)| summarize(user, &entries, Some(totals.total_cost)))
+        .collect();
+    users.sort_by_key(|u| Reverse(u.total_cost));
+
+    TeamSummary { users, totals }
+}
+
+fn summarize(
+    user: String,
+    entries: &[LoadedUsageEntry],
+    team_cost: Option<Decimal>,
+) -> UserSummary {
+    let first_activity = entries.iter().map(|e| e.timestamp).min();
+    let last_activity = entries.iter().map(|e| e.timestamp).max();
+
+    let mut usage = UsageAccumulator::default();
+    for entry in entries {
+        usage.add(entry);
+    }
+    let tokens = usage.tokens();
+    let total_cost = usage.cost();
+
+    let share = |cost: Decimal, of: Decimal| {
+        if of.is_zero() {
+            0.0
+        } else {
+            (cost / of * Decimal::ONE_HUNDRED).to_f64().unwrap_or(0.0)
+        }
+    };
+
+    let mut models: Vec<ModelShare> = usage
+        .model_breakdowns()
+        .into_iter()
+        .map(|b| ModelShare {
+            cost_share: share(b.cost, total_cost),
+            total_tokens: b.input_tokens
+                + b.output_tokens
+                + b.cache_creation_tokens
+                + b.cache_read_tokens,
+            model: b.model_name,
+            cost: b.cost,
+        })
+        .collect();
+    models.sort_by_key(|m| Reverse(m.cost));
+
+    UserSummary {
+        user,
+        machines: entries
+            .iter()
+            .map(|e| e.machine.as_deref().unwrap_or("local"))
+            .unique()
+            .sorted()
+            .map(str::to_string)
+            .collect(),
+        sessions: entries
+            .iter()
+            .filter_map(|e| e.session_id.as_ref())
+            .unique()
+            .count(),
+        requests: usage.request_count(),
+        input_tokens: tokens.input_tokens,
+        output_tokens: tokens.output_tokens,
+        cache_creation_tokens: tokens.cache_creation_tokens,
+        cache_read_tokens: tokens.cache_read_tokens,
+        total_tokens: tokens.input_tokens
+            + tokens.output_tokens
+            + tokens.cache_creation_tokens
+            + tokens.cache_read_tokens,
+        total_cost,
+        cost_share: team_cost.map_or(100.0, |team| share(total_cost, team)),
+        first_activity,
+        last_activity,
+        models,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_team_has_zero_totals() {
+        let team = team_summary(Vec::new());
+        assert!(team.users.is_empty());
+        assert_eq!(team.totals.requests, 0);
+        assert_eq!(team.totals.total_cost, Decimal::ZERO);
+        assert_eq!(team.totals.first_activity, None);
+        assert_eq!(team.totals.last_activity, None);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::aggregation::UsageAccumulator;
+use crate::types::*;
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Resolution of a session timeline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineGranularity {
+    /// One row per local clock hour
+    #[default]
+    Hour,
+    /// One row per message
+    Message,
+}
+
+/// Usage within one hour or message of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelinePoint {
+    /// Start of the hour, or the message timestamp
+    pub time: DateTime<Utc>,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost: Decimal,
+    pub models: Vec<ModelName>,
+
+    /// Idle time since the previous message, when longer than the idle threshold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_before_seconds: Option<i64>,
+}
+
+impl TimelinePoint {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+}
+
+/// A session's usage over time, with idle gaps marked
+///
+/// Entries are expected to belong to a single session. Hours are local clock hours
+/// so the timeline lines up with the time of day the session ran.
+pub fn session_timeline(
+    entries: &[LoadedUsageEntry],
+    granularity: TimelineGranularity,
+    idle_threshold: Duration,
+) -> Vec<TimelinePoint> {
+    let sorted: Vec<&LoadedUsageEntry> = entries.iter().sorted_by_key(|e| e.timestamp).collect();
+
+    // Gaps are measured between consecutive messages, so hour rows carry the
+    // gap before their first message
+    let mut idle_before = Vec::with_capacity(sorted.len());
+    let mut previous: Option<DateTime<Utc>> = None;
+    for entry in &sorted {
+        let gap = previous
+            .map(|p| entry.timestamp - p)
+            .filter(|gap| *gap > idle_threshold);
+        idle_before.push(gap.map(|g| g.num_seconds()));
+        previous = Some(entry.timestamp);
+    }
+
+    match granularity {
+        TimelineGranularity::Message => sorted
+            .iter()
+            .zip(idle_before)
+            .map(|(entry, idle)| TimelinePoint {
+                time: entry.timestamp,
+                requests: 1,
+                input_tokens: entry.tokens.input_tokens,
+                output_tokens: entry.tokens.output_tokens,
+                cache_creation_tokens: entry.tokens.cache_creation_input_tokens,
+                cache_read_tokens: entry.tokens.cache_read_input_tokens,
+                cost: entry.cost,
+                models: vec![entry.model.clone()],
+                idle_before_seconds: idle,
+            })
+            .collect(),
+        TimelineGranularity::Hour => {
+            // Each hour keeps the idle gap before its first message
+            let mut hours: IndexMap<DateTime<Utc>, (UsageAccumulator, Option<i64>)> =
+                IndexMap::new();
+            for (entry, idle) in sorted.iter().zip(idle_before) {
+                hours
+                    .entry(local_hour(entry.timestamp))
+                    .or_insert_with(|| (UsageAccumulator::default(), idle))
+                    .0
+                    .add(entry);
+            }
+
+            hours
+                .into_iter()
+                .map(|(hour, (usage, idle))| {
+                    let tokens = usage.tokens();
+                    TimelinePoint {
+                        time: hour,
+                        requests: usage.request_count(),
+                        input_tokens: tokens.input_tokens,
+                        output_tokens: tokens.output_tokens,
+                        cache_creation_tokens: tokens.cache_creation_tokens,
+                        cache_read_tokens: tokens.cache_read_tokens,
+                        cost: usage.cost(),
+                        models: usage
+                            .model_breakdowns()
+                            .into_iter()
+                            .map(|b| b.model_name)
+                            .collect(),
+                        idle_before_seconds: idle,
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Start of the local clock hour containing `timestamp`
+fn local_hour(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    let local = timestamp.with_timezone(&Local);
+    let floored = local
+        .with_minute(0)
+        .and_then(|t| t.with_second(0))
+        .and_then(|t| t.with_nanosecond(0))
+        .unwrap_or(local);
+    floored.with_timezone(&Utc)
+}
@@ -0,0 +1,312 @@
+//! Portable usage archives for combining usage across machines and users.
+//!
+//! An archive is a JSONL file: one [`ArchiveHeader`] line followed by one
+//! [`LoadedUsageEntry`] per line, already priced and deduplicated. Archives
+//! are read back with [`read_archive`], or by passing them to
+//! [`UsageLoader::data_file`](crate::UsageLoader::data_file).
+
+use crate::types::LoadedUsageEntry;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Value of the header's `format` field
+pub const ARCHIVE_FORMAT: &str = "rccusage-archive";
+
+/// Newest archive version this build reads and the one it writes
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// First line of an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveHeader {
+    pub format: String,
+    pub version: u32,
+
+    /// Machine the archive was exported from
+    pub machine: String,
+
+    /// User whose local logs were exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    pub exported_at: DateTime<Utc>,
+    pub entries: usize,
+}
+
+/// Label for this machine: `HOSTNAME`, `COMPUTERNAME` or `/etc/hostname`
+pub fn default_machine_label() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .chain(std::fs::read_to_string("/etc/hostname").ok())
+        .map(|label| label.trim().to_string())
+        .find(|label| !label.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Label for the user of this machine's logs: `USER` or `USERNAME`
+pub fn default_user_label() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|label| label.trim().to_string())
+        .find(|label| !label.is_empty())
+        .unwrap_or_else(|| "local".to_string())
+}
+
+/// [`default_user_label`], read once
+pub fn local_user_label() -> &'static str {
+    static LABEL: OnceLock<String> = OnceLock::new();
+    LABEL.get_or_init(default_user_label)
+}
+
+/// Write entries to an archive at `path`, or to stdout when `path` is `-`
+///
+/// Entries without machine or user labels are stamped with `machine` and
+/// `user`; entries that came from another archive keep theirs. Duplicates are
+/// dropped. Returns the number of entries written.
+pub fn write_archive(
+    path: &Path,
+    entries: &[LoadedUsageEntry],
+    machine: &str,
+    user: &str,
+) -> Result<usize> {
+    let entries: Vec<LoadedUsageEntry> = dedupe(entries.to_vec())
+        .into_iter()
+        .map(|mut entry| {
+            entry.machine.get_or_insert_with(|| machine.to_string());
+            entry.user.get_or_insert_with(|| user.to_string());
+            entry
+        })
+        .collect();
+
+    write_entries(path, &entries, machine, Some(user))?;
+    Ok(entries.len())
+}
+
+/// Write a header and the entries as they are, without stamping labels
+pub(crate) fn write_entries(
+    path: &Path,
+    entries: &[LoadedUsageEntry],
+    machine: &str,
+    user: Option<&str>,
+) -> Result<()> {
+    let writer: Box<dyn Write> = if path == Path::new("-") {
+        Box::new(std::io::stdout().lock())
+    } else {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create archive: {}", path.display()))?;
+        Box::new(file)
+    };
+    let mut writer = BufWriter::new(writer);
+
+    let header = ArchiveHeader {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        machine: machine.to_string(),
+        user: user.map(str::to_string),
+        exported_at: Utc::now(),
+        entries: entries.len(),
+    };
+    serde_json::to_writer(&mut writer, &header)?;
+    writeln!(writer)?;
+
+    for entry in entries {
+        serde_json::to_writer(&mut writer, entry)?;
+        writeln!(writer)?;
+    }
+
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write archive: {}", path.display()))
+}
+
+/// Read every entry from an archive, sorted by timestamp
+///
+/// Entries without machine or user labels get the header's. Malformed entry
+/// lines are skipped, like malformed transcript lines.
+pub fn read_archive(path: &Path) -> Result<Vec<LoadedUsageEntry>> {
+    let (header, entries) = read_entries(path)?;
+
+    let mut entries: Vec<LoadedUsageEntry> = entries
+        .into_iter()
+        .map(|mut entry| {
+            entry.machine.get_or_insert_with(|| header.machine.clone());
+            if entry.user.is_none() {
+                entry.user = header.user.clone();
+            }
+            entry
+        })
+        .collect();
+
+    entries.sort_by_key(|e| e.timestamp);
+    Ok(entries)
+}
+
+/// Read and validate the header, then every well-formed entry as written
+pub(crate) fn read_entries(path: &Path) -> Result<(ArchiveHeader, Vec<LoadedUsageEntry>)> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open archive: {}", path.display()))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let first = lines
+        .next()
+        .transpose()
+        .with_context(|| format!("Failed to read archive: {}", path.display()))?
+        .unwrap_or_default();
+    let header: ArchiveHeader = serde_json::from_str(&first)
+        .ok()
+        .filter(|h: &ArchiveHeader| h.format == ARCHIVE_FORMAT)
+        .with_context(|| format!("Not a usage archive: {}", path.display()))?;
+    if header.version > ARCHIVE_VERSION {
+        bail!(
+            "Archive {} is version {}, newer than supported version {}",
+            path.display(),
+            header.version,
+            ARCHIVE_VERSION
+        );
+    }
+
+    let entries = lines
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<LoadedUsageEntry>(line.trim()).ok())
+        .collect();
+
+    Ok((header, entries))
+}
+
+/// Drop entries whose [`record_key`](LoadedUsageEntry::record_key) was
+/// already seen, keeping the first
+pub fn dedupe(entries: Vec<LoadedUsageEntry>) -> Vec<LoadedUsageEntry> {
+    let mut seen = HashSet::new();
+    entries
+        .into_iter()
+        .filter(|entry| seen.insert(entry.record_key()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MessageId, ModelName, RequestId, SessionId, TokenCounts};
+    use chrono::{TimeZone, Timelike};
+    use rust_decimal::Decimal;
+    use std::path::PathBuf;
+
+    fn entry(minute: u32, id: Option<&str>) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 18, 9, minute, 0).unwrap(),
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: 100,
+                output_tokens: 200,
+                ..TokenCounts::default()
+            },
+            cost: Decimal::new(125, 3),
+            session_id: Some(SessionId::new("session-1")),
+            request_id: id.map(|id| RequestId::new(format!("req-{id}"))),
+            message_id: id.map(|id| MessageId::new(format!("msg-{id}"))),
+            project: Some("-home-dev-alpha".to_string()),
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    fn archive_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "rccusage-archive-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn archive_round_trips_and_stamps_labels() {
+        let path = archive_path("roundtrip");
+        let mut imported = entry(1, Some("b"));
+        imported.machine = Some("desktop".to_string());
+        imported.user = Some("bob".to_string());
+
+        let written = write_archive(
+            &path,
+            &[entry(5, Some("a")), imported.clone()],
+            "laptop",
+            "alice",
+        )
+        .unwrap();
+        assert_eq!(written, 2);
+
+        let (header, _) = read_entries(&path).unwrap();
+        assert_eq!(header.format, ARCHIVE_FORMAT);
+        assert_eq!(header.version, ARCHIVE_VERSION);
+        assert_eq!(header.machine, "laptop");
+        assert_eq!(header.user.as_deref(), Some("alice"));
+        assert_eq!(header.entries, 2);
+
+        // Sorted by time, imported labels kept, local entries stamped
+        let entries = read_archive(&path).unwrap();
+        let labels: Vec<(String, Option<&str>, Option<&str>)> = entries
+            .iter()
+            .map(|e| (e.unique_hash(), e.machine.as_deref(), e.user.as_deref()))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("msg-b:req-b".to_string(), Some("desktop"), Some("bob")),
+                ("msg-a:req-a".to_string(), Some("laptop"), Some("alice")),
+            ]
+        );
+        assert_eq!(entries[1].tokens.output_tokens, 200);
+        assert_eq!(entries[1].cost, Decimal::new(125, 3));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_files_and_newer_versions() {
+        let path = archive_path("header");
+
+        std::fs::write(&path, "{\"type\":\"assistant\"}\n").unwrap();
+        let err = read_archive(&path).unwrap_err();
+        assert!(err.to_string().contains("Not a usage archive"), "{}", err);
+
+        let header = format!(
+            "{{\"format\":\"{}\",\"version\":{},\"machine\":\"laptop\",\
+             \"exportedAt\":\"2026-10-18T09:00:00Z\",\"entries\":0}}\n",
+            ARCHIVE_FORMAT,
+            ARCHIVE_VERSION + 1
+        );
+        std::fs::write(&path, header).unwrap();
+        let err = read_archive(&path).unwrap_err();
+        assert!(err.to_string().contains("newer than supported"), "{}", err);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn dedupes_entries_with_and_without_ids() {
+        let entries = vec![
+            entry(1, Some("a")),
+            entry(2, Some("a")),
+            entry(3, None),
+            entry(3, None),
+            entry(4, None),
+        ];
+
+        let kept: Vec<u32> = dedupe(entries)
+            .iter()
+            .map(|e| e.timestamp.minute())
+            .collect();
+        assert_eq!(kept, [1, 3, 4]);
+    }
+}
@@ -0,0 +1,447 @@
+//! Columnar export of usage entries as Parquet or Arrow IPC, behind the `parquet` feature.
+//!
+//! A dataset is a directory with one file per UTC month, in Hive-style
+//! partitions: `month=2026-10/usage.parquet` (or `usage.arrow`). Every file
+//! has the schema from [`usage_schema`], one row per request:
+//!
+//! | Column | Type | Notes |
+//! |---|---|---|
+//! | `timestamp` | timestamp (µs, UTC) | |
+//! | `date` | date32 | UTC date of `timestamp` |
+//! | `model` | utf8 | |
+//! | `input_tokens` | uint64 | |
+//! | `output_tokens` | uint64 | |
+//! | `cache_creation_tokens` | uint64 | |
+//! | `cache_read_tokens` | uint64 | |
+//! | `total_tokens` | uint64 | sum of the four token columns |
+//! | `cost` | decimal(18, 10) | USD, as priced at export |
+//! | `session_id` | utf8, nullable | |
+//! | `request_id` | utf8, nullable | |
+//! | `message_id` | utf8, nullable | |
+//! | `project` | utf8, nullable | Claude project directory name |
+//! | `cwd` | utf8, nullable | working directory of the request |
+//! | `git_branch` | utf8, nullable | branch checked out in `cwd` |
+//! | `version` | utf8, nullable | Claude Code version |
+//! | `is_sidechain` | bool | subagent request |
+//! | `machine` | utf8 | |
+//! | `user` | utf8 | |
+
+use crate::archive::dedupe;
+use crate::types::LoadedUsageEntry;
+use anyhow::{bail, Context, Result};
+use arrow_array::{
+    ArrayRef, BooleanArray, Date32Array, Decimal128Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Datelike, Months, NaiveDate};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Precision and scale of the `cost` column
+const COST_PRECISION: u8 = 18;
+const COST_SCALE: i8 = 10;
+
+/// File format of a columnar dataset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnarFormat {
+    /// Apache Parquet, Snappy-compressed
+    #[default]
+    Parquet,
+    /// Arrow IPC file format
+    Arrow,
+}
+
+impl ColumnarFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Arrow => "arrow",
+        }
+    }
+}
+
+/// One month's file in a dataset
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Partition {
+    /// Month as `YYYY-MM`
+    pub month: String,
+    pub path: PathBuf,
+    pub rows: usize,
+}
+
+/// Schema of every file in a dataset
+pub fn usage_schema() -> SchemaRef {
+    let text = |name: &str, nullable: bool| Field::new(name, DataType::Utf8, nullable);
+    let tokens = |name: &str| Field::new(name, DataType::UInt64, false);
+
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("date", DataType::Date32, false),
+        text("model", false),
+        tokens("input_tokens"),
+        tokens("output_tokens"),
+        tokens("cache_creation_tokens"),
+        tokens("cache_read_tokens"),
+        tokens("total_tokens"),
+        Field::new(
+            "cost",
+            DataType::Decimal128(COST_PRECISION, COST_SCALE),
+            false,
+        ),
+        text("session_id", true),
+        text("request_id", true),
+        text("message_id", true),
+        text("project", true),
+        text("cwd", true),
+        text("git_branch", true),
+        text("version", true),
+        Field::new("is_sidechain", DataType::Boolean, false),
+        text("machine", false),
+        text("user", false),
+    ]))
+}
+
+/// Convert entries to one record batch with [`usage_schema`]
+///
+/// Entries without machine or user labels get `machine` and `user`.
+pub fn usage_batch(entries: &[LoadedUsageEntry], machine: &str, user: &str) -> Result<RecordBatch> {
+    let epoch = DateTime::UNIX_EPOCH.date_naive();
+    let strings = |value: fn(&LoadedUsageEntry) -> Option<&str>| -> ArrayRef {
+        Arc::new(entries.iter().map(value).collect::<StringArray>())
+    };
+    let counts = |value: fn(&LoadedUsageEntry) -> u64| -> ArrayRef {
+        Arc::new(entries.iter().map(value).collect::<UInt64Array>())
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                entries.iter().map(|e| e.timestamp.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(Date32Array::from_iter_values(
+            entries
+                .iter()
+                .map(|e| (e.timestamp.date_naive() - epoch).num_days() as i32),
+        )),
+        strings(|e| Some(e.model.as_str())),
+        counts(|e| e.tokens.input_tokens),
+        counts(|e| e.tokens.output_tokens),
+        counts(|e| e.tokens.cache_creation_input_tokens),
+        counts(|e| e.tokens.cache_read_input_tokens),
+        counts(|e| e.tokens.total()),
+        Arc::new(
+            Decimal128Array::from_iter_values(entries.iter().map(|e| {
+                let mut cost = e.cost;
+                cost.rescale(COST_SCALE as u32);
+                cost.mantissa()
+            }))
+            .with_precision_and_scale(COST_PRECISION, COST_SCALE)?,
+        ),
+        strings(|e| e.session_id.as_ref().map(|s| s.0.as_str())),
+        strings(|e| e.request_id.as_ref().map(|r| r.0.as_str())),
+        strings(|e| e.message_id.as_ref().map(|m| m.0.as_str())),
+        strings(|e| e.project.as_deref()),
+        strings(|e| e.cwd.as_deref()),
+        strings(|e| e.git_branch.as_deref()),
+        strings(|e| e.version.as_deref()),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| Some(e.is_sidechain))
+                .collect::<BooleanArray>(),
+        ),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| Some(e.machine.as_deref().unwrap_or(machine)))
+                .collect::<StringArray>(),
+        ),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| Some(e.user.as_deref().unwrap_or(user)))
+                .collect::<StringArray>(),
+        ),
+    ];
+
+    Ok(RecordBatch::try_new(usage_schema(), columns)?)
+}
+
+/// Write entries as a dataset under `dir`, one file per UTC month
+///
+/// `since` and `until` are the dates the entries were loaded for. Duplicates
+/// are dropped, and entries without machine or user labels are stamped as in
+/// [`write_archive`](crate::archive::write_archive). Files for months already
+/// in `dir` are replaced and other months are left alone, so an existing month
+/// is only rewritten when `since` and `until` cover all of it; otherwise
+/// nothing is written.
+pub fn write_dataset(
+    dir: &Path,
+    entries: &[LoadedUsageEntry],
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    format: ColumnarFormat,
+    machine: &str,
+    user: &str,
+) -> Result<Vec<Partition>> {
+    let mut months: BTreeMap<NaiveDate, Vec<LoadedUsageEntry>> = BTreeMap::new();
+    for entry in dedupe(entries.to_vec()) {
+        let month = entry.timestamp.date_naive().with_day(1).unwrap_or_default();
+        months.entry(month).or_default().push(entry);
+    }
+
+    let path = |month: NaiveDate| {
+        dir.join(format!("month={}", month.format("%Y-%m")))
+            .join(format!("usage.{}", format.extension()))
+    };
+
+    // Check every month first so a refused export leaves the dataset as it was
+    for &month in months.keys() {
+        let last = month + Months::new(1) - chrono::Duration::days(1);
+        let whole =
+            since.is_none_or(|since| since <= month) && until.is_none_or(|until| until >= last);
+        let path = path(month);
+        if !whole && path.exists() {
+            bail!(
+                "The date range covers only part of {}, and writing it would replace {} \
+                 with a partial month. Export whole months or use another directory",
+                month.format("%Y-%m"),
+                path.display()
+            );
+        }
+    }
+
+    let mut partitions = Vec::new();
+    for (month, mut entries) in months {
+        entries.sort_by_key(|e| e.timestamp);
+
+        let path = path(month);
+        let partition = path.parent().unwrap_or(dir);
+        std::fs::create_dir_all(partition)
+            .with_context(|| format!("Failed to create directory: {}", partition.display()))?;
+
+        let month = month.format("%Y-%m").to_string();
+        let batch = usage_batch(&entries, machine, user)?;
+        write_batch(&path, &batch, format)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        partitions.push(Partition {
+            month,
+            path,
+            rows: entries.len(),
+        });
+    }
+
+    Ok(partitions)
+}
+
+fn write_batch(path: &Path, batch: &RecordBatch, format: ColumnarFormat) -> Result<()> {
+    let file = File::create(path)?;
+
+    match format {
+        ColumnarFormat::Parquet => {
+            let props = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+            writer.write(batch)?;
+            writer.close()?;
+        }
+        ColumnarFormat::Arrow => {
+            let mut writer = arrow_ipc::writer::FileWriter::try_new(file, &batch.schema())?;
+            writer.write(batch)?;
+            writer.finish()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MessageId, ModelName, RequestId, SessionId, TokenCounts};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Date32Type, Decimal128Type, TimestampMicrosecondType, UInt64Type};
+    use chrono::{TimeZone, Utc};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rust_decimal::Decimal;
+
+    fn entry(day: u32, month: u32, id: &str, cost: Decimal) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, month, day, 12, 30, 0).unwrap(),
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: 100,
+                output_tokens: 200,
+                cache_creation_input_tokens: 300,
+                cache_read_input_tokens: 4000,
+            },
+            cost,
+            session_id: Some(SessionId::new("session-1")),
+            request_id: Some(RequestId::new(format!("req-{id}"))),
+            message_id: Some(MessageId::new(format!("msg-{id}"))),
+            project: Some("-home-dev-alpha".to_string()),
+            version: None,
+            cwd: None,
+            git_branch: Some("main".to_string()),
+            is_sidechain: true,
+            machine: None,
+            user: Some("alice".to_string()),
+        }
+    }
+
+    fn dataset_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rccusage-columnar-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn parquet_rows(path: &Path) -> usize {
+        ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .sum()
+    }
+
+    #[test]
+    fn usage_batch_has_schema_and_row_values() {
+        let batch = usage_batch(&[entry(18, 10, "1", Decimal::ONE)], "laptop", "bob").unwrap();
+
+        assert_eq!(batch.schema(), usage_schema());
+        assert_eq!(batch.num_rows(), 1);
+
+        let column = |name: &str| batch.column_by_name(name).unwrap();
+        let timestamp = Utc.with_ymd_and_hms(2026, 10, 18, 12, 30, 0).unwrap();
+        assert_eq!(
+            column("timestamp")
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(0),
+            timestamp.timestamp_micros()
+        );
+        assert_eq!(
+            column("date").as_primitive::<Date32Type>().value(0),
+            (timestamp.date_naive() - DateTime::UNIX_EPOCH.date_naive()).num_days() as i32
+        );
+        assert_eq!(
+            column("model").as_string::<i32>().value(0),
+            "claude-sonnet-4-5-20250929"
+        );
+        assert_eq!(
+            column("cache_read_tokens")
+                .as_primitive::<UInt64Type>()
+                .value(0),
+            4000
+        );
+        assert_eq!(
+            column("total_tokens").as_primitive::<UInt64Type>().value(0),
+            4600
+        );
+        assert_eq!(column("request_id").as_string::<i32>().value(0), "req-1");
+        assert!(column("cwd").is_null(0));
+        assert!(column("is_sidechain").as_boolean().value(0));
+
+        // Labels from the entry win over the defaults
+        assert_eq!(column("machine").as_string::<i32>().value(0), "laptop");
+        assert_eq!(column("user").as_string::<i32>().value(0), "alice");
+    }
+
+    #[test]
+    fn cost_is_rescaled_to_column_scale() {
+        let entries = [
+            entry(18, 10, "1", Decimal::new(125, 3)),
+            entry(18, 10, "2", Decimal::new(3, 0)),
+            entry(18, 10, "3", Decimal::new(123_456_789_012_345, 14)),
+        ];
+        let batch = usage_batch(&entries, "laptop", "bob").unwrap();
+
+        let cost = batch
+            .column_by_name("cost")
+            .unwrap()
+            .as_primitive::<Decimal128Type>();
+        assert_eq!(cost.precision(), COST_PRECISION);
+        assert_eq!(cost.scale(), COST_SCALE);
+        assert_eq!(cost.value(0), 1_250_000_000);
+        assert_eq!(cost.value(1), 30_000_000_000);
+        // Digits past the scale are rounded away
+        assert_eq!(cost.value(2), 12_345_678_901);
+    }
+
+    #[test]
+    fn writes_one_partition_per_month() {
+        let dir = dataset_dir("months");
+        let entries = [
+            entry(30, 9, "1", Decimal::ONE),
+            entry(1, 10, "2", Decimal::ONE),
+            entry(18, 10, "3", Decimal::ONE),
+            entry(18, 10, "3", Decimal::ONE),
+        ];
+
+        let partitions = write_dataset(
+            &dir,
+            &entries,
+            None,
+            None,
+            ColumnarFormat::Parquet,
+            "laptop",
+            "bob",
+        )
+        .unwrap();
+
+        let written: Vec<(&str, usize)> = partitions
+            .iter()
+            .map(|p| (p.month.as_str(), p.rows))
+            .collect();
+        assert_eq!(written, [("2026-09", 1), ("2026-10", 2)]);
+        assert_eq!(
+            partitions[1].path,
+            dir.join("month=2026-10").join("usage.parquet")
+        );
+        assert_eq!(parquet_rows(&partitions[1].path), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_to_replace_a_month_with_part_of_it() {
+        let dir = dataset_dir("partial");
+        let october = [
+            entry(1, 10, "1", Decimal::ONE),
+            entry(18, 10, "2", Decimal::ONE),
+        ];
+        let format = ColumnarFormat::Parquet;
+        write_dataset(&dir, &october, None, None, format, "laptop", "bob").unwrap();
+
+        let since = NaiveDate::from_ymd_opt(2026, 10, 15);
+        let err =
+            write_dataset(&dir, &october[1..], since, None, format, "laptop", "bob").unwrap_err();
+        assert!(err.to_string().contains("2026-10"), "{}", err);
+        assert_eq!(parquet_rows(&dir.join("month=2026-10/usage.parquet")), 2);
+
+        // A new month may start part way through, and whole months are replaced
+        let until = NaiveDate::from_ymd_opt(2026, 10, 31);
+        let november = [entry(2, 11, "3", Decimal::ONE)];
+        write_dataset(&dir, &november, since, None, format, "laptop", "bob").unwrap();
+        write_dataset(&dir, &october, None, until, format, "laptop", "bob").unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+use crate::commands::{render_report, AnomaliesArgs};
+use anyhow::Result;
+use rccusage::analytics::anomalies::{detect_anomalies, AnomalyOptions};
+use rccusage::output::table;
+use rccusage::UsageLoader;
+use tracing::info;
+
+pub async fn run(args: AnomaliesArgs) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // Earlier usage is still needed as history, so --since only limits what is reported
+    let since = options.since.take();
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    if entries.is_empty() {
+        if options.json {
+            println!("[]");
+        } else {
+            println!("No usage data found");
+        }
+        return Ok(());
+    }
+
+    info!("Detecting anomalies...");
+    let anomaly_options = AnomalyOptions {
+        method: args.method,
+        threshold: args
+            .threshold
+            .unwrap_or_else(|| args.method.default_threshold()),
+        history: args.history as usize,
+        min_cost: args.min_cost,
+    };
+    let output = render_report(
+        &options,
+        args.by_user,
+        entries,
+        |entries| {
+            let mut anomalies = detect_anomalies(&entries, anomaly_options);
+            if let Some(since) = since {
+                anomalies.retain(|a| a.time.date_naive() >= since);
+            }
+            Ok(anomalies)
+        },
+        |anomalies| {
+            if anomalies.is_empty() {
+                Ok("No anomalies found\n".to_string())
+            } else {
+                Ok(table::render_anomalies_table(&anomalies, options.compact))
+            }
+        },
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
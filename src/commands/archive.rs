@@ -0,0 +1,40 @@
+use crate::commands::{ArchiveArgs, ArchiveCommand, ArchiveSyncArgs};
+use anyhow::Result;
+use rccusage::history::HistoryStore;
+use rccusage::output::render_json;
+use rccusage::UsageLoader;
+use tracing::info;
+
+pub async fn run(args: ArchiveArgs) -> Result<()> {
+    match args.command {
+        ArchiveCommand::Sync(args) => sync(args).await,
+    }
+}
+
+async fn sync(args: ArchiveSyncArgs) -> Result<()> {
+    let store = HistoryStore::open_default();
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::new()
+        .mode(args.mode)
+        .offline(args.offline)
+        .history(false)
+        .load()
+        .await?;
+
+    info!("Syncing history in {}...", store.dir().display());
+    let summary = store.sync(&entries)?;
+
+    if args.json {
+        print!("{}", render_json(&summary, None)?);
+    } else {
+        println!(
+            "Stored {} new entries in {} ({} total)",
+            summary.new_entries,
+            summary.store.display(),
+            summary.total_entries
+        );
+    }
+
+    Ok(())
+}
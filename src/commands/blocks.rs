@@ -1,18 +1,39 @@
-use crate::aggregation::identify_session_blocks;
-use crate::commands::BlocksArgs;
-use crate::data_loader::load_usage_entries;
-use crate::output::{output_json, table};
-use crate::pricing::PricingFetcher;
+use crate::commands::{render_report, BlocksArgs};
+use crate::dashboard::BlocksDashboard;
 use anyhow::Result;
 use chrono::{Duration, Utc};
+use rccusage::aggregation::identify_session_blocks;
+use rccusage::config::Config;
+use rccusage::output::{render_json, table};
+use rccusage::types::{CommonOptions, SessionBlock};
+use rccusage::UsageLoader;
 use tracing::info;
 
-pub async fn run(args: BlocksArgs) -> Result<()> {
+pub async fn run(args: BlocksArgs, config: &Config) -> Result<()> {
     let options = args.common.to_common_options();
-    let pricing_fetcher = PricingFetcher::new(options.offline);
+
+    // Live mode takes over the terminal with a full-screen dashboard
+    if args.live {
+        let dashboard = BlocksDashboard::new(
+            options,
+            args.token_limit,
+            std::time::Duration::from_secs(args.watch.refresh_interval),
+        );
+        return dashboard.run().await;
+    }
+
+    if args.watch.watch {
+        return args
+            .watch
+            .to_monitor(config)?
+            .watch(&options, |aggregate| {
+                render(&args, &options, aggregate.blocks(args.token_limit))
+            })
+            .await;
+    }
 
     info!("Loading usage data...");
-    let entries = load_usage_entries(&options, &pricing_fetcher).await?;
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
 
     if entries.is_empty() {
         if options.json {
@@ -24,8 +45,32 @@ pub async fn run(args: BlocksArgs) -> Result<()> {
     }
 
     info!("Identifying session blocks...");
-    let mut blocks = identify_session_blocks(entries, args.token_limit);
+    if args.by_user {
+        print!(
+            "{}",
+            render_report(
+                &options,
+                true,
+                entries,
+                |entries| Ok(identify_session_blocks(entries, args.token_limit)),
+                |rows| render(&args, &options, rows),
+            )?
+        );
+        return Ok(());
+    }
+
+    let blocks = identify_session_blocks(entries, args.token_limit);
 
+    print!("{}", render(&args, &options, blocks)?);
+
+    Ok(())
+}
+
+fn render(
+    args: &BlocksArgs,
+    options: &CommonOptions,
+    mut blocks: Vec<SessionBlock>,
+) -> Result<String> {
     // Filter blocks based on flags
     if args.active {
         // Show only active block
@@ -37,20 +82,21 @@ pub async fn run(args: BlocksArgs) -> Result<()> {
     }
 
     if blocks.is_empty() {
-        if options.json {
-            println!("[]");
+        return Ok(if options.json {
+            "[]\n".to_string()
         } else {
-            println!("No session blocks found for the specified period");
-        }
-        return Ok(());
+            "No session blocks found for the specified period\n".to_string()
+        });
     }
 
-    // Output results
+    // Render results
     if options.json {
-        output_json(&blocks, options.jq.as_deref())?;
+        render_json(&blocks, options.jq.as_deref())
     } else {
-        table::output_blocks_table(&blocks, args.token_limit, options.compact)?;
+        Ok(table::render_blocks_table(
+            &blocks,
+            args.token_limit,
+            options.compact,
+        ))
     }
-
-    Ok(())
 }
@@ -0,0 +1,69 @@
+use crate::commands::{render_report, CacheArgs};
+use anyhow::Result;
+use rccusage::analytics::cache::{analyze_cache, cache_totals, CacheStats, CacheUsage};
+use rccusage::analytics::GroupBy;
+use rccusage::output::table;
+use rccusage::pricing::PricingFetcher;
+use rccusage::UsageLoader;
+use serde::Serialize;
+use tracing::info;
+
+pub async fn run(args: CacheArgs) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // If all_time flag is set, clear date filters
+    if args.all_time {
+        options.since = None;
+        options.until = None;
+    }
+
+    let pricing_fetcher = PricingFetcher::new(options.offline);
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone())
+        .pricing_fetcher(pricing_fetcher.clone())
+        .load()
+        .await?;
+
+    // JSON keeps its shape without usage: empty groups and zero totals
+    if entries.is_empty() && !options.json {
+        println!("No usage data found");
+        return Ok(());
+    }
+
+    info!("Analyzing cache usage...");
+    let output = render_report(
+        &options,
+        args.by_user,
+        entries,
+        |entries| {
+            let rows = analyze_cache(&entries, &pricing_fetcher, args.by, options.order);
+            let totals = cache_totals(&rows);
+            Ok(CacheReport {
+                group_by: args.by,
+                groups: rows,
+                totals,
+            })
+        },
+        |report| {
+            Ok(table::render_cache_table(
+                &report.groups,
+                &report.totals,
+                args.by.label(),
+                options.compact,
+            ))
+        },
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
+
+/// Cache statistics per group with their totals
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheReport {
+    group_by: GroupBy,
+    groups: Vec<CacheUsage>,
+    totals: CacheStats,
+}
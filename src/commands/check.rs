@@ -0,0 +1,80 @@
+use crate::commands::CheckArgs;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use colored::*;
+use rccusage::aggregation::IncrementalAggregate;
+use rccusage::config::Config;
+use rccusage::notifications::{Alert, Notifier, Severity};
+use rccusage::output::render_json;
+use rccusage::UsageLoader;
+use tracing::{info, warn};
+
+pub async fn run(args: CheckArgs, config: &Config) -> Result<()> {
+    let options = args.common.to_common_options();
+
+    let mut notifier = Notifier::from_config(config)?;
+    if let Some(state) = &args.state {
+        notifier = notifier.state_path(state);
+    }
+    if notifier.rules().is_empty() {
+        warn!("No thresholds configured; set notifications.tokenLimit, budget.daily or notifications.anomalies");
+    }
+
+    info!("Loading usage data...");
+    let mut aggregate = IncrementalAggregate::default();
+    aggregate.extend(UsageLoader::from_options(options.clone()).load().await?);
+    let now = Utc::now();
+
+    if args.dry_run {
+        let alerts = notifier.pending(&aggregate, now)?;
+        if options.json {
+            print!("{}", render_json(&alerts, options.jq.as_deref())?);
+        } else if alerts.is_empty() {
+            println!("No new alerts");
+        } else {
+            for alert in &alerts {
+                println!("{}", format_alert(alert));
+            }
+        }
+        return Ok(());
+    }
+
+    let summary = notifier.check(&aggregate, now).await?;
+
+    if options.json {
+        print!("{}", render_json(&summary, options.jq.as_deref())?);
+    } else if summary.alerts.is_empty() {
+        println!("No new alerts");
+    } else {
+        for alert in &summary.alerts {
+            println!("{}", format_alert(alert));
+        }
+        println!();
+        println!(
+            "Sent {} of {} webhook posts",
+            summary.delivered,
+            summary.delivered + summary.failures.len()
+        );
+        for failure in &summary.failures {
+            println!("{} {}: {}", "Failed:".red(), failure.key, failure.error);
+        }
+    }
+
+    // A non-zero exit lets cron report the failure; the next check retries it
+    if !summary.failures.is_empty() {
+        bail!(
+            "{} webhook deliveries failed and will be retried by the next check",
+            summary.failures.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn format_alert(alert: &Alert) -> String {
+    let severity = match alert.severity {
+        Severity::Critical => alert.severity.as_str().red().bold(),
+        Severity::Warning => alert.severity.as_str().yellow().bold(),
+    };
+    format!("{} {}: {}", severity, alert.title.bold(), alert.message)
+}
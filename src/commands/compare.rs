@@ -0,0 +1,68 @@
+use crate::commands::{render_report, CompareArgs};
+use anyhow::{bail, Result};
+use chrono::{Duration, Utc};
+use rccusage::analytics::compare::{compare_periods, DateRange};
+use rccusage::output::table;
+use rccusage::UsageLoader;
+use tracing::info;
+
+pub async fn run(args: CompareArgs) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    let until = options.until.unwrap_or_else(|| Utc::now().date_naive());
+    let since = options
+        .since
+        .unwrap_or(until - Duration::days(args.days - 1));
+    if since > until {
+        bail!("--since must not be after --until");
+    }
+
+    let current = DateRange::new(since, until);
+    let previous = current.preceding();
+
+    // Load both ranges at once
+    options.since = Some(previous.since);
+    options.until = Some(current.until);
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    // JSON keeps its shape without usage: empty rows and zero totals
+    if entries.is_empty() && !options.json {
+        println!("No usage data found");
+        return Ok(());
+    }
+
+    info!("Comparing periods...");
+    let output = render_report(
+        &options,
+        args.by_user,
+        entries,
+        |entries| Ok(compare_periods(&entries, current)),
+        |comparison| {
+            Ok(format!(
+                "{} to {} vs {} to {} ({} days)\n\n{}\n{}",
+                current.since,
+                current.until,
+                previous.since,
+                previous.until,
+                current.days(),
+                table::render_comparison_table(
+                    &comparison.models,
+                    &comparison.totals,
+                    "Model",
+                    options.compact
+                ),
+                table::render_comparison_table(
+                    &comparison.projects,
+                    &comparison.totals,
+                    "Project",
+                    options.compact
+                )
+            ))
+        },
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
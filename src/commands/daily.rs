@@ -1,13 +1,14 @@
-use crate::aggregation::aggregate_daily;
-use crate::commands::DailyArgs;
-use crate::data_loader::load_usage_entries;
-use crate::live::LiveMonitor;
-use crate::output::{output_json, table};
-use crate::pricing::PricingFetcher;
+use crate::commands::{render_report, DailyArgs};
 use anyhow::Result;
+use rccusage::aggregation::aggregate_daily;
+use rccusage::config::Config;
+use rccusage::output::chart::{render_usage_chart, Charset, ChartRow};
+use rccusage::output::{render_json, table};
+use rccusage::types::{CommonOptions, DailyUsage};
+use rccusage::UsageLoader;
 use tracing::info;
 
-pub async fn run(args: DailyArgs) -> Result<()> {
+pub async fn run(args: DailyArgs, config: &Config) -> Result<()> {
     let mut options = args.common.to_common_options();
 
     // If all_time flag is set, clear date filters
@@ -17,34 +18,18 @@ pub async fn run(args: DailyArgs) -> Result<()> {
     }
 
     // If watch mode is enabled, use live monitoring
-    if args.watch {
-        let monitor = LiveMonitor::new();
-        let args_clone = args.clone();
-
-        monitor.watch(move || {
-            let runtime = tokio::runtime::Runtime::new()?;
-            runtime.block_on(run_once(args_clone.clone()))
-        })?;
-    } else {
-        run_once(args).await?;
-    }
-
-    Ok(())
-}
-
-async fn run_once(args: DailyArgs) -> Result<()> {
-    let mut options = args.common.to_common_options();
-
-    // If all_time flag is set, clear date filters
-    if args.all_time {
-        options.since = None;
-        options.until = None;
+    if args.watch.watch {
+        return args
+            .watch
+            .to_monitor(config)?
+            .watch(&options, |aggregate| {
+                render(&args, &options, aggregate.daily(options.order))
+            })
+            .await;
     }
 
-    let pricing_fetcher = PricingFetcher::new(options.offline);
-
     info!("Loading usage data...");
-    let entries = load_usage_entries(&options, &pricing_fetcher).await?;
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
 
     if entries.is_empty() {
         if options.json {
@@ -56,23 +41,58 @@ async fn run_once(args: DailyArgs) -> Result<()> {
     }
 
     info!("Aggregating daily usage...");
+    if args.by_user {
+        print!(
+            "{}",
+            render_report(
+                &options,
+                true,
+                entries,
+                |entries| Ok(aggregate_daily(entries, options.order)),
+                |rows| render(&args, &options, rows),
+            )?
+        );
+        return Ok(());
+    }
+
     let daily_usage = aggregate_daily(entries, options.order);
 
+    print!("{}", render(&args, &options, daily_usage)?);
+
+    Ok(())
+}
+
+fn render(
+    args: &DailyArgs,
+    options: &CommonOptions,
+    daily_usage: Vec<DailyUsage>,
+) -> Result<String> {
     if daily_usage.is_empty() {
-        if options.json {
-            println!("[]");
+        return Ok(if options.json {
+            "[]\n".to_string()
         } else {
-            println!("No usage data found for the specified period");
-        }
-        return Ok(());
+            "No usage data found for the specified period\n".to_string()
+        });
     }
 
-    // Output results
+    // Render results
     if options.json {
-        output_json(&daily_usage, options.jq.as_deref())?;
+        render_json(&daily_usage, options.jq.as_deref())
+    } else if args.chart {
+        let rows: Vec<ChartRow> = daily_usage
+            .iter()
+            .map(|u| ChartRow {
+                label: u.date.to_string(),
+                total_tokens: u.total_tokens(),
+                model_breakdowns: &u.model_breakdowns,
+            })
+            .collect();
+        Ok(render_usage_chart(&rows, Charset::detect()))
     } else {
-        table::output_daily_table(&daily_usage, options.compact)?;
+        Ok(table::render_daily_table(
+            &daily_usage,
+            options.compact,
+            args.breakdown,
+        ))
     }
-
-    Ok(())
 }
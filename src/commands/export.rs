@@ -0,0 +1,97 @@
+use crate::commands::ExportArgs;
+use anyhow::{bail, Result};
+use rccusage::archive::{default_machine_label, local_user_label, write_archive};
+use rccusage::output::render_json;
+use rccusage::UsageLoader;
+use serde_json::json;
+use std::path::Path;
+use tracing::info;
+
+pub async fn run(args: ExportArgs) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // If all_time flag is set, clear date filters
+    if args.all_time {
+        options.since = None;
+        options.until = None;
+    }
+
+    let machine = args.machine.unwrap_or_else(default_machine_label);
+    let user = args
+        .as_user
+        .unwrap_or_else(|| local_user_label().to_string());
+
+    #[cfg(feature = "parquet")]
+    if let Some(dir) = args.output.as_deref() {
+        let format = args.format.unwrap_or_default();
+
+        info!("Loading usage data...");
+        let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+        info!("Writing {:?} dataset...", format);
+        let partitions = rccusage::columnar::write_dataset(
+            dir,
+            &entries,
+            options.since,
+            options.until,
+            format,
+            &machine,
+            &user,
+        )?;
+
+        if options.json {
+            print!("{}", render_json(&partitions, options.jq.as_deref())?);
+        } else {
+            for partition in &partitions {
+                println!("{}: {} rows", partition.path.display(), partition.rows);
+            }
+            println!(
+                "Exported {} entries from {}@{} to {} ({} months)",
+                partitions.iter().map(|p| p.rows).sum::<usize>(),
+                user,
+                machine,
+                dir.display(),
+                partitions.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let Some(path) = args.archive.as_deref() else {
+        if cfg!(feature = "parquet") {
+            bail!("Nothing to export: pass --archive <PATH> or --output <DIR>");
+        }
+        bail!("Nothing to export: pass --archive <PATH>");
+    };
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    info!("Writing archive...");
+    let written = write_archive(path, &entries, &machine, &user)?;
+
+    // Keep stdout clean when the archive itself goes there
+    if path == Path::new("-") {
+        return Ok(());
+    }
+
+    if options.json {
+        let output = json!({
+            "archive": path,
+            "machine": machine,
+            "user": user,
+            "entries": written,
+        });
+        print!("{}", render_json(&output, options.jq.as_deref())?);
+    } else {
+        println!(
+            "Exported {} entries from {}@{} to {}",
+            written,
+            user,
+            machine,
+            path.display()
+        );
+    }
+
+    Ok(())
+}
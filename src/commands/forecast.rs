@@ -0,0 +1,58 @@
+use crate::commands::{render_report, ForecastArgs};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use rccusage::aggregation::aggregate_daily;
+use rccusage::analytics::forecast::{forecast, ForecastPeriod};
+use rccusage::config::Config;
+use rccusage::output::table;
+use rccusage::types::SortOrder;
+use rccusage::UsageLoader;
+use std::collections::HashMap;
+use tracing::info;
+
+pub async fn run(args: ForecastArgs, config: &Config) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // --until moves the forecast date. Without --since, history is loaded back
+    // to the window or the start of the quarter, whichever is earlier
+    let as_of = options.until.unwrap_or_else(|| Utc::now().date_naive());
+    let (quarter_start, _) = ForecastPeriod::Quarter.bounds(as_of);
+    options.since = Some(
+        options
+            .since
+            .unwrap_or_else(|| quarter_start.min(as_of - Duration::days(args.window))),
+    );
+    options.until = Some(as_of);
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    // JSON keeps its shape without usage: a flat forecast with zero spend
+    if entries.is_empty() && !options.json {
+        println!("No usage data found");
+        return Ok(());
+    }
+
+    let mut budgets = HashMap::new();
+    if let Some(budget) = args.budget.or(config.budget.monthly) {
+        budgets.insert(ForecastPeriod::Month, budget);
+    }
+    if let Some(budget) = args.quarterly_budget.or(config.budget.quarterly) {
+        budgets.insert(ForecastPeriod::Quarter, budget);
+    }
+
+    info!("Fitting forecast...");
+    let output = render_report(
+        &options,
+        args.by_user,
+        entries,
+        |entries| {
+            let daily = aggregate_daily(entries, SortOrder::Asc);
+            Ok(forecast(&daily, as_of, args.method, args.window, &budgets))
+        },
+        |result| Ok(table::render_forecast_table(&result, options.compact)),
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
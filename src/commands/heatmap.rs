@@ -0,0 +1,44 @@
+use crate::commands::{render_report, HeatmapArgs};
+use anyhow::Result;
+use rccusage::analytics::heatmap::usage_heatmap;
+use rccusage::output::table;
+use rccusage::UsageLoader;
+use tracing::info;
+
+pub async fn run(args: HeatmapArgs) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // If all_time flag is set, clear date filters
+    if args.all_time {
+        options.since = None;
+        options.until = None;
+    }
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    // JSON keeps its shape without usage: every cell zero
+    if entries.is_empty() && !options.json {
+        println!("No usage data found");
+        return Ok(());
+    }
+
+    info!("Building heatmap...");
+    let timezone = args.common.time_zone();
+    let output = render_report(
+        &options,
+        args.by_user,
+        entries,
+        |entries| Ok(usage_heatmap(&entries, timezone)),
+        |heatmap| {
+            Ok(table::render_heatmap(
+                &heatmap,
+                args.metric,
+                options.compact,
+            ))
+        },
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
@@ -1,14 +1,47 @@
+pub mod anomalies;
+pub mod archive;
 pub mod blocks;
+pub mod cache;
+#[cfg(feature = "notifications")]
+pub mod check;
+pub mod compare;
 pub mod daily;
+pub mod export;
+pub mod forecast;
+pub mod heatmap;
 pub mod monthly;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod plan;
+#[cfg(feature = "sqlite")]
+pub mod query;
 pub mod session;
+pub mod simulate;
 pub mod statusline;
+pub mod team;
 pub mod weekly;
 
-use crate::types::{CommonOptions, CostMode, SortOrder};
 use anyhow::Result;
 use chrono::NaiveDate;
+use chrono_tz::Tz;
 use clap::{Parser, Subcommand};
+use colored::*;
+use rccusage::aggregation::{split_by_user, DEFAULT_IDLE_MINUTES};
+use rccusage::analytics::anomalies::{AnomalyMethod, MIN_HISTORY};
+use rccusage::analytics::forecast::ForecastMethod;
+use rccusage::analytics::heatmap::HeatmapMetric;
+use rccusage::analytics::timeline::TimelineGranularity;
+use rccusage::analytics::GroupBy;
+use rccusage::config::Config;
+use rccusage::live::LiveMonitor;
+use rccusage::output::render_json;
+use rccusage::types::{CommonOptions, CostMode, LabeledPath, LoadedUsageEntry, SortOrder};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
 
 /// Usage analysis tool for Claude Code
 #[derive(Parser, Debug)]
@@ -38,7 +71,52 @@ pub enum Commands {
     Blocks(BlocksArgs),
 
     /// Show compact status line (Beta)
+    ///
+    /// The status line describes the current session on this machine, so there
+    /// is no --by-user.
     Statusline(StatuslineArgs),
+
+    /// Show prompt cache hit ratio and savings
+    Cache(CacheArgs),
+
+    /// Re-price usage as other models or prices and compare with plans
+    Simulate(SimulateArgs),
+
+    /// Compare subscription plan prices with API-equivalent cost per month
+    Plan(PlanArgs),
+
+    /// Show usage by weekday and hour of day
+    Heatmap(HeatmapArgs),
+
+    /// Compare a date range with the preceding range of equal length
+    Compare(CompareArgs),
+
+    /// Project month-end and quarter-end spend from recent daily cost
+    Forecast(ForecastArgs),
+
+    /// List days and sessions with unusually high cost
+    Anomalies(AnomaliesArgs),
+
+    /// Write usage entries to a portable archive
+    Export(ExportArgs),
+
+    /// Manage the local history that outlives transcript cleanup
+    Archive(ArchiveArgs),
+
+    /// Summarize usage per user with totals and model mix
+    Team(TeamArgs),
+
+    /// Ingest usage into a local SQLite history and run SQL against it
+    #[cfg(feature = "sqlite")]
+    Query(QueryArgs),
+
+    /// Send usage to an OpenTelemetry collector as OTLP metrics and logs
+    #[cfg(feature = "otlp")]
+    Otlp(OtlpArgs),
+
+    /// Send webhook alerts for thresholds crossed since the last check, e.g. from cron
+    #[cfg(feature = "notifications")]
+    Check(CheckArgs),
 }
 
 /// Common arguments shared across commands
@@ -83,6 +161,26 @@ pub struct CommonArgs {
     /// Force compact display mode (auto-detected by default)
     #[arg(long)]
     pub compact: bool,
+
+    /// Also load usage from this archive written by `export --archive`, as [USER=]PATH (repeatable)
+    #[arg(
+        long = "data-file",
+        visible_alias = "import",
+        value_name = "[USER=]PATH"
+    )]
+    pub data_files: Vec<LabeledPath>,
+
+    /// Also load this Claude data directory, as [USER=]PATH (repeatable)
+    #[arg(long = "data-dir", value_name = "[USER=]PATH")]
+    pub data_dirs: Vec<LabeledPath>,
+
+    /// Only include usage belonging to this user
+    #[arg(long)]
+    pub user: Option<String>,
+
+    /// Ignore the history kept by `archive sync`; read only the live transcripts
+    #[arg(long)]
+    pub no_history: bool,
 }
 
 impl CommonArgs {
@@ -97,7 +195,61 @@ impl CommonArgs {
             project: self.project.clone(),
             jq: self.jq.clone(),
             compact: self.compact,
+            data_files: self.data_files.clone(),
+            data_dirs: self.data_dirs.clone(),
+            user: self.user.clone(),
+            no_history: self.no_history,
+        }
+    }
+
+    /// The `--timezone` (or `TZ`) zone, or `None` to use the system's local time
+    ///
+    /// `TZ` may hold a POSIX rule rather than an IANA name; those fall back to local time.
+    pub fn time_zone(&self) -> Option<Tz> {
+        let name = self.timezone.as_deref()?.trim_start_matches(':');
+        match name.parse::<Tz>() {
+            Ok(tz) => Some(tz),
+            Err(_) => {
+                warn!("Unknown time zone '{}', using local time", name);
+                None
+            }
+        }
+    }
+}
+
+/// Live monitoring arguments shared by report commands
+#[derive(Parser, Debug, Clone)]
+pub struct WatchArgs {
+    /// Enable live monitoring mode (watch for file changes)
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Refresh interval in seconds for live monitoring
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub refresh_interval: u64,
+
+    /// Don't send the webhook alerts configured under `notifications` while watching
+    #[cfg(feature = "notifications")]
+    #[arg(long)]
+    pub no_notify: bool,
+}
+
+impl WatchArgs {
+    /// Build a live monitor using the configured refresh interval
+    ///
+    /// Configured webhooks are notified while watching unless `--no-notify` is given.
+    #[cfg_attr(not(feature = "notifications"), allow(unused_variables))]
+    pub fn to_monitor(&self, config: &Config) -> Result<LiveMonitor> {
+        let monitor =
+            LiveMonitor::new().with_refresh_interval(Duration::from_secs(self.refresh_interval));
+
+        #[cfg(feature = "notifications")]
+        if !self.no_notify && !config.notifications.webhooks.is_empty() {
+            let notifier = rccusage::notifications::Notifier::from_config(config)?;
+            return Ok(monitor.with_notifier(notifier));
         }
+
+        Ok(monitor)
     }
 }
 
@@ -111,13 +263,24 @@ pub struct DailyArgs {
     #[arg(long)]
     pub by_project: bool,
 
+    /// Show a separate report for each user (not with --watch, which follows one combined report)
+    #[arg(long, conflicts_with = "watch")]
+    pub by_user: bool,
+
     /// Show all daily data ever (no date filtering)
     #[arg(long)]
     pub all_time: bool,
 
-    /// Enable live monitoring mode (watch for file changes)
+    /// Show per-model rows under each period
     #[arg(long)]
-    pub watch: bool,
+    pub breakdown: bool,
+
+    /// Draw cost per period as bars stacked by model family, with a token sparkline
+    #[arg(long, conflicts_with_all = ["json", "breakdown"])]
+    pub chart: bool,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
 }
 
 /// Arguments for monthly command
@@ -130,9 +293,24 @@ pub struct MonthlyArgs {
     #[arg(long)]
     pub by_project: bool,
 
+    /// Show a separate report for each user (not with --watch, which follows one combined report)
+    #[arg(long, conflicts_with = "watch")]
+    pub by_user: bool,
+
     /// Show all monthly data ever (no date filtering)
     #[arg(long)]
     pub all_time: bool,
+
+    /// Show per-model rows under each period
+    #[arg(long)]
+    pub breakdown: bool,
+
+    /// Draw cost per period as bars stacked by model family, with a token sparkline
+    #[arg(long, conflicts_with_all = ["json", "breakdown"])]
+    pub chart: bool,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
 }
 
 /// Arguments for weekly command
@@ -145,9 +323,24 @@ pub struct WeeklyArgs {
     #[arg(long)]
     pub by_project: bool,
 
+    /// Show a separate report for each user (not with --watch, which follows one combined report)
+    #[arg(long, conflicts_with = "watch")]
+    pub by_user: bool,
+
     /// Show all weekly data ever (no date filtering)
     #[arg(long)]
     pub all_time: bool,
+
+    /// Show per-model rows under each period
+    #[arg(long)]
+    pub breakdown: bool,
+
+    /// Draw cost per period as bars stacked by model family, with a token sparkline
+    #[arg(long, conflicts_with_all = ["json", "breakdown"])]
+    pub chart: bool,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
 }
 
 /// Arguments for session command
@@ -156,6 +349,10 @@ pub struct SessionArgs {
     #[command(flatten)]
     pub common: CommonArgs,
 
+    /// Show a separate report for each user (not with --watch, which follows one combined report, or --id)
+    #[arg(long, conflicts_with_all = ["watch", "id"])]
+    pub by_user: bool,
+
     /// Show only sessions with activity in last N days
     #[arg(long)]
     pub recent_days: Option<u32>,
@@ -163,6 +360,327 @@ pub struct SessionArgs {
     /// Show all sessions ever (no date filtering)
     #[arg(long, conflicts_with = "recent_days")]
     pub all_time: bool,
+
+    /// Show a usage timeline for one session (full ID or unique prefix)
+    #[arg(long, conflicts_with = "watch")]
+    pub id: Option<String>,
+
+    /// Timeline resolution for --id
+    #[arg(long, value_enum, default_value_t = TimelineGranularity::Hour)]
+    pub timeline: TimelineGranularity,
+
+    /// Gaps between messages longer than this many minutes don't count as active time
+    #[arg(long, default_value_t = DEFAULT_IDLE_MINUTES, value_parser = clap::value_parser!(i64).range(1..))]
+    pub idle_minutes: i64,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
+}
+
+/// Arguments for cache command
+#[derive(Parser, Debug)]
+pub struct CacheArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Show a separate report for each user
+    #[arg(long)]
+    pub by_user: bool,
+
+    /// Group cache statistics by day, session or project
+    #[arg(long, value_enum, default_value_t = GroupBy::Day)]
+    pub by: GroupBy,
+
+    /// Include all data ever (no date filtering)
+    #[arg(long)]
+    pub all_time: bool,
+}
+
+/// Arguments for simulate command
+#[derive(Parser, Debug)]
+pub struct SimulateArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Show a separate report for each user
+    #[arg(long)]
+    pub by_user: bool,
+
+    /// Price all usage as this model (e.g. claude-sonnet-4-5)
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// Price models containing PATTERN as MODEL, e.g. opus=claude-sonnet-4-5 (repeatable)
+    #[arg(long, value_parser = parse_model_mapping)]
+    pub map: Vec<(String, String)>,
+
+    /// JSON price table (USD per 1M tokens) overriding bundled pricing
+    #[arg(long)]
+    pub pricing_file: Option<PathBuf>,
+
+    /// Group results by day, session or project
+    #[arg(long, value_enum, default_value_t = GroupBy::Day)]
+    pub by: GroupBy,
+
+    /// Include all data ever (no date filtering)
+    #[arg(long)]
+    pub all_time: bool,
+}
+
+/// Arguments for plan command
+#[derive(Parser, Debug)]
+pub struct PlanArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Show a separate report for each user
+    #[arg(long)]
+    pub by_user: bool,
+
+    /// Plan to evaluate (Pro, Max5x, Max20x or a plan from the config file) [default: all]
+    #[arg(long)]
+    pub plan: Option<String>,
+
+    /// Monthly price in USD, for a custom plan or to override --plan's price
+    #[arg(long, value_parser = parse_usd)]
+    pub monthly_cost: Option<Decimal>,
+
+    /// Include all data ever (no date filtering)
+    #[arg(long)]
+    pub all_time: bool,
+}
+
+/// Arguments for heatmap command
+#[derive(Parser, Debug)]
+pub struct HeatmapArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Show a separate report for each user
+    #[arg(long)]
+    pub by_user: bool,
+
+    /// Value used to shade the grid
+    #[arg(long, value_enum, default_value_t = HeatmapMetric::Cost)]
+    pub metric: HeatmapMetric,
+
+    /// Include all data ever (no date filtering)
+    #[arg(long)]
+    pub all_time: bool,
+}
+
+/// Arguments for compare command
+#[derive(Parser, Debug)]
+pub struct CompareArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Show a separate report for each user
+    #[arg(long)]
+    pub by_user: bool,
+
+    /// Length of the range in days when --since is not given; ends at --until or today
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(i64).range(1..))]
+    pub days: i64,
+}
+
+/// Arguments for forecast command
+#[derive(Parser, Debug)]
+pub struct ForecastArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Show a separate report for each user
+    #[arg(long)]
+    pub by_user: bool,
+
+    /// How daily cost is extrapolated
+    #[arg(long, value_enum, default_value_t = ForecastMethod::Linear)]
+    pub method: ForecastMethod,
+
+    /// Number of completed days the trend is fitted over
+    #[arg(long, default_value_t = 28, value_parser = clap::value_parser!(i64).range(2..))]
+    pub window: i64,
+
+    /// Monthly budget in USD, overriding the config file
+    #[arg(long, value_parser = parse_usd)]
+    pub budget: Option<Decimal>,
+
+    /// Quarterly budget in USD, overriding the config file
+    #[arg(long, value_parser = parse_usd)]
+    pub quarterly_budget: Option<Decimal>,
+}
+
+/// Arguments for anomalies command
+#[derive(Parser, Debug)]
+pub struct AnomaliesArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Show a separate report for each user
+    #[arg(long)]
+    pub by_user: bool,
+
+    /// Outlier score: median absolute deviation or standard z-score
+    #[arg(long, value_enum, default_value_t = AnomalyMethod::Mad)]
+    pub method: AnomalyMethod,
+
+    /// Score above which a day or session is reported [default: 3.5 for mad, 3 for zscore]
+    #[arg(long)]
+    pub threshold: Option<f64>,
+
+    /// Number of preceding days or sessions each one is compared with
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(MIN_HISTORY as u64..))]
+    pub history: u64,
+
+    /// Ignore outliers costing less than this many USD
+    #[arg(long, default_value = "1", value_parser = parse_usd)]
+    pub min_cost: Decimal,
+}
+
+/// Arguments for export command
+#[derive(Parser, Debug)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Write a usage archive to this file ("-" for stdout)
+    #[arg(long, value_name = "PATH")]
+    pub archive: Option<PathBuf>,
+
+    /// Write a columnar dataset to this directory, one file per month
+    #[cfg(feature = "parquet")]
+    #[arg(long, value_name = "DIR", conflicts_with = "archive")]
+    pub output: Option<PathBuf>,
+
+    /// File format of the --output dataset [default: parquet]
+    #[cfg(feature = "parquet")]
+    #[arg(long, value_enum, requires = "output")]
+    pub format: Option<rccusage::columnar::ColumnarFormat>,
+
+    /// Label recorded for this machine's entries [default: hostname]
+    #[arg(long)]
+    pub machine: Option<String>,
+
+    /// Label recorded as the user of this machine's entries [default: $USER]
+    #[arg(long)]
+    pub as_user: Option<String>,
+
+    /// Include all data ever (no date filtering)
+    #[arg(long)]
+    pub all_time: bool,
+}
+
+/// Arguments for query command
+#[cfg(feature = "sqlite")]
+#[derive(Parser, Debug)]
+pub struct QueryArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// SQL statement to run; without one, only ingest new usage
+    pub sql: Option<String>,
+
+    /// Usage history database [default: ~/.config/ccusage/usage.db]
+    #[arg(long, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+
+    /// Query the database as it is, without ingesting new usage first
+    #[arg(long, conflicts_with_all = ["data_files", "data_dirs"])]
+    pub no_ingest: bool,
+}
+
+/// Arguments for archive command
+#[derive(Parser, Debug)]
+pub struct ArchiveArgs {
+    #[command(subcommand)]
+    pub command: ArchiveCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ArchiveCommand {
+    /// Copy usage from the Claude data directories into the local history
+    Sync(ArchiveSyncArgs),
+}
+
+/// Arguments for archive sync
+#[derive(Parser, Debug)]
+pub struct ArchiveSyncArgs {
+    /// Output format as JSON instead of text
+    #[arg(long)]
+    pub json: bool,
+
+    /// Cost calculation mode for newly stored entries
+    #[arg(long, value_enum, default_value_t = CostMode::Auto)]
+    pub mode: CostMode,
+
+    /// Use offline pricing only
+    #[arg(long)]
+    pub offline: bool,
+}
+
+/// Arguments for otlp command
+#[cfg(feature = "otlp")]
+#[derive(Parser, Debug)]
+pub struct OtlpArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Collector base URL; /v1/metrics and /v1/logs are appended [default: http://localhost:4318]
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    pub endpoint: Option<String>,
+
+    /// Header sent with every export as NAME=VALUE, e.g. an API key (repeatable)
+    #[arg(long = "header", value_name = "NAME=VALUE", value_parser = parse_header)]
+    pub headers: Vec<(String, String)>,
+
+    /// Signals to send
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [rccusage::otlp::OtlpSignal::Metrics, rccusage::otlp::OtlpSignal::Logs]
+    )]
+    pub signals: Vec<rccusage::otlp::OtlpSignal>,
+
+    /// Keep running and export requests as they are written
+    #[arg(long)]
+    pub follow: bool,
+
+    /// With --follow, first export the usage already written
+    #[arg(long, requires = "follow")]
+    pub backfill: bool,
+
+    /// Seconds between retries of failed exports with --follow
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub retry_interval: u64,
+}
+
+/// Arguments for check command
+#[cfg(feature = "notifications")]
+#[derive(Parser, Debug)]
+pub struct CheckArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// List the alerts that would be sent without sending or recording them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// File recording which alerts were sent [default: ~/.config/ccusage/notifications.json]
+    #[arg(long, value_name = "PATH")]
+    pub state: Option<PathBuf>,
+}
+
+/// Arguments for team command
+#[derive(Parser, Debug)]
+pub struct TeamArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    /// Include all data ever (no date filtering)
+    #[arg(long)]
+    pub all_time: bool,
 }
 
 /// Arguments for blocks command
@@ -171,6 +689,10 @@ pub struct BlocksArgs {
     #[command(flatten)]
     pub common: CommonArgs,
 
+    /// Show a separate report for each user (not with --watch or --live, which follow one combined report)
+    #[arg(long, conflicts_with_all = ["watch", "live"])]
+    pub by_user: bool,
+
     /// Show only the active block with projections
     #[arg(long)]
     pub active: bool,
@@ -182,6 +704,13 @@ pub struct BlocksArgs {
     /// Token limit for quota warnings (number or "max")
     #[arg(long, value_parser = parse_token_limit)]
     pub token_limit: Option<u64>,
+
+    /// Show a full-screen live dashboard for the active block
+    #[arg(long, conflicts_with_all = ["json", "watch"])]
+    pub live: bool,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
 }
 
 /// Arguments for statusline command
@@ -190,24 +719,96 @@ pub struct StatuslineArgs {
     #[command(flatten)]
     pub common: CommonArgs,
 
-    /// Format for statusline output
-    #[arg(long, default_value = "compact")]
-    pub format: String,
+    /// Output format: compact, minimal, tokens, a config preset name, or a template
+    /// such as "{model} {block_cost} ({time_left})" [default: compact]
+    #[arg(long)]
+    pub format: Option<String>,
+
+    /// Token limit for block percentage and colors (or "max" for the busiest recent block)
+    #[arg(long, value_parser = parse_token_limit)]
+    pub token_limit: Option<u64>,
+
+    #[command(flatten)]
+    pub watch: WatchArgs,
 }
 
 impl Cli {
-    pub async fn run(self) -> Result<()> {
+    pub async fn run(self, config: &Config) -> Result<()> {
         match self.command {
-            Commands::Daily(args) => daily::run(args).await,
-            Commands::Monthly(args) => monthly::run(args).await,
-            Commands::Weekly(args) => weekly::run(args).await,
-            Commands::Session(args) => session::run(args).await,
-            Commands::Blocks(args) => blocks::run(args).await,
-            Commands::Statusline(args) => statusline::run(args).await,
+            Commands::Daily(args) => daily::run(args, config).await,
+            Commands::Monthly(args) => monthly::run(args, config).await,
+            Commands::Weekly(args) => weekly::run(args, config).await,
+            Commands::Session(args) => session::run(args, config).await,
+            Commands::Blocks(args) => blocks::run(args, config).await,
+            Commands::Statusline(args) => statusline::run(args, config).await,
+            Commands::Cache(args) => cache::run(args).await,
+            Commands::Simulate(args) => simulate::run(args, config).await,
+            Commands::Plan(args) => plan::run(args, config).await,
+            Commands::Heatmap(args) => heatmap::run(args).await,
+            Commands::Compare(args) => compare::run(args).await,
+            Commands::Forecast(args) => forecast::run(args, config).await,
+            Commands::Anomalies(args) => anomalies::run(args).await,
+            Commands::Export(args) => export::run(args).await,
+            Commands::Archive(args) => archive::run(args).await,
+            Commands::Team(args) => team::run(args).await,
+            #[cfg(feature = "sqlite")]
+            Commands::Query(args) => query::run(args).await,
+            #[cfg(feature = "otlp")]
+            Commands::Otlp(args) => otlp::run(args, config).await,
+            #[cfg(feature = "notifications")]
+            Commands::Check(args) => check::run(args, config).await,
         }
     }
 }
 
+/// Build a report and render it as JSON or a table, separately for each user with `by_user`
+///
+/// Every report has the same per-user JSON shape: one list of
+/// `{"user": ..., "report": ...}` objects, where `report` is what the command
+/// prints without `--by-user`. Tables are printed one per user under a heading.
+fn render_report<T: Serialize>(
+    options: &CommonOptions,
+    by_user: bool,
+    entries: Vec<LoadedUsageEntry>,
+    report: impl Fn(Vec<LoadedUsageEntry>) -> Result<T>,
+    render_table: impl Fn(T) -> Result<String>,
+) -> Result<String> {
+    if !by_user {
+        let report = report(entries)?;
+        return if options.json {
+            render_json(&report, options.jq.as_deref())
+        } else {
+            render_table(report)
+        };
+    }
+
+    let mut users = split_by_user(entries);
+    if users.is_empty() {
+        // Without usage, report nothing for the selected or local user
+        let user = options
+            .user
+            .clone()
+            .unwrap_or_else(|| rccusage::archive::local_user_label().to_string());
+        users.insert(user, Vec::new());
+    }
+
+    if options.json {
+        let reports = users
+            .into_iter()
+            .map(|(user, entries)| Ok(json!({ "user": user, "report": report(entries)? })))
+            .collect::<Result<Vec<_>>>()?;
+        return render_json(&reports, options.jq.as_deref());
+    }
+
+    let mut output = String::new();
+    for (user, entries) in users {
+        output.push_str(&format!("{} {}\n", "User:".bold(), user.cyan()));
+        output.push_str(&render_table(report(entries)?)?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
 /// Parse date from YYYYMMDD format
 fn parse_date(s: &str) -> Result<NaiveDate, String> {
     NaiveDate::parse_from_str(s, "%Y%m%d")
@@ -223,3 +824,170 @@ fn parse_token_limit(s: &str) -> Result<u64, String> {
             .map_err(|e| format!("Invalid token limit: {}", e))
     }
 }
+
+/// Parse PATTERN=MODEL model mapping
+fn parse_model_mapping(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((pattern, model)) if !pattern.is_empty() && !model.is_empty() => {
+            Ok((pattern.to_string(), model.to_string()))
+        }
+        _ => Err(format!("Invalid mapping '{}'. Use PATTERN=MODEL", s)),
+    }
+}
+
+/// Parse a NAME=VALUE header
+#[cfg(feature = "otlp")]
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("Invalid header '{}'. Use NAME=VALUE", s)),
+    }
+}
+
+/// Parse a non-negative USD amount
+fn parse_usd(s: &str) -> Result<Decimal, String> {
+    match s.trim_start_matches('$').parse::<Decimal>() {
+        Ok(amount) if amount >= Decimal::ZERO => Ok(amount),
+        Ok(_) => Err("Amount must not be negative".to_string()),
+        Err(e) => Err(format!("Invalid amount: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rccusage::types::{ModelName, TokenCounts};
+
+    fn entry(user: Option<&str>, cost: i64) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap(),
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: 100,
+                ..TokenCounts::default()
+            },
+            cost: Decimal::new(cost, 0),
+            session_id: None,
+            request_id: None,
+            message_id: None,
+            project: None,
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: user.map(String::from),
+        }
+    }
+
+    fn total_cost(entries: Vec<LoadedUsageEntry>) -> Result<Decimal> {
+        Ok(entries.iter().map(|e| e.cost).sum())
+    }
+
+    fn entries() -> Vec<LoadedUsageEntry> {
+        vec![
+            entry(Some("bob"), 2),
+            entry(Some("alice"), 1),
+            entry(Some("bob"), 3),
+        ]
+    }
+
+    #[test]
+    fn render_report_for_everyone() {
+        let options = CommonOptions::default();
+        let output = render_report(&options, false, entries(), total_cost, |c| {
+            Ok(format!("{}\n", c))
+        })
+        .unwrap();
+        assert_eq!(output, "6\n");
+    }
+
+    #[test]
+    fn render_report_tables_per_user() {
+        colored::control::set_override(false);
+        let options = CommonOptions::default();
+
+        let output = render_report(&options, true, entries(), total_cost, |c| {
+            Ok(format!("{}\n", c))
+        })
+        .unwrap();
+        assert_eq!(output, "User: alice\n1\n\nUser: bob\n5\n\n");
+    }
+
+    #[test]
+    fn render_report_json_per_user() {
+        let options = CommonOptions {
+            json: true,
+            ..CommonOptions::default()
+        };
+
+        let output =
+            render_report(&options, true, entries(), total_cost, |_| unreachable!()).unwrap();
+        let reports: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            reports,
+            json!([
+                { "user": "alice", "report": "1" },
+                { "user": "bob", "report": "5" },
+            ])
+        );
+    }
+
+    #[test]
+    fn render_report_json_per_user_without_usage() {
+        let options = CommonOptions {
+            json: true,
+            user: Some("alice".to_string()),
+            ..CommonOptions::default()
+        };
+
+        let output =
+            render_report(&options, true, Vec::new(), total_cost, |_| unreachable!()).unwrap();
+        let reports: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(reports, json!([{ "user": "alice", "report": "0" }]));
+    }
+
+    /// Per-user JSON from `render_report`, checked for the `{user, report}` shape
+    fn reports_by_user<T: Serialize>(
+        report: impl Fn(Vec<LoadedUsageEntry>) -> Result<T>,
+    ) -> Vec<serde_json::Value> {
+        let options = CommonOptions {
+            json: true,
+            ..CommonOptions::default()
+        };
+        let output = render_report(&options, true, entries(), report, |_| unreachable!()).unwrap();
+
+        let reports: Vec<serde_json::Value> = serde_json::from_str(&output).unwrap();
+        for report in &reports {
+            let keys: Vec<&String> = report.as_object().unwrap().keys().collect();
+            assert_eq!(keys, ["report", "user"]);
+        }
+        reports
+    }
+
+    #[test]
+    fn period_and_analytics_reports_share_the_per_user_shape() {
+        let daily = reports_by_user(|entries| {
+            Ok(rccusage::aggregation::aggregate_daily(
+                entries,
+                SortOrder::Asc,
+            ))
+        });
+        assert_eq!(daily[0]["user"], "alice");
+        assert_eq!(daily[0]["report"][0]["date"], "2026-10-18");
+        assert_eq!(daily[1]["report"][0]["totalCost"], "5");
+        assert!(daily[1]["report"][0].get("user").is_none());
+
+        let heatmap = reports_by_user(|entries| {
+            Ok(rccusage::analytics::heatmap::usage_heatmap(
+                &entries,
+                Some(Tz::UTC),
+            ))
+        });
+        assert_eq!(heatmap[1]["user"], "bob");
+        assert!(heatmap[1]["report"].is_object());
+    }
+}
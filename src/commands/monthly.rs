@@ -1,12 +1,14 @@
-use crate::aggregation::aggregate_monthly;
-use crate::commands::MonthlyArgs;
-use crate::data_loader::load_usage_entries;
-use crate::output::{output_json, table};
-use crate::pricing::PricingFetcher;
+use crate::commands::{render_report, MonthlyArgs};
 use anyhow::Result;
+use rccusage::aggregation::aggregate_monthly;
+use rccusage::config::Config;
+use rccusage::output::chart::{render_usage_chart, Charset, ChartRow};
+use rccusage::output::{render_json, table};
+use rccusage::types::{CommonOptions, MonthlyUsage};
+use rccusage::UsageLoader;
 use tracing::info;
 
-pub async fn run(args: MonthlyArgs) -> Result<()> {
+pub async fn run(args: MonthlyArgs, config: &Config) -> Result<()> {
     let mut options = args.common.to_common_options();
 
     // If all_time flag is set, clear date filters
@@ -15,10 +17,18 @@ pub async fn run(args: MonthlyArgs) -> Result<()> {
         options.until = None;
     }
 
-    let pricing_fetcher = PricingFetcher::new(options.offline);
+    if args.watch.watch {
+        return args
+            .watch
+            .to_monitor(config)?
+            .watch(&options, |aggregate| {
+                render(&args, &options, aggregate.monthly(options.order))
+            })
+            .await;
+    }
 
     info!("Loading usage data...");
-    let entries = load_usage_entries(&options, &pricing_fetcher).await?;
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
 
     if entries.is_empty() {
         if options.json {
@@ -30,23 +40,58 @@ pub async fn run(args: MonthlyArgs) -> Result<()> {
     }
 
     info!("Aggregating monthly usage...");
+    if args.by_user {
+        print!(
+            "{}",
+            render_report(
+                &options,
+                true,
+                entries,
+                |entries| Ok(aggregate_monthly(entries, options.order)),
+                |rows| render(&args, &options, rows),
+            )?
+        );
+        return Ok(());
+    }
+
     let monthly_usage = aggregate_monthly(entries, options.order);
 
+    print!("{}", render(&args, &options, monthly_usage)?);
+
+    Ok(())
+}
+
+fn render(
+    args: &MonthlyArgs,
+    options: &CommonOptions,
+    monthly_usage: Vec<MonthlyUsage>,
+) -> Result<String> {
     if monthly_usage.is_empty() {
-        if options.json {
-            println!("[]");
+        return Ok(if options.json {
+            "[]\n".to_string()
         } else {
-            println!("No usage data found for the specified period");
-        }
-        return Ok(());
+            "No usage data found for the specified period\n".to_string()
+        });
     }
 
-    // Output results
+    // Render results
     if options.json {
-        output_json(&monthly_usage, options.jq.as_deref())?;
+        render_json(&monthly_usage, options.jq.as_deref())
+    } else if args.chart {
+        let rows: Vec<ChartRow> = monthly_usage
+            .iter()
+            .map(|u| ChartRow {
+                label: u.date.to_string(),
+                total_tokens: u.total_tokens(),
+                model_breakdowns: &u.model_breakdowns,
+            })
+            .collect();
+        Ok(render_usage_chart(&rows, Charset::detect()))
     } else {
-        table::output_monthly_table(&monthly_usage, options.compact)?;
+        Ok(table::render_monthly_table(
+            &monthly_usage,
+            options.compact,
+            args.breakdown,
+        ))
     }
-
-    Ok(())
 }
@@ -0,0 +1,155 @@
+use crate::commands::OtlpArgs;
+use anyhow::Result;
+use chrono::Local;
+use rccusage::config::Config;
+use rccusage::live::LiveMonitor;
+use rccusage::otlp::{OtlpExporter, OtlpSignal, DEFAULT_ENDPOINT};
+use rccusage::output::render_json;
+use rccusage::types::{CommonOptions, LoadedUsageEntry};
+use rccusage::UsageLoader;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub async fn run(args: OtlpArgs, config: &Config) -> Result<()> {
+    let options = args.common.to_common_options();
+
+    let endpoint = args
+        .endpoint
+        .clone()
+        .or_else(|| config.otlp.endpoint.clone())
+        .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string());
+
+    // Config headers first, then OTEL_EXPORTER_OTLP_HEADERS, then --header
+    let env_headers =
+        parse_env_headers(&std::env::var("OTEL_EXPORTER_OTLP_HEADERS").unwrap_or_default());
+
+    let exporter = config
+        .otlp
+        .headers
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .chain(env_headers)
+        .chain(args.headers.iter().cloned())
+        .fold(
+            OtlpExporter::new(endpoint).signals(args.signals.clone()),
+            |exporter, (name, value)| exporter.header(name, value),
+        );
+
+    if args.follow {
+        return follow(exporter, &options, &args).await;
+    }
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    info!("Exporting to {}...", exporter.endpoint());
+    let exported = exporter.export(&entries).await?;
+
+    if options.json {
+        let output = json!({
+            "endpoint": exporter.endpoint(),
+            "signals": args.signals,
+            "entries": exported,
+        });
+        print!("{}", render_json(&output, options.jq.as_deref())?);
+    } else {
+        println!("Exported {} requests to {}", exported, exporter.endpoint());
+    }
+
+    Ok(())
+}
+
+/// Parse `OTEL_EXPORTER_OTLP_HEADERS`: comma-separated NAME=VALUE pairs
+fn parse_env_headers(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+/// Export new requests as they are written, retrying failed batches
+async fn follow(exporter: OtlpExporter, options: &CommonOptions, args: &OtlpArgs) -> Result<()> {
+    let monitor =
+        LiveMonitor::new().with_refresh_interval(Duration::from_secs(args.retry_interval));
+
+    println!(
+        "Exporting new requests to {} · Ctrl+C to exit",
+        exporter.endpoint()
+    );
+
+    // Requests not yet accepted by the collector, per signal so a signal that
+    // went through is not sent again when another fails
+    let pending: Arc<Mutex<HashMap<OtlpSignal, Vec<LoadedUsageEntry>>>> = Arc::default();
+    let mut first = true;
+
+    monitor
+        .follow(options, |entries| {
+            // The first call holds the usage written before we started
+            if !std::mem::take(&mut first) || args.backfill {
+                let mut pending = pending.lock().unwrap();
+                for signal in exporter.selected_signals() {
+                    pending.entry(*signal).or_default().extend(entries.clone());
+                }
+            }
+
+            let pending = pending.clone();
+            let exporter = exporter.clone();
+            async move {
+                let queued = std::mem::take(&mut *pending.lock().unwrap());
+
+                for (signal, batch) in queued {
+                    if batch.is_empty() {
+                        continue;
+                    }
+
+                    match exporter.export_signal(signal, &batch).await {
+                        Ok(exported) => println!(
+                            "{} exported {} requests as {}",
+                            Local::now().format("%H:%M:%S"),
+                            exported,
+                            signal.name()
+                        ),
+                        Err(partial) => {
+                            warn!(
+                                "Export of {} failed, retrying later: {}",
+                                signal.name(),
+                                partial
+                            );
+                            // Requeue what was not accepted ahead of newer requests
+                            let mut pending = pending.lock().unwrap();
+                            let newer = pending.remove(&signal).unwrap_or_default();
+                            let mut rest = batch;
+                            rest.drain(..partial.exported);
+                            rest.extend(newer);
+                            pending.insert(signal, rest);
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_env_headers() {
+        assert_eq!(
+            parse_env_headers("x-api-key=abc, Authorization = Bearer t=1,,=skipped,bad"),
+            [
+                ("x-api-key".to_string(), "abc".to_string()),
+                ("Authorization".to_string(), "Bearer t=1".to_string()),
+            ]
+        );
+        assert!(parse_env_headers("").is_empty());
+    }
+}
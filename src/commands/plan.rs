@@ -0,0 +1,78 @@
+use crate::commands::{render_report, PlanArgs};
+use anyhow::{bail, Result};
+use rccusage::aggregation::{aggregate_daily, aggregate_monthly};
+use rccusage::analytics::plans::{find_plan, plan_values, resolve_plans, SubscriptionPlan};
+use rccusage::config::Config;
+use rccusage::output::table;
+use rccusage::types::SortOrder;
+use rccusage::UsageLoader;
+use tracing::info;
+
+pub async fn run(args: PlanArgs, config: &Config) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // If all_time flag is set, clear date filters
+    if args.all_time {
+        options.since = None;
+        options.until = None;
+    }
+
+    let plans = selected_plans(&args, config)?;
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    if entries.is_empty() {
+        if options.json {
+            println!("[]");
+        } else {
+            println!("No usage data found");
+        }
+        return Ok(());
+    }
+
+    info!("Comparing plans...");
+    let output = render_report(
+        &options,
+        args.by_user,
+        entries,
+        |entries| {
+            let daily = aggregate_daily(entries.clone(), SortOrder::Asc);
+            let monthly = aggregate_monthly(entries, options.order);
+            Ok(plan_values(&monthly, &daily, &plans))
+        },
+        |values| Ok(table::render_plan_value_table(&values, options.compact)),
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
+
+/// Plans to report on: an explicit or configured plan, or every known plan
+fn selected_plans(args: &PlanArgs, config: &Config) -> Result<Vec<SubscriptionPlan>> {
+    let plans = resolve_plans(&config.plans);
+    let name = args.plan.as_deref().or(config.plan.as_deref());
+
+    match (name, args.monthly_cost) {
+        (name, Some(cost)) => {
+            // Keep the canonical name when overriding a known plan's price
+            let name = name.map_or("Custom", |n| {
+                find_plan(&plans, n).map_or(n, |p| p.name.as_str())
+            });
+            Ok(vec![SubscriptionPlan::new(name, cost)])
+        }
+        (Some(name), None) => match find_plan(&plans, name) {
+            Some(plan) => Ok(vec![plan.clone()]),
+            None => bail!(
+                "Unknown plan '{}'. Available: {}. Use --monthly-cost for a custom plan",
+                name,
+                plans
+                    .iter()
+                    .map(|p| p.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        },
+        (None, None) => Ok(plans),
+    }
+}
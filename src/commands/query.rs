@@ -0,0 +1,101 @@
+use crate::commands::QueryArgs;
+use anyhow::Result;
+use rccusage::data_loader::{find_jsonl_files, find_jsonl_files_in, projects_dir};
+use rccusage::database::{IngestSummary, UsageDatabase};
+use rccusage::output::{render_json, table};
+use rccusage::types::CommonOptions;
+use rccusage::PricingFetcher;
+use tracing::info;
+
+pub async fn run(args: QueryArgs) -> Result<()> {
+    let options = args.common.to_common_options();
+    let path = args.db.unwrap_or_else(UsageDatabase::default_path);
+
+    let mut db = UsageDatabase::open(&path)?;
+
+    let summary = if args.no_ingest {
+        None
+    } else {
+        info!("Ingesting usage data into {}...", path.display());
+        Some(ingest(&mut db, &options).await?)
+    };
+
+    let Some(sql) = args.sql else {
+        let summary = summary.unwrap_or(IngestSummary {
+            total_entries: db.entry_count()?,
+            ..Default::default()
+        });
+        if options.json {
+            print!("{}", render_json(&summary, options.jq.as_deref())?);
+        } else {
+            println!(
+                "Ingested {} new entries from {} changed files ({} unchanged); {} entries in {}",
+                summary.new_entries,
+                summary.files_read,
+                summary.files_skipped,
+                summary.total_entries,
+                path.display()
+            );
+        }
+        return Ok(());
+    };
+
+    info!("Running query...");
+    let result = db.query(&sql)?;
+
+    if options.json {
+        print!("{}", render_json(&result, options.jq.as_deref())?);
+    } else if result.rows.is_empty() {
+        println!("No rows");
+    } else {
+        let rows: Vec<Vec<String>> = result
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| match value {
+                        serde_json::Value::Null => String::new(),
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        print!(
+            "{}",
+            table::render_query_table(&result.columns, &rows, options.compact)
+        );
+    }
+
+    Ok(())
+}
+
+/// Store new entries from the local transcripts, `--data-dir` and `--data-file`
+async fn ingest(db: &mut UsageDatabase, options: &CommonOptions) -> Result<IngestSummary> {
+    let pricing_fetcher = PricingFetcher::new(options.offline);
+
+    let files = find_jsonl_files().await?;
+    let mut summary = db.ingest_files(&files, None, options, &pricing_fetcher)?;
+
+    for dir in &options.data_dirs {
+        let files = find_jsonl_files_in(&[projects_dir(&dir.path)?])?;
+        let dir_summary =
+            db.ingest_files(&files, dir.label.as_deref(), options, &pricing_fetcher)?;
+        summary.files_read += dir_summary.files_read;
+        summary.files_skipped += dir_summary.files_skipped;
+        summary.new_entries += dir_summary.new_entries;
+    }
+
+    for file in &options.data_files {
+        let mut entries = rccusage::archive::read_archive(&file.path)?;
+        if let Some(label) = &file.label {
+            entries
+                .iter_mut()
+                .for_each(|e| e.user = Some(label.clone()));
+        }
+        summary.new_entries += db.ingest(&entries)?;
+    }
+
+    summary.total_entries = db.entry_count()?;
+    Ok(summary)
+}
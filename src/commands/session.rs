@@ -1,13 +1,17 @@
-use crate::aggregation::aggregate_sessions;
-use crate::commands::SessionArgs;
-use crate::data_loader::load_usage_entries;
-use crate::output::{output_json, table};
-use crate::pricing::PricingFetcher;
-use anyhow::Result;
+use crate::commands::{render_report, SessionArgs};
+use anyhow::{bail, Result};
 use chrono::{Duration, Utc};
+use itertools::Itertools;
+use rccusage::aggregation::aggregate_sessions_with_idle;
+use rccusage::analytics::timeline::session_timeline;
+use rccusage::config::Config;
+use rccusage::output::{render_json, table};
+use rccusage::types::{CommonOptions, LoadedUsageEntry, SessionUsage};
+use rccusage::UsageLoader;
+use serde_json::json;
 use tracing::info;
 
-pub async fn run(args: SessionArgs) -> Result<()> {
+pub async fn run(args: SessionArgs, config: &Config) -> Result<()> {
     let mut options = args.common.to_common_options();
 
     // If all_time flag is set, clear date filters
@@ -16,10 +20,24 @@ pub async fn run(args: SessionArgs) -> Result<()> {
         options.until = None;
     }
 
-    let pricing_fetcher = PricingFetcher::new(options.offline);
+    let idle_threshold = Duration::minutes(args.idle_minutes);
+
+    if args.watch.watch {
+        return args
+            .watch
+            .to_monitor(config)?
+            .watch(&options, |aggregate| {
+                render(
+                    &args,
+                    &options,
+                    aggregate.sessions_with_idle(options.order, idle_threshold),
+                )
+            })
+            .await;
+    }
 
     info!("Loading usage data...");
-    let entries = load_usage_entries(&options, &pricing_fetcher).await?;
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
 
     if entries.is_empty() {
         if options.json {
@@ -30,9 +48,41 @@ pub async fn run(args: SessionArgs) -> Result<()> {
         return Ok(());
     }
 
+    if let Some(id) = &args.id {
+        return show_session(&args, &options, id, entries, idle_threshold);
+    }
+
     info!("Aggregating session usage...");
-    let mut session_usage = aggregate_sessions(entries, options.order);
+    if args.by_user {
+        print!(
+            "{}",
+            render_report(
+                &options,
+                true,
+                entries,
+                |entries| Ok(aggregate_sessions_with_idle(
+                    entries,
+                    options.order,
+                    idle_threshold
+                )),
+                |rows| render(&args, &options, rows),
+            )?
+        );
+        return Ok(());
+    }
+
+    let session_usage = aggregate_sessions_with_idle(entries, options.order, idle_threshold);
 
+    print!("{}", render(&args, &options, session_usage)?);
+
+    Ok(())
+}
+
+fn render(
+    args: &SessionArgs,
+    options: &CommonOptions,
+    mut session_usage: Vec<SessionUsage>,
+) -> Result<String> {
     // Filter by recent days if specified
     if let Some(days) = args.recent_days {
         let cutoff_date = Utc::now().date_naive() - Duration::days(days.into());
@@ -40,19 +90,85 @@ pub async fn run(args: SessionArgs) -> Result<()> {
     }
 
     if session_usage.is_empty() {
-        if options.json {
-            println!("[]");
+        return Ok(if options.json {
+            "[]\n".to_string()
         } else {
-            println!("No session data found for the specified period");
-        }
-        return Ok(());
+            "No session data found for the specified period\n".to_string()
+        });
+    }
+
+    // Render results
+    if options.json {
+        render_json(&session_usage, options.jq.as_deref())
+    } else {
+        Ok(table::render_session_table(&session_usage, options.compact))
     }
+}
+
+/// Print the summary and timeline of a single session
+fn show_session(
+    args: &SessionArgs,
+    options: &CommonOptions,
+    id: &str,
+    entries: Vec<LoadedUsageEntry>,
+    idle_threshold: Duration,
+) -> Result<()> {
+    // Accept a unique prefix, since tables truncate long session IDs
+    let matches: Vec<String> = entries
+        .iter()
+        .filter_map(|e| e.session_id.as_ref())
+        .filter(|s| s.0.starts_with(id))
+        .map(|s| s.0.clone())
+        .unique()
+        .collect();
+
+    let session_id = match matches.as_slice() {
+        [] => bail!("No session found matching '{}'", id),
+        [only] => only.clone(),
+        _ if matches.iter().any(|m| m == id) => id.to_string(),
+        many => bail!(
+            "'{}' matches {} sessions; use a longer prefix: {}",
+            id,
+            many.len(),
+            many.join(", ")
+        ),
+    };
+
+    let session_entries: Vec<LoadedUsageEntry> = entries
+        .into_iter()
+        .filter(|e| e.session_id.as_ref().is_some_and(|s| s.0 == session_id))
+        .collect();
+
+    let timeline = session_timeline(&session_entries, args.timeline, idle_threshold);
+
+    // A session resumed from another directory has a row per project; report the largest
+    let Some(session) =
+        aggregate_sessions_with_idle(session_entries, options.order, idle_threshold)
+            .into_iter()
+            .max_by_key(|s| s.total_cost)
+    else {
+        bail!("No usage data found for session '{}'", session_id);
+    };
 
-    // Output results
     if options.json {
-        output_json(&session_usage, options.jq.as_deref())?;
+        let output = json!({
+            "session": session,
+            "granularity": args.timeline,
+            "idleMinutes": args.idle_minutes,
+            "timeline": timeline,
+        });
+        print!("{}", render_json(&output, options.jq.as_deref())?);
     } else {
-        table::output_session_table(&session_usage, options.compact)?;
+        print!(
+            "{}",
+            table::render_session_timeline(
+                &session,
+                &timeline,
+                args.timeline,
+                idle_threshold,
+                options.compact
+            )
+        );
     }
 
     Ok(())
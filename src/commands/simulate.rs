@@ -0,0 +1,125 @@
+use crate::commands::{render_report, SimulateArgs};
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use rccusage::analytics::plans::resolve_plans;
+use rccusage::analytics::simulate::{
+    compare_plans, simulate, simulation_totals, PlanComparison, Scenario, SimulatedUsage,
+};
+use rccusage::analytics::GroupBy;
+use rccusage::config::Config;
+use rccusage::output::table;
+use rccusage::pricing::{ModelPricing, PricingFetcher};
+use rccusage::types::ModelName;
+use rccusage::UsageLoader;
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::info;
+
+pub async fn run(args: SimulateArgs, config: &Config) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // If all_time flag is set, clear date filters
+    if args.all_time {
+        options.since = None;
+        options.until = None;
+    }
+
+    let scenario = build_scenario(&args, options.offline)?;
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    // JSON keeps its shape without usage: empty groups and zero totals
+    if entries.is_empty() && !options.json {
+        println!("No usage data found");
+        return Ok(());
+    }
+
+    info!("Simulating costs...");
+    let plans = resolve_plans(&config.plans);
+    let output = render_report(
+        &options,
+        args.by_user,
+        entries,
+        |entries| {
+            // Period covered, for prorating plan prices
+            let first = entries.first().map(|e| e.timestamp.date_naive());
+            let last = entries.last().map(|e| e.timestamp.date_naive());
+            let today = Utc::now().date_naive();
+            let start = options.since.or(first).unwrap_or(today);
+            let end = options.until.or(last).unwrap_or(today);
+            let days = (end - start).num_days() + 1;
+
+            let rows = simulate(&entries, &scenario, args.by, options.order);
+            let totals = simulation_totals(&rows);
+            let plans = compare_plans(&plans, days, &totals);
+
+            Ok(SimulationReport {
+                group_by: args.by,
+                period: Period { start, end, days },
+                groups: rows,
+                totals,
+                plans,
+            })
+        },
+        |report| {
+            Ok(format!(
+                "{}\n{}",
+                table::render_simulation_table(
+                    &report.groups,
+                    &report.totals,
+                    args.by.label(),
+                    options.compact
+                ),
+                table::render_plan_comparison_table(&report.plans, report.period.days)
+            ))
+        },
+    )?;
+    print!("{}", output);
+
+    Ok(())
+}
+
+/// Simulated costs per group, with the plans they are compared against
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulationReport {
+    group_by: GroupBy,
+    period: Period,
+    groups: Vec<SimulatedUsage>,
+    totals: SimulatedUsage,
+    plans: Vec<PlanComparison>,
+}
+
+/// Dates covered by a simulation
+#[derive(Serialize)]
+struct Period {
+    start: NaiveDate,
+    end: NaiveDate,
+    days: i64,
+}
+
+/// Build the scenario from the model and pricing options
+fn build_scenario(args: &SimulateArgs, offline: bool) -> Result<Scenario> {
+    let mut pricing_fetcher = PricingFetcher::new(offline);
+
+    if let Some(path) = &args.pricing_file {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read pricing file: {}", path.display()))?;
+        let pricing: HashMap<String, ModelPricing> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse pricing file: {}", path.display()))?;
+        pricing_fetcher = pricing_fetcher.with_custom_pricing(pricing);
+    }
+
+    let mut scenario = Scenario::new(pricing_fetcher);
+
+    for (pattern, model) in &args.map {
+        scenario = scenario.map_model(pattern, ModelName::new(model));
+    }
+    if let Some(model) = &args.model {
+        scenario = scenario.all_as(ModelName::new(model));
+    }
+
+    scenario.validate()?;
+    Ok(scenario)
+}
@@ -1,26 +1,379 @@
-use crate::aggregation::identify_session_blocks;
 use crate::commands::StatuslineArgs;
-use crate::data_loader::load_usage_entries;
-use crate::output::output_json;
-use crate::pricing::PricingFetcher;
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use colored::{Color, Colorize};
+use itertools::Itertools;
+use rccusage::aggregation::identify_session_blocks;
+use rccusage::config::{Config, StatuslineConfig};
+use rccusage::data_loader::{
+    extract_project_name, find_jsonl_files_modified_since, load_usage_entries_from,
+    project_name_for_dir,
+};
+use rccusage::output::render_json;
+use rccusage::output::table::{abbreviate_model_name, format_tokens_compact};
+use rccusage::output::template::Template;
+use rccusage::pricing::PricingFetcher;
+use rccusage::types::{CommonOptions, LoadedUsageEntry, SessionBlock, StatuslineHookInput};
 use rust_decimal::prelude::*;
 use serde_json::json;
-use tracing::info;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
 
-pub async fn run(args: StatuslineArgs) -> Result<()> {
+/// How far back to read for the active block and today's totals
+///
+/// The statusline runs after every assistant message, so only recently modified
+/// transcripts are parsed. A block can start at most five hours ago; the extra
+/// margin lets the block boundaries settle the same way a full scan would.
+const LOOKBACK_HOURS: i64 = 24;
+
+/// How long to wait for the hook payload on stdin
+///
+/// Claude Code writes the payload and closes the pipe straight away. When stdin is
+/// a pipe that nobody writes to, the statusline is printed without it instead of
+/// hanging.
+const HOOK_INPUT_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Placeholders available in statusline templates
+const PLACEHOLDERS: &[&str] = &[
+    "model",
+    "project",
+    "session_cost",
+    "session_tokens",
+    "today_cost",
+    "today_tokens",
+    "block_cost",
+    "block_tokens",
+    "block_percent",
+    "burn_rate",
+    "time_left",
+];
+
+const DEFAULT_WARNING_PERCENT: f64 = 50.0;
+const DEFAULT_CRITICAL_PERCENT: f64 = 80.0;
+
+/// Output format selected with `--format` or the config file
+#[derive(Debug)]
+enum Format {
+    Compact,
+    Minimal,
+    Tokens,
+    Template(Template),
+}
+
+impl Format {
+    /// Resolve a format name against the config presets and built-in formats
+    ///
+    /// Unknown names fall back to the compact format, so a typo doesn't leave the
+    /// status line empty.
+    fn resolve(name: &str, config: &StatuslineConfig) -> Result<Self> {
+        if let Some(preset) = config.presets.get(name) {
+            return Template::parse(preset, PLACEHOLDERS)
+                .with_context(|| format!("Invalid statusline preset '{}'", name))
+                .map(Self::Template);
+        }
+
+        match name {
+            "compact" => Ok(Self::Compact),
+            "minimal" => Ok(Self::Minimal),
+            "tokens" => Ok(Self::Tokens),
+            template if template.contains('{') => {
+                Ok(Self::Template(Template::parse(template, PLACEHOLDERS)?))
+            }
+            other => {
+                warn!(
+                    "Unknown statusline format '{}', using compact. Use compact, minimal, tokens, a preset from the config file, or a template",
+                    other
+                );
+                Ok(Self::Compact)
+            }
+        }
+    }
+}
+
+/// How the status is rendered
+#[derive(Debug)]
+struct View {
+    format: Format,
+    warning_percent: f64,
+    critical_percent: f64,
+}
+
+impl View {
+    fn new(args: &StatuslineArgs, config: &StatuslineConfig) -> Result<Self> {
+        let name = args
+            .format
+            .as_deref()
+            .or(config.format.as_deref())
+            .unwrap_or("compact");
+
+        Ok(Self {
+            format: Format::resolve(name, config)?,
+            warning_percent: config.warning_percent.unwrap_or(DEFAULT_WARNING_PERCENT),
+            critical_percent: config.critical_percent.unwrap_or(DEFAULT_CRITICAL_PERCENT),
+        })
+    }
+
+    /// Color for a percentage of the token limit
+    fn color_for(&self, percent: f64) -> Color {
+        if percent >= self.critical_percent {
+            Color::Red
+        } else if percent >= self.warning_percent {
+            Color::Yellow
+        } else {
+            Color::Green
+        }
+    }
+}
+
+/// Tokens and cost for one slice of usage
+#[derive(Debug, Clone, Copy, Default)]
+struct UsageTotals {
+    tokens: u64,
+    cost: Decimal,
+}
+
+impl UsageTotals {
+    fn from_entries<'a>(entries: impl IntoIterator<Item = &'a LoadedUsageEntry>) -> Self {
+        entries.into_iter().fold(Self::default(), |mut acc, entry| {
+            acc.tokens += entry.tokens.total();
+            acc.cost += entry.cost;
+            acc
+        })
+    }
+}
+
+/// Everything shown on the status line
+#[derive(Debug, Default)]
+struct Status {
+    model: Option<String>,
+    project: Option<String>,
+    session: Option<UsageTotals>,
+    today: Option<UsageTotals>,
+    block: Option<SessionBlock>,
+    token_limit: Option<u64>,
+}
+
+impl Status {
+    /// Status for the active block; a `u64::MAX` limit means the busiest other block
+    fn from_blocks(blocks: Vec<SessionBlock>, token_limit: Option<u64>) -> Self {
+        let token_limit = match token_limit {
+            Some(u64::MAX) => blocks
+                .iter()
+                .filter(|b| !b.is_active && b.is_gap != Some(true))
+                .map(|b| b.total_tokens())
+                .max()
+                .filter(|&tokens| tokens > 0),
+            limit => limit,
+        };
+
+        Self {
+            block: blocks.into_iter().find(|b| b.is_active),
+            token_limit,
+            ..Self::default()
+        }
+    }
+
+    /// Active block tokens as a percentage of the token limit
+    fn block_percent(&self) -> Option<f64> {
+        let limit = self.token_limit.filter(|&l| l > 0)?;
+        let tokens = self.block.as_ref().map_or(0, |b| b.total_tokens());
+        Some(tokens as f64 / limit as f64 * 100.0)
+    }
+
+    /// Whether session-scoped figures from the hook payload are available
+    fn has_hook_context(&self) -> bool {
+        self.session.is_some() || self.today.is_some()
+    }
+}
+
+pub async fn run(args: StatuslineArgs, config: &Config) -> Result<()> {
     let options = args.common.to_common_options();
+    let view = View::new(&args, &config.statusline)?;
+
+    // Claude Code and shell prompts read the status line through a pipe, so don't
+    // let the non-TTY check strip template colors; NO_COLOR still disables them
+    if std::env::var_os("NO_COLOR").is_none() {
+        colored::control::set_override(true);
+    }
+    let token_limit = args.token_limit.or(config.statusline.token_limit);
+
+    if args.watch.watch {
+        return args
+            .watch
+            .to_monitor(config)?
+            .watch(&options, |aggregate| {
+                let status = Status::from_blocks(aggregate.blocks(None), token_limit);
+                render(&args, &view, &status)
+            })
+            .await;
+    }
+
     let pricing_fetcher = PricingFetcher::new(options.offline);
+    let now = Utc::now();
 
     info!("Loading usage data...");
-    let entries = load_usage_entries(&options, &pricing_fetcher).await?;
+    let entries = load_recent_entries(&options, &pricing_fetcher, now).await?;
+
+    let status = match read_hook_input() {
+        Some(hook) => hook_status(&hook, &options, &pricing_fetcher, entries, token_limit, now)?,
+        None => Status::from_blocks(identify_session_blocks(entries, None), token_limit),
+    };
+
+    print!("{}", render(&args, &view, &status)?);
+
+    Ok(())
+}
+
+/// Read the Claude Code `statusLine` payload, if one was piped in
+fn read_hook_input() -> Option<StatuslineHookInput> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return None;
+    }
+
+    let Some(input) = read_with_timeout(stdin, HOOK_INPUT_TIMEOUT) else {
+        debug!("No statusline hook input on stdin");
+        return None;
+    };
+    if input.trim().is_empty() {
+        return None;
+    }
+
+    match serde_json::from_str(&input) {
+        Ok(hook) => Some(hook),
+        Err(e) => {
+            debug!("Ignoring invalid statusline hook input: {}", e);
+            None
+        }
+    }
+}
+
+/// Read `reader` to the end on a separate thread, giving up after `timeout`
+///
+/// A reader that is still blocked when the timeout expires is left to the thread,
+/// which ends with the process.
+fn read_with_timeout<R: Read + Send + 'static>(
+    mut reader: R,
+    timeout: std::time::Duration,
+) -> Option<String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut input = String::new();
+        if reader.read_to_string(&mut input).is_ok() {
+            let _ = tx.send(input);
+        }
+    });
+
+    rx.recv_timeout(timeout).ok()
+}
+
+/// Load entries from transcripts touched within the lookback window
+async fn load_recent_entries(
+    options: &CommonOptions,
+    pricing_fetcher: &PricingFetcher,
+    now: DateTime<Utc>,
+) -> Result<Vec<LoadedUsageEntry>> {
+    let cutoff = now - Duration::hours(LOOKBACK_HOURS);
+    let files = find_jsonl_files_modified_since(cutoff.into()).await?;
+
+    let cutoff_date = cutoff.date_naive();
+    let recent_options = CommonOptions {
+        since: Some(options.since.map_or(cutoff_date, |s| s.max(cutoff_date))),
+        ..options.clone()
+    };
+
+    let mut entries = load_usage_entries_from(&files, &recent_options, pricing_fetcher)?;
+    entries.retain(|e| e.timestamp >= cutoff);
+
+    Ok(entries)
+}
 
-    // Find active block
-    let blocks = identify_session_blocks(entries, None);
-    let active_block = blocks.iter().find(|b| b.is_active);
+/// Build the status for the session described by the hook payload
+fn hook_status(
+    hook: &StatuslineHookInput,
+    options: &CommonOptions,
+    pricing_fetcher: &PricingFetcher,
+    entries: Vec<LoadedUsageEntry>,
+    token_limit: Option<u64>,
+    now: DateTime<Utc>,
+) -> Result<Status> {
+    let transcript = hook
+        .transcript_path
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|p| p.exists());
 
-    if options.json {
-        let status = if let Some(block) = active_block {
+    let project = transcript
+        .as_deref()
+        .map(extract_project_name)
+        .or_else(|| hook.project_dir().map(project_name_for_dir));
+
+    // The transcript holds the whole session, which may predate the lookback window
+    let session = match &transcript {
+        Some(path) => Some(load_transcript_totals(path, options, pricing_fetcher)?),
+        None => hook.session_id.as_deref().map(|id| {
+            UsageTotals::from_entries(
+                entries
+                    .iter()
+                    .filter(|e| e.session_id.as_ref().is_some_and(|s| s.0 == id)),
+            )
+        }),
+    };
+
+    let today_totals = today_totals(&entries, project.as_deref(), now);
+
+    let model = hook
+        .model
+        .as_ref()
+        .and_then(|m| m.display_name.clone().or_else(|| m.id.clone()));
+
+    Ok(Status {
+        model,
+        project,
+        session: Some(session.unwrap_or_default()),
+        today: Some(today_totals),
+        ..Status::from_blocks(identify_session_blocks(entries, None), token_limit)
+    })
+}
+
+/// Sum the entries from the user's local calendar day, optionally for one project
+fn today_totals(
+    entries: &[LoadedUsageEntry],
+    project: Option<&str>,
+    now: DateTime<Utc>,
+) -> UsageTotals {
+    let today = now.with_timezone(&Local).date_naive();
+    UsageTotals::from_entries(entries.iter().filter(|e| {
+        e.timestamp.with_timezone(&Local).date_naive() == today
+            && project.is_none_or(|p| e.project.as_deref() == Some(p))
+    }))
+}
+
+/// Sum every entry in a session transcript, ignoring date and project filters
+fn load_transcript_totals(
+    path: &Path,
+    options: &CommonOptions,
+    pricing_fetcher: &PricingFetcher,
+) -> Result<UsageTotals> {
+    let transcript_options = CommonOptions {
+        since: None,
+        until: None,
+        project: None,
+        ..options.clone()
+    };
+
+    let entries =
+        load_usage_entries_from(&[path.to_path_buf()], &transcript_options, pricing_fetcher)?;
+
+    Ok(UsageTotals::from_entries(&entries))
+}
+
+fn render(args: &StatuslineArgs, view: &View, status: &Status) -> Result<String> {
+    let active_block = status.block.as_ref();
+
+    if args.common.json {
+        let mut output = if let Some(block) = active_block {
             json!({
                 "active": true,
                 "tokens": block.total_tokens(),
@@ -38,46 +391,143 @@ pub async fn run(args: StatuslineArgs) -> Result<()> {
             })
         };
 
-        output_json(&status, args.common.jq.as_deref())?;
+        if status.has_hook_context() {
+            output["model"] = json!(status.model);
+            output["project"] = json!(status.project);
+            output["session"] = totals_json(status.session.unwrap_or_default());
+            output["today"] = totals_json(status.today.unwrap_or_default());
+        }
+
+        if let Some(limit) = status.token_limit {
+            output["token_limit"] = json!(limit);
+            output["percent"] = json!(status.block_percent());
+        }
+
+        render_json(&output, args.common.jq.as_deref())
+    } else if let Format::Template(template) = &view.format {
+        Ok(template.render(|name| template_value(name, view, status, Utc::now())))
+    } else if status.has_hook_context() {
+        Ok(render_hook_text(&view.format, status))
     } else {
         // Compact text output for shell prompts
         if let Some(block) = active_block {
             let tokens = block.total_tokens();
             let cost = block.cost_usd;
 
-            match args.format.as_str() {
-                "compact" => {
-                    // Compact format: "1.2K tokens | $0.05"
-                    let tokens_str = format_token_count(tokens);
-                    let cost_str = format_cost_compact(cost);
-                    print!("{} | {}", tokens_str, cost_str);
-                }
-                "minimal" => {
+            Ok(match view.format {
+                Format::Minimal => {
                     // Minimal format: just cost
-                    print!("{}", format_cost_compact(cost));
+                    format_cost_compact(cost)
                 }
-                "tokens" => {
+                Format::Tokens => {
                     // Just token count
-                    print!("{}", format_token_count(tokens));
+                    format_token_count(tokens)
                 }
                 _ => {
-                    // Default to compact
+                    // Compact format: "1.2K tokens | $0.05"
                     let tokens_str = format_token_count(tokens);
                     let cost_str = format_cost_compact(cost);
-                    print!("{} | {}", tokens_str, cost_str);
+                    format!("{} | {}", tokens_str, cost_str)
                 }
-            }
+            })
         } else {
             // No active block
-            match args.format.as_str() {
-                "minimal" => print!("$0.00"),
-                "tokens" => print!("0"),
-                _ => print!("No active session"),
-            }
+            Ok(match view.format {
+                Format::Minimal => "$0.00".to_string(),
+                Format::Tokens => "0".to_string(),
+                _ => "No active session".to_string(),
+            })
         }
     }
+}
 
-    Ok(())
+/// Text output when invoked as Claude Code's statusLine command
+fn render_hook_text(format: &Format, status: &Status) -> String {
+    let session = status.session.unwrap_or_default();
+
+    match format {
+        Format::Minimal => format_cost_compact(session.cost),
+        Format::Tokens => format_token_count(session.tokens),
+        _ => {
+            // Compact format: "Opus 4.1 | $0.23 session | $1.23 today | $0.45 block (2h 45m left)"
+            let today = status.today.unwrap_or_default();
+            let block = match &status.block {
+                Some(block) => format!(
+                    "{} block ({} left)",
+                    format_cost_compact(block.cost_usd),
+                    format_time_left(block.end_time - Utc::now())
+                ),
+                None => "No active block".to_string(),
+            };
+
+            status
+                .model
+                .iter()
+                .cloned()
+                .chain([
+                    format!("{} session", format_cost_compact(session.cost)),
+                    format!("{} today", format_cost_compact(today.cost)),
+                    block,
+                ])
+                .collect::<Vec<_>>()
+                .join(" | ")
+        }
+    }
+}
+
+/// Value of a template placeholder, colored by token limit usage where relevant
+fn template_value(name: &str, view: &View, status: &Status, now: DateTime<Utc>) -> String {
+    let block = status.block.as_ref();
+    let session = status.session.unwrap_or_default();
+    let today = status.today.unwrap_or_default();
+    let color = status.block_percent().map(|p| view.color_for(p));
+
+    let value = match name {
+        "model" => status
+            .model
+            .clone()
+            .or_else(|| {
+                block
+                    .filter(|b| !b.models.is_empty())
+                    .map(|b| b.models.iter().map(|m| abbreviate_model_name(m)).join(", "))
+            })
+            .unwrap_or_else(|| "-".to_string()),
+        "project" => status.project.clone().unwrap_or_else(|| "-".to_string()),
+        "session_cost" => format_cost_compact(session.cost),
+        "session_tokens" => format_tokens_compact(session.tokens),
+        "today_cost" => format_cost_compact(today.cost),
+        "today_tokens" => format_tokens_compact(today.tokens),
+        "block_cost" => format_cost_compact(block.map_or(Decimal::ZERO, |b| b.cost_usd)),
+        "block_tokens" => format_tokens_compact(block.map_or(0, |b| b.total_tokens())),
+        "block_percent" => status
+            .block_percent()
+            .map_or_else(|| "-".to_string(), |p| format!("{:.0}%", p)),
+        "burn_rate" => format_cost_compact(block.map_or(Decimal::ZERO, |b| burn_rate(b, now))),
+        "time_left" => {
+            block.map_or_else(|| "-".to_string(), |b| format_time_left(b.end_time - now))
+        }
+        _ => String::new(),
+    };
+
+    match (name, color) {
+        ("block_cost" | "block_tokens" | "block_percent", Some(color)) => {
+            value.color(color).to_string()
+        }
+        _ => value,
+    }
+}
+
+/// Cost per hour since the block started
+fn burn_rate(block: &SessionBlock, now: DateTime<Utc>) -> Decimal {
+    let elapsed_minutes = (now - block.start_time).num_minutes().max(1);
+    block.cost_usd * Decimal::from(60) / Decimal::from(elapsed_minutes)
+}
+
+fn totals_json(totals: UsageTotals) -> serde_json::Value {
+    json!({
+        "tokens": totals.tokens,
+        "cost": totals.cost.to_f64().unwrap_or(0.0),
+    })
 }
 
 fn format_token_count(tokens: u64) -> String {
@@ -93,3 +543,115 @@ fn format_token_count(tokens: u64) -> String {
 fn format_cost_compact(cost: Decimal) -> String {
     format!("${:.2}", cost)
 }
+
+fn format_time_left(remaining: Duration) -> String {
+    let minutes = remaining.num_minutes().max(0);
+    format!("{}h {}m", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rccusage::types::{ModelName, TokenCounts};
+    use std::io::Cursor;
+    use std::time::Instant;
+
+    fn entry(timestamp: DateTime<Utc>, project: &str) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp,
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: 100,
+                ..TokenCounts::default()
+            },
+            cost: Decimal::ONE,
+            session_id: None,
+            request_id: None,
+            message_id: None,
+            project: Some(project.to_string()),
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    /// A reader that never produces data, like a pipe nobody writes to
+    struct Stalled;
+
+    impl Read for Stalled {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            std::thread::sleep(std::time::Duration::from_secs(60));
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn reads_piped_input() {
+        let input = read_with_timeout(Cursor::new(r#"{"session_id":"abc"}"#), HOOK_INPUT_TIMEOUT);
+        assert_eq!(input.as_deref(), Some(r#"{"session_id":"abc"}"#));
+    }
+
+    #[test]
+    fn gives_up_on_stdin_without_writer() {
+        let start = Instant::now();
+        assert!(read_with_timeout(Stalled, HOOK_INPUT_TIMEOUT).is_none());
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn resolves_formats() {
+        let mut config = StatuslineConfig::default();
+        config
+            .presets
+            .insert("prompt".to_string(), "{model} {time_left}".to_string());
+
+        assert!(matches!(
+            Format::resolve("minimal", &config).unwrap(),
+            Format::Minimal
+        ));
+        assert!(matches!(
+            Format::resolve("prompt", &config).unwrap(),
+            Format::Template(_)
+        ));
+        assert!(matches!(
+            Format::resolve("{session_cost}", &config).unwrap(),
+            Format::Template(_)
+        ));
+        assert!(Format::resolve("{nope}", &config).is_err());
+    }
+
+    #[test]
+    fn unknown_format_falls_back_to_compact() {
+        let format = Format::resolve("fancy", &StatuslineConfig::default()).unwrap();
+        assert!(matches!(format, Format::Compact));
+    }
+
+    #[test]
+    fn today_is_the_local_calendar_day() {
+        let local = |d, h, m| {
+            Local
+                .with_ymd_and_hms(2026, 10, d, h, m, 0)
+                .single()
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let entries = vec![
+            entry(local(17, 23, 30), "alpha"),
+            entry(local(18, 0, 30), "alpha"),
+            entry(local(18, 11, 0), "alpha"),
+            entry(local(18, 11, 30), "beta"),
+        ];
+        let now = local(18, 12, 0);
+
+        let all = today_totals(&entries, None, now);
+        assert_eq!(all.tokens, 300);
+        assert_eq!(all.cost, Decimal::new(3, 0));
+
+        let alpha = today_totals(&entries, Some("alpha"), now);
+        assert_eq!(alpha.tokens, 200);
+    }
+}
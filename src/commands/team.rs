@@ -0,0 +1,36 @@
+use crate::commands::TeamArgs;
+use anyhow::Result;
+use rccusage::analytics::team::team_summary;
+use rccusage::output::{render_json, table};
+use rccusage::UsageLoader;
+use tracing::info;
+
+pub async fn run(args: TeamArgs) -> Result<()> {
+    let mut options = args.common.to_common_options();
+
+    // If all_time flag is set, clear date filters
+    if args.all_time {
+        options.since = None;
+        options.until = None;
+    }
+
+    info!("Loading usage data...");
+    let entries = UsageLoader::from_options(options.clone()).load().await?;
+
+    // JSON keeps its shape without usage: no users and zero totals
+    if entries.is_empty() && !options.json {
+        println!("No usage data found");
+        return Ok(());
+    }
+
+    info!("Summarizing usage per user...");
+    let team = team_summary(entries);
+
+    if options.json {
+        print!("{}", render_json(&team, options.jq.as_deref())?);
+    } else {
+        print!("{}", table::render_team_table(&team, options.compact));
+    }
+
+    Ok(())
+}
@@ -1,6 +1,11 @@
+//! Settings loaded from `ccusage.config.json`.
+
+use crate::analytics::plans::SubscriptionPlan;
 use crate::types::{CostMode, SortOrder};
 use anyhow::{Context, Result};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -39,6 +44,156 @@ pub struct Config {
     /// Log level (0-4)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub log_level: Option<u8>,
+
+    /// Statusline formats and color thresholds
+    #[serde(default)]
+    pub statusline: StatuslineConfig,
+
+    /// Subscription plan you pay for, by name (e.g. "Max 20x")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>,
+
+    /// Plan prices overriding or adding to Pro, Max 5x and Max 20x
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plans: Vec<SubscriptionPlan>,
+
+    /// Spending limits in USD
+    #[serde(default)]
+    pub budget: BudgetConfig,
+
+    /// OpenTelemetry collector for the `otlp` command
+    #[serde(default)]
+    pub otlp: OtlpConfig,
+
+    /// Webhooks fired when usage crosses a threshold
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+}
+
+/// Budgets in the config file
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetConfig {
+    /// Spend per UTC day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily: Option<Decimal>,
+
+    /// Spend per calendar month
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly: Option<Decimal>,
+
+    /// Spend per calendar quarter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarterly: Option<Decimal>,
+}
+
+/// OpenTelemetry collector settings in the config file
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OtlpConfig {
+    /// Collector base URL, e.g. "http://localhost:4318"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Headers sent with every export, e.g. an API key
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+/// Webhook notification settings in the config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationsConfig {
+    /// Where alerts are posted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// Token limit the active block is measured against; defaults to
+    /// `statusline.tokenLimit`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_limit: Option<u64>,
+
+    /// Percentages of the token limit at which the active block alerts
+    #[serde(default = "default_block_percents")]
+    pub block_percents: Vec<f64>,
+
+    /// Alert on days and sessions the `anomalies` command would report
+    #[serde(default)]
+    pub anomalies: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            webhooks: Vec::new(),
+            token_limit: None,
+            block_percents: default_block_percents(),
+            anomalies: false,
+        }
+    }
+}
+
+fn default_block_percents() -> Vec<f64> {
+    vec![80.0, 100.0]
+}
+
+/// One webhook in the config file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Payload shape
+    #[serde(default)]
+    pub format: WebhookFormat,
+
+    /// Text of the alert, with `{title}`, `{message}`, `{kind}`, `{severity}`
+    /// and `{key}` placeholders [default: the title, in bold for Slack and
+    /// Discord, then the message]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+
+    /// Headers sent with every post, e.g. an authorization token
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+/// Payload shape a webhook expects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// The alert as a JSON object, plus the rendered `text`
+    #[default]
+    Json,
+    /// Slack incoming webhook (`{"text": ...}`)
+    Slack,
+    /// Discord webhook (`{"content": ...}`)
+    Discord,
+}
+
+/// Statusline settings in the config file
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StatuslineConfig {
+    /// Format used when `--format` is not given (built-in name, preset or template)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    /// Token limit that block percentages and colors are measured against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_limit: Option<u64>,
+
+    /// Percentage of the token limit at which block values turn yellow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning_percent: Option<f64>,
+
+    /// Percentage of the token limit at which block values turn red
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical_percent: Option<f64>,
+
+    /// Named format templates, selectable with `--format <name>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub presets: HashMap<String, String>,
 }
 
 impl Config {
@@ -93,3 +248,42 @@ impl Config {
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rccusage-config-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_config_file() {
+        let path = write_config(
+            "valid",
+            r#"{ "budget": { "daily": 5 }, "statusline": { "format": "minimal" } }"#,
+        );
+
+        let config = Config::load_from_file(&path).unwrap();
+        assert_eq!(config.statusline.format.as_deref(), Some("minimal"));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn malformed_config_error_names_file_and_cause() {
+        let path = write_config("malformed", r#"{ "budget": { "daily": "lots" } }"#);
+
+        let err = format!("{:#}", Config::load_from_file(&path).unwrap_err());
+        assert!(err.contains(&path.display().to_string()), "{}", err);
+        assert!(err.contains("line 1"), "{}", err);
+
+        let _ = fs::remove_file(&path);
+    }
+}
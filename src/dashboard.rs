@@ -0,0 +1,469 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Utc};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use itertools::Itertools;
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Gauge, Paragraph, Row, Sparkline, Table};
+use ratatui::{DefaultTerminal, Frame};
+use rccusage::aggregation::IncrementalAggregate;
+use rccusage::data_loader::IncrementalLoader;
+use rccusage::live::LiveMonitor;
+use rccusage::logger;
+use rccusage::output::table::{
+    abbreviate_model_name, format_cost, format_duration, format_tokens_compact,
+};
+use rccusage::pricing::PricingFetcher;
+use rccusage::types::*;
+use rust_decimal::prelude::*;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Instant;
+
+/// Width of each burn rate sparkline bucket in minutes
+const BURN_BUCKET_MINUTES: i64 = 5;
+
+/// How much recent history is kept for the burn rate sparkline
+const BURN_WINDOW_HOURS: i64 = 6;
+
+/// Slowest refresh interval reachable with the `-` key
+const MAX_REFRESH_SECS: u64 = 60;
+
+/// Full-screen live dashboard for the active 5-hour billing block
+pub struct BlocksDashboard {
+    token_limit: Option<u64>,
+    refresh_interval: std::time::Duration,
+    loader: IncrementalLoader,
+    aggregate: IncrementalAggregate,
+    recent_entries: Vec<(DateTime<Utc>, u64)>,
+    snapshot: DashboardSnapshot,
+    last_refresh: Instant,
+    paused: bool,
+}
+
+/// Data shown on one frame of the dashboard
+#[derive(Default)]
+struct DashboardSnapshot {
+    active_block: Option<SessionBlock>,
+    token_limit: Option<u64>,
+    burn_history: Vec<u64>,
+    model_breakdowns: Vec<ModelBreakdown>,
+    first_entry: Option<DateTime<Utc>>,
+    refreshed_at: Option<DateTime<Local>>,
+}
+
+impl BlocksDashboard {
+    pub fn new(
+        options: CommonOptions,
+        token_limit: Option<u64>,
+        refresh_interval: std::time::Duration,
+    ) -> Self {
+        let pricing_fetcher = PricingFetcher::new(options.offline);
+
+        Self {
+            token_limit,
+            refresh_interval,
+            loader: IncrementalLoader::new(options, pricing_fetcher),
+            aggregate: IncrementalAggregate::default(),
+            recent_entries: Vec::new(),
+            snapshot: DashboardSnapshot::default(),
+            last_refresh: Instant::now(),
+            paused: false,
+        }
+    }
+
+    /// Take over the terminal and run until the user quits
+    pub async fn run(mut self) -> Result<()> {
+        let monitor = LiveMonitor::new();
+        let (_watcher, rx) = monitor.subscribe()?;
+
+        // Load before entering the alternate screen so startup errors stay visible
+        let entries = self.loader.load_all().await?;
+        self.ingest(entries);
+        self.refresh();
+
+        let mut terminal = ratatui::init();
+        logger::set_console_suspended(true);
+        let result = self.event_loop(&mut terminal, &rx).await;
+        logger::set_console_suspended(false);
+        ratatui::restore();
+
+        result
+    }
+
+    async fn event_loop(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        rx: &mpsc::Receiver<PathBuf>,
+    ) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            // Poll the keyboard briefly so file events and the timer stay responsive
+            let has_input =
+                tokio::task::block_in_place(|| event::poll(std::time::Duration::from_millis(200)))?;
+
+            if has_input {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        match key.code {
+                            KeyCode::Char('q') | KeyCode::Esc => break,
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                break
+                            }
+                            KeyCode::Char('r') => {
+                                // Rescan every file for anything the watcher missed
+                                let entries = self.loader.load_all().await?;
+                                self.ingest(entries);
+                                self.refresh();
+                            }
+                            KeyCode::Char('p') => self.paused = !self.paused,
+                            KeyCode::Char('+') | KeyCode::Char('=') => {
+                                let secs = self.refresh_interval.as_secs().saturating_sub(1);
+                                self.refresh_interval = std::time::Duration::from_secs(secs.max(1));
+                            }
+                            KeyCode::Char('-') => {
+                                let secs = self.refresh_interval.as_secs() + 1;
+                                self.refresh_interval =
+                                    std::time::Duration::from_secs(secs.min(MAX_REFRESH_SECS));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            // Read only what was appended to the files touched since the last pass
+            let changed: Vec<PathBuf> = rx.try_iter().unique().collect();
+            if !changed.is_empty() {
+                let entries = self.loader.load_changed(&changed).await?;
+                self.ingest(entries);
+            }
+
+            if !self.paused
+                && (!changed.is_empty() || self.last_refresh.elapsed() >= self.refresh_interval)
+            {
+                self.refresh();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add newly loaded entries to the aggregate and the burn rate window
+    fn ingest(&mut self, entries: Vec<LoadedUsageEntry>) {
+        let window_start = Utc::now() - Duration::hours(BURN_WINDOW_HOURS);
+
+        self.recent_entries.extend(
+            entries
+                .iter()
+                .filter(|e| e.timestamp >= window_start)
+                .map(|e| (e.timestamp, e.tokens.total())),
+        );
+        self.recent_entries.retain(|(t, _)| *t >= window_start);
+
+        self.aggregate.extend(entries);
+    }
+
+    /// Rebuild the snapshot from the current aggregate
+    fn refresh(&mut self) {
+        let now = Utc::now();
+
+        let blocks = self.aggregate.blocks(self.token_limit);
+        let active_block = blocks.iter().find(|b| b.is_active).cloned();
+
+        // Without an explicit limit (or with "max"), measure against the busiest past block
+        let token_limit = match self.token_limit {
+            Some(limit) if limit != u64::MAX => Some(limit),
+            _ => blocks
+                .iter()
+                .filter(|b| !b.is_active && b.is_gap != Some(true))
+                .map(|b| b.total_tokens())
+                .max(),
+        };
+
+        let mut snapshot = DashboardSnapshot {
+            token_limit,
+            refreshed_at: Some(Local::now()),
+            ..Default::default()
+        };
+
+        if let Some(block) = active_block {
+            if let Some(accumulator) = self
+                .aggregate
+                .block_tracker()
+                .accumulators()
+                .iter()
+                .rev()
+                .find(|a| a.start_time == block.start_time)
+            {
+                snapshot.first_entry = Some(accumulator.first_timestamp);
+                snapshot.model_breakdowns = accumulator.usage.model_breakdowns();
+            }
+
+            snapshot.burn_history = burn_history(&self.recent_entries, &block, now);
+            snapshot.active_block = Some(block);
+        }
+
+        self.snapshot = snapshot;
+        self.last_refresh = Instant::now();
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [header_area, body_area, footer_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    " rccusage ",
+                    Style::default().fg(Color::Black).bg(Color::Cyan),
+                ),
+                Span::raw(" Live billing block monitor"),
+            ])),
+            header_area,
+        );
+
+        match &self.snapshot.active_block {
+            Some(block) => self.render_block(frame, body_area, block),
+            None => {
+                let message = Paragraph::new("No active block. Waiting for new usage...")
+                    .alignment(Alignment::Center)
+                    .block(Block::bordered());
+                frame.render_widget(message, body_area);
+            }
+        }
+
+        self.render_footer(frame, footer_area);
+    }
+
+    fn render_block(&self, frame: &mut Frame, area: Rect, block: &SessionBlock) {
+        let [tokens_area, time_area, burn_area, stats_area, models_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(7),
+            Constraint::Length(3),
+            Constraint::Min(4),
+        ])
+        .areas(area);
+
+        let now = Utc::now();
+        let tokens = block.total_tokens();
+
+        // Token usage against the limit
+        let (token_ratio, token_label) = match self.snapshot.token_limit {
+            Some(limit) if limit > 0 => {
+                let ratio = tokens as f64 / limit as f64;
+                (
+                    ratio,
+                    format!(
+                        "{} / {} ({:.1}%)",
+                        format_tokens_compact(tokens),
+                        format_tokens_compact(limit),
+                        ratio * 100.0
+                    ),
+                )
+            }
+            _ => (0.0, format!("{} (no limit)", format_tokens_compact(tokens))),
+        };
+
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title(" Tokens "))
+                .gauge_style(Style::default().fg(usage_color(token_ratio)))
+                .ratio(token_ratio.clamp(0.0, 1.0))
+                .label(token_label),
+            tokens_area,
+        );
+
+        // Time elapsed in the 5-hour window
+        let block_length = (block.end_time - block.start_time).num_seconds().max(1);
+        let elapsed = (now - block.start_time)
+            .num_seconds()
+            .clamp(0, block_length);
+        let remaining = Duration::seconds(block_length - elapsed);
+        let time_label = format!(
+            "{} - {} · {} remaining",
+            block.start_time.with_timezone(&Local).format("%H:%M"),
+            block.end_time.with_timezone(&Local).format("%H:%M"),
+            format_duration(remaining)
+        );
+
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title(" Time "))
+                .gauge_style(Style::default().fg(Color::Blue))
+                .ratio(elapsed as f64 / block_length as f64)
+                .label(time_label),
+            time_area,
+        );
+
+        // Burn rate since the first entry of the block
+        let active_minutes = self
+            .snapshot
+            .first_entry
+            .map(|first| (now - first).num_seconds() as f64 / 60.0)
+            .unwrap_or(0.0)
+            .max(1.0);
+        let tokens_per_minute = tokens as f64 / active_minutes;
+        let cost_per_hour = block.cost_usd.to_f64().unwrap_or(0.0) / active_minutes * 60.0;
+
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title(format!(
+                    " Burn rate · {}/min · ${:.2}/h ",
+                    format_tokens_compact(tokens_per_minute as u64),
+                    cost_per_hour
+                )))
+                .style(Style::default().fg(Color::Yellow))
+                .data(&self.snapshot.burn_history),
+            burn_area,
+        );
+
+        // Projection to the end of the block at the current rate
+        let remaining_minutes = remaining.num_seconds() as f64 / 60.0;
+        let projected_tokens = tokens + (tokens_per_minute * remaining_minutes) as u64;
+        let projected_cost = block.cost_usd
+            + Decimal::from_f64(cost_per_hour * remaining_minutes / 60.0).unwrap_or_default();
+
+        let mut projection = vec![
+            Span::raw("Cost "),
+            Span::styled(
+                format_cost(block.cost_usd),
+                Style::default().fg(Color::Green),
+            ),
+            Span::raw("   Projected "),
+            Span::styled(
+                format!(
+                    "{} · {}",
+                    format_tokens_compact(projected_tokens),
+                    format_cost(projected_cost)
+                ),
+                Style::default().fg(Color::Yellow),
+            ),
+        ];
+
+        if let Some(limit) = self.snapshot.token_limit {
+            if projected_tokens > limit {
+                projection.push(Span::styled(
+                    "   ⚠ projected to exceed limit",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+            }
+        }
+
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(projection)])
+                .block(Block::bordered().title(" Projection ")),
+            stats_area,
+        );
+
+        // Per-model split
+        let header = Row::new(vec!["Model", "Input", "Output", "Cache", "Cost", "Share"])
+            .style(Style::default().fg(Color::Blue));
+
+        let rows = self.snapshot.model_breakdowns.iter().map(|b| {
+            let share = if block.cost_usd > Decimal::ZERO {
+                (b.cost / block.cost_usd * Decimal::from(100))
+                    .to_f64()
+                    .unwrap_or(0.0)
+            } else {
+                0.0
+            };
+
+            Row::new(vec![
+                Cell::from(abbreviate_model_name(b.model_name.as_str()))
+                    .style(Style::default().fg(Color::Cyan)),
+                Cell::from(format_tokens_compact(b.input_tokens)),
+                Cell::from(format_tokens_compact(b.output_tokens)),
+                Cell::from(format_tokens_compact(
+                    b.cache_creation_tokens + b.cache_read_tokens,
+                )),
+                Cell::from(format_cost(b.cost)).style(Style::default().fg(Color::Green)),
+                Cell::from(format!("{:.0}%", share)),
+            ])
+        });
+
+        frame.render_widget(
+            Table::new(
+                rows,
+                [
+                    Constraint::Min(12),
+                    Constraint::Length(8),
+                    Constraint::Length(8),
+                    Constraint::Length(8),
+                    Constraint::Length(9),
+                    Constraint::Length(6),
+                ],
+            )
+            .header(header)
+            .block(Block::bordered().title(" Models ")),
+            models_area,
+        );
+    }
+
+    fn render_footer(&self, frame: &mut Frame, area: Rect) {
+        let refreshed = self
+            .snapshot
+            .refreshed_at
+            .map(|t| t.format("%H:%M:%S").to_string())
+            .unwrap_or_default();
+
+        let status = if self.paused {
+            Span::styled("paused", Style::default().fg(Color::Yellow))
+        } else {
+            Span::raw(format!("every {}s", self.refresh_interval.as_secs()))
+        };
+
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    " q quit · r refresh · p pause · +/- interval ",
+                    Style::default().add_modifier(Modifier::DIM),
+                ),
+                Span::raw(format!(" updated {} · ", refreshed)),
+                status,
+            ])),
+            area,
+        );
+    }
+}
+
+/// Bucket the block's entries into fixed-width token totals for the sparkline
+fn burn_history(
+    recent_entries: &[(DateTime<Utc>, u64)],
+    block: &SessionBlock,
+    now: DateTime<Utc>,
+) -> Vec<u64> {
+    let buckets = ((now - block.start_time).num_minutes() / BURN_BUCKET_MINUTES + 1).clamp(1, 60);
+    let mut history = vec![0u64; buckets as usize];
+
+    for (timestamp, tokens) in recent_entries {
+        if *timestamp < block.start_time || *timestamp >= block.end_time {
+            continue;
+        }
+
+        let idx = (*timestamp - block.start_time).num_minutes() / BURN_BUCKET_MINUTES;
+        if let Some(bucket) = history.get_mut(idx as usize) {
+            *bucket += tokens;
+        }
+    }
+
+    history
+}
+
+/// Pick a gauge color based on how close usage is to the limit
+fn usage_color(ratio: f64) -> Color {
+    if ratio >= 0.9 {
+        Color::Red
+    } else if ratio >= 0.7 {
+        Color::Yellow
+    } else {
+        Color::Green
+    }
+}
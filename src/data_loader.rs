@@ -1,15 +1,21 @@
+//! Discovery, parsing, pricing and deduplication of Claude Code JSONL transcripts.
+
+use crate::archive;
+use crate::history::HistoryStore;
 use crate::pricing::PricingFetcher;
 use crate::types::*;
 use crate::utils;
-use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
 use dashmap::DashSet;
 use glob::glob;
 use rust_decimal::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 /// Default Claude data directories to search
 const DEFAULT_CLAUDE_PATHS: &[&str] = &[
@@ -45,16 +51,19 @@ pub fn get_claude_data_dirs() -> Vec<PathBuf> {
 
 /// Find all JSONL files in Claude data directories
 pub async fn find_jsonl_files() -> Result<Vec<PathBuf>> {
-    let dirs = get_claude_data_dirs();
-    if dirs.is_empty() {
-        return Ok(Vec::new());
-    }
+    find_jsonl_files_in(&get_claude_data_dirs())
+}
 
+/// Find all JSONL files under the given directories
+pub fn find_jsonl_files_in(dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
     let mut all_files = Vec::new();
 
     for dir in dirs {
         let pattern = format!("{}/**/*.jsonl", dir.display());
-        for path in glob(&pattern).context("Failed to read glob pattern")?.flatten() {
+        for path in glob(&pattern)
+            .context("Failed to read glob pattern")?
+            .flatten()
+        {
             all_files.push(path);
         }
     }
@@ -62,6 +71,35 @@ pub async fn find_jsonl_files() -> Result<Vec<PathBuf>> {
     Ok(all_files)
 }
 
+/// Find JSONL files modified at or after the given time
+pub async fn find_jsonl_files_modified_since(since: SystemTime) -> Result<Vec<PathBuf>> {
+    let files = find_jsonl_files().await?;
+
+    Ok(files
+        .into_iter()
+        .filter(|path| {
+            std::fs::metadata(path)
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified >= since)
+        })
+        .collect())
+}
+
+/// The `projects` directory of a Claude data directory given as `--data-dir`
+///
+/// `dir` may be the data directory itself or its `projects` subdirectory.
+pub fn projects_dir(dir: &Path) -> Result<PathBuf> {
+    let projects = if dir.ends_with("projects") || !dir.join("projects").exists() {
+        dir.to_path_buf()
+    } else {
+        dir.join("projects")
+    };
+    if !projects.exists() {
+        bail!("Data directory not found: {}", dir.display());
+    }
+    Ok(projects)
+}
+
 /// Extract project name from file path
 pub fn extract_project_name(file_path: &Path) -> String {
     // Path structure: .../projects/{project}/{sessionId}.jsonl
@@ -80,6 +118,49 @@ pub fn extract_project_name(file_path: &Path) -> String {
     "unknown".to_string()
 }
 
+/// Where a transcript file sits in the Claude data directory
+///
+/// Claude Code writes Task tool subagents to their own `agent-<id>.jsonl`
+/// transcripts, either beside the session files or under
+/// `{sessionId}/subagents/`.
+#[derive(Debug, Clone)]
+struct TranscriptSource {
+    project: String,
+    is_agent: bool,
+    parent_session: Option<SessionId>,
+}
+
+impl TranscriptSource {
+    fn from_path(file_path: &Path) -> Self {
+        let is_agent = file_path
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().starts_with("agent-"));
+
+        let parent_session = file_path
+            .parent()
+            .filter(|dir| dir.file_name().is_some_and(|name| name == "subagents"))
+            .and_then(|dir| dir.parent())
+            .and_then(|dir| dir.file_name())
+            .map(|name| SessionId::new(name.to_string_lossy().to_string()));
+
+        Self {
+            project: extract_project_name(file_path),
+            is_agent,
+            parent_session,
+        }
+    }
+}
+
+/// Project directory name Claude Code uses for a working directory
+///
+/// Claude Code replaces every character other than ASCII letters and digits
+/// with `-`, so `/home/me/my.app` is stored under `-home-me-my-app`.
+pub fn project_name_for_dir(dir: &str) -> String {
+    dir.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
 /// Stream JSONL file line by line (PR #706 fix - prevents memory issues with large files)
 pub fn stream_jsonl_file<F>(file_path: &Path, mut process_line: F) -> Result<()>
 where
@@ -168,6 +249,11 @@ pub async fn parse_usage_entry(
         message_id: data.message.id.as_ref().map(|m| MessageId::new(m.clone())),
         project: Some(project),
         version: data.version.clone(),
+        cwd: data.cwd.clone(),
+        git_branch: data.git_branch.clone(),
+        is_sidechain: data.is_sidechain.unwrap_or(false),
+        machine: None,
+        user: None,
     })
 }
 
@@ -177,62 +263,32 @@ pub async fn load_usage_entries(
     pricing_fetcher: &PricingFetcher,
 ) -> Result<Vec<LoadedUsageEntry>> {
     let files = find_jsonl_files().await?;
+    load_usage_entries_from(&files, options, pricing_fetcher)
+}
+
+/// Load usage entries from the given JSONL files with streaming and deduplication
+pub fn load_usage_entries_from(
+    files: &[PathBuf],
+    options: &CommonOptions,
+    pricing_fetcher: &PricingFetcher,
+) -> Result<Vec<LoadedUsageEntry>> {
     let seen_hashes = Arc::new(DashSet::new());
     let mut all_entries = Vec::new();
 
     for file_path in files {
-        let project = extract_project_name(&file_path);
+        let source = TranscriptSource::from_path(file_path);
 
         // Filter by project if specified
-        if let Some(ref target_project) = options.project {
-            if project != *target_project {
-                continue;
-            }
+        if !project_matches(options, &source.project) {
+            continue;
         }
 
         let mut file_entries = Vec::new();
-        let fetcher = pricing_fetcher.clone();
-        let mode = options.mode;
 
         // Stream file line by line (PR #706 fix)
-        stream_jsonl_file(&file_path, |line, _line_num| {
-            // Parse JSON line
-            if let Ok(data) = serde_json::from_str::<UsageData>(line) {
-                // Skip API error messages
-                if data.is_api_error_message.unwrap_or(false) {
-                    return Ok(());
-                }
-
-                // Create entry synchronously for now (can optimize later with async streaming)
-                let entry_future = parse_usage_entry(&data, project.clone(), mode, &fetcher);
-                if let Ok(entry) = tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(entry_future)
-                }) {
-                    // Deduplication check
-                    let hash = entry.unique_hash();
-                    if !hash.is_empty() && seen_hashes.contains(&hash) {
-                        return Ok(());
-                    }
-
-                    if !hash.is_empty() {
-                        seen_hashes.insert(hash);
-                    }
-
-                    // Date filtering
-                    let entry_date = entry.timestamp.date_naive();
-                    if let Some(since) = options.since {
-                        if entry_date < since {
-                            return Ok(());
-                        }
-                    }
-                    if let Some(until) = options.until {
-                        if entry_date > until {
-                            return Ok(());
-                        }
-                    }
-
-                    file_entries.push(entry);
-                }
+        stream_jsonl_file(file_path, |line, _line_num| {
+            if let Some(entry) = parse_line(line, &source, options, pricing_fetcher, &seen_hashes) {
+                file_entries.push(entry);
             }
 
             Ok(())
@@ -246,3 +302,592 @@ pub async fn load_usage_entries(
 
     Ok(all_entries)
 }
+
+/// Builder for loading usage entries
+///
+/// Defaults match the CLI: all dates, every project, [`CostMode::Auto`], the
+/// directories from [`get_claude_data_dirs`] and the [`HistoryStore`] kept by
+/// `archive sync`. Loading parses files on the calling
+/// thread with `block_in_place`, so it must run on a multi-threaded Tokio runtime.
+#[derive(Clone, Default)]
+pub struct UsageLoader {
+    options: CommonOptions,
+    data_dirs: Option<Vec<PathBuf>>,
+    pricing_fetcher: Option<PricingFetcher>,
+}
+
+impl UsageLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from already-parsed command options
+    pub fn from_options(options: CommonOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Only load entries on or after this date
+    pub fn since(mut self, date: NaiveDate) -> Self {
+        self.options.since = Some(date);
+        self
+    }
+
+    /// Only load entries on or before this date
+    pub fn until(mut self, date: NaiveDate) -> Self {
+        self.options.until = Some(date);
+        self
+    }
+
+    /// Only load entries from this project directory name
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.options.project = Some(project.into());
+        self
+    }
+
+    /// How entry costs are determined
+    pub fn mode(mut self, mode: CostMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Use only bundled pricing data
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.options.offline = offline;
+        self
+    }
+
+    /// Read these `projects` directories instead of the Claude defaults
+    ///
+    /// The history store is skipped, since it holds entries from the defaults.
+    pub fn data_dirs(mut self, dirs: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.data_dirs = Some(dirs.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Also load entries from this usage archive (see [`crate::archive`])
+    pub fn data_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.data_files.push(LabeledPath::new(None, path));
+        self
+    }
+
+    /// Also load entries from this usage archive, attributed to `user`
+    pub fn user_data_file(mut self, user: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.options
+            .data_files
+            .push(LabeledPath::new(Some(user.into()), path));
+        self
+    }
+
+    /// Also load this Claude data directory, attributed to `user`
+    pub fn user_data_dir(mut self, user: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.options
+            .data_dirs
+            .push(LabeledPath::new(Some(user.into()), path));
+        self
+    }
+
+    /// Whether to include entries from the history store (on by default)
+    pub fn history(mut self, enabled: bool) -> Self {
+        self.options.no_history = !enabled;
+        self
+    }
+
+    /// Only load entries belonging to this user
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.options.user = Some(user.into());
+        self
+    }
+
+    /// Price entries with this fetcher, e.g. one with custom pricing
+    pub fn pricing_fetcher(mut self, pricing_fetcher: PricingFetcher) -> Self {
+        self.pricing_fetcher = Some(pricing_fetcher);
+        self
+    }
+
+    pub fn options(&self) -> &CommonOptions {
+        &self.options
+    }
+
+    /// Load, price and deduplicate entries, sorted by timestamp
+    pub async fn load(&self) -> Result<Vec<LoadedUsageEntry>> {
+        let files = match &self.data_dirs {
+            Some(dirs) => find_jsonl_files_in(dirs)?,
+            None => find_jsonl_files().await?,
+        };
+
+        let pricing_fetcher = self
+            .pricing_fetcher
+            .clone()
+            .unwrap_or_else(|| PricingFetcher::new(self.options.offline));
+
+        let mut entries = load_usage_entries_from(&files, &self.options, &pricing_fetcher)?;
+
+        if self.data_dirs.is_none() {
+            entries.extend(load_history(&self.options, &entries)?);
+            entries.sort_by_key(|e| e.timestamp);
+        }
+
+        if !self.options.data_files.is_empty() || !self.options.data_dirs.is_empty() {
+            let seen_hashes: DashSet<String> = entries.iter().map(|e| e.unique_hash()).collect();
+            entries.extend(load_extra_sources(
+                &self.options,
+                &pricing_fetcher,
+                &seen_hashes,
+            )?);
+            entries.sort_by_key(|e| e.timestamp);
+        }
+
+        entries.retain(|e| user_matches(&self.options, e));
+
+        Ok(entries)
+    }
+}
+
+/// Loader that remembers how far each JSONL file has been read
+///
+/// Live mode uses this to parse only the lines appended since the previous
+/// refresh. Deduplication state is kept across calls.
+pub struct IncrementalLoader {
+    options: CommonOptions,
+    pricing_fetcher: PricingFetcher,
+    offsets: HashMap<PathBuf, u64>,
+    seen_hashes: DashSet<String>,
+    extra_sources_loaded: bool,
+}
+
+impl IncrementalLoader {
+    pub fn new(options: CommonOptions, pricing_fetcher: PricingFetcher) -> Self {
+        Self {
+            options,
+            pricing_fetcher,
+            offsets: HashMap::new(),
+            seen_hashes: DashSet::new(),
+            extra_sources_loaded: false,
+        }
+    }
+
+    /// Read new lines from every JSONL file in the Claude data directories
+    ///
+    /// The history store, data files and directories are read on the first call
+    /// only; they aren't followed.
+    pub async fn load_all(&mut self) -> Result<Vec<LoadedUsageEntry>> {
+        let files = find_jsonl_files().await?;
+        let mut entries = self.load_changed(&files).await?;
+
+        if !self.extra_sources_loaded {
+            self.extra_sources_loaded = true;
+            let mut extra = load_history(&self.options, &entries)?;
+            for entry in &extra {
+                self.seen_hashes.insert(entry.unique_hash());
+            }
+            extra.extend(load_extra_sources(
+                &self.options,
+                &self.pricing_fetcher,
+                &self.seen_hashes,
+            )?);
+            extra.retain(|e| user_matches(&self.options, e));
+            entries.extend(extra);
+            entries.sort_by_key(|e| e.timestamp);
+        }
+
+        Ok(entries)
+    }
+
+    /// Read lines appended to the given files since they were last read
+    pub async fn load_changed(&mut self, paths: &[PathBuf]) -> Result<Vec<LoadedUsageEntry>> {
+        let mut new_entries = Vec::new();
+
+        for file_path in paths {
+            let source = TranscriptSource::from_path(file_path);
+            if !project_matches(&self.options, &source.project) {
+                continue;
+            }
+
+            // Files can disappear between the event and the read
+            if !file_path.exists() {
+                self.offsets.remove(file_path);
+                continue;
+            }
+
+            let offset = self.offsets.get(file_path).copied().unwrap_or(0);
+            let new_offset = stream_jsonl_file_from(file_path, offset, |line| {
+                if let Some(entry) = parse_line(
+                    line,
+                    &source,
+                    &self.options,
+                    &self.pricing_fetcher,
+                    &self.seen_hashes,
+                ) {
+                    new_entries.push(entry);
+                }
+            })?;
+
+            self.offsets.insert(file_path.clone(), new_offset);
+        }
+
+        new_entries.retain(|e| user_matches(&self.options, e));
+        new_entries.sort_by_key(|e| e.timestamp);
+
+        Ok(new_entries)
+    }
+}
+
+/// Stream complete lines starting at a byte offset, returning the offset after the last one
+///
+/// A trailing line without a newline is still being written, so it is left for the
+/// next read. If the file shrank below the offset it was replaced and is read again
+/// from the start.
+pub fn stream_jsonl_file_from<F>(file_path: &Path, offset: u64, mut process_line: F) -> Result<u64>
+where
+    F: FnMut(&str),
+{
+    let mut file = File::open(file_path)
+        .with_context(|| format!("Failed to open file: {}", file_path.display()))?;
+
+    let len = file.metadata()?.len();
+    let mut position = if offset > len { 0 } else { offset };
+    file.seek(SeekFrom::Start(position))?;
+
+    let mut reader = BufReader::new(file);
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        let read = reader
+            .read_until(b'\n', &mut buf)
+            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+
+        if read == 0 || buf.last() != Some(&b'\n') {
+            break;
+        }
+
+        position += read as u64;
+
+        // Silently skip lines that aren't valid UTF-8
+        if let Ok(line) = std::str::from_utf8(&buf) {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() {
+                process_line(trimmed);
+            }
+        }
+    }
+
+    Ok(position)
+}
+
+/// Check a project name against the `--project` filter
+fn project_matches(options: &CommonOptions, project: &str) -> bool {
+    options
+        .project
+        .as_ref()
+        .is_none_or(|target| target == project)
+}
+
+/// Parse one JSONL line into an entry, applying deduplication and date filters
+fn parse_line(
+    line: &str,
+    source: &TranscriptSource,
+    options: &CommonOptions,
+    pricing_fetcher: &PricingFetcher,
+    seen_hashes: &DashSet<String>,
+) -> Option<LoadedUsageEntry> {
+    // Parse JSON line
+    let data = serde_json::from_str::<UsageData>(line).ok()?;
+
+    // Skip API error messages
+    if data.is_api_error_message.unwrap_or(false) {
+        return None;
+    }
+
+    // Create entry synchronously for now (can optimize later with async streaming)
+    let entry_future =
+        parse_usage_entry(&data, source.project.clone(), options.mode, pricing_fetcher);
+    let mut entry =
+        tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(entry_future))
+            .ok()?;
+
+    // Subagent transcripts belong to the session that started them
+    if source.is_agent {
+        entry.is_sidechain = true;
+        if entry.session_id.is_none() {
+            entry.session_id = source.parent_session.clone();
+        }
+    }
+
+    // Deduplication check
+    let hash = entry.unique_hash();
+    if !hash.is_empty() && !seen_hashes.insert(hash) {
+        return None;
+    }
+
+    in_date_range(options, &entry).then_some(entry)
+}
+
+/// Check an entry against the `--since` and `--until` filters
+fn in_date_range(options: &CommonOptions, entry: &LoadedUsageEntry) -> bool {
+    let entry_date = entry.timestamp.date_naive();
+    options.since.is_none_or(|since| entry_date >= since)
+        && options.until.is_none_or(|until| entry_date <= until)
+}
+
+/// Check an entry against the `--user` filter
+fn user_matches(options: &CommonOptions, entry: &LoadedUsageEntry) -> bool {
+    options
+        .user
+        .as_ref()
+        .is_none_or(|user| user == entry.user_label())
+}
+
+/// Entries from the history store that aren't among the `live` ones
+///
+/// Date and project filters apply as for live entries, and only segments
+/// overlapping the date range are read. Nothing is read with `--no-history`.
+fn load_history(
+    options: &CommonOptions,
+    live: &[LoadedUsageEntry],
+) -> Result<Vec<LoadedUsageEntry>> {
+    if options.no_history {
+        return Ok(Vec::new());
+    }
+
+    let live: HashSet<String> = live.iter().map(|e| e.record_key()).collect();
+    Ok(HistoryStore::open_default()
+        .read_range(options.since, options.until)?
+        .into_iter()
+        .filter(|entry| {
+            let project = entry.project.as_deref().unwrap_or("unknown");
+            !live.contains(&entry.record_key())
+                && project_matches(options, project)
+                && in_date_range(options, entry)
+        })
+        .collect())
+}
+
+/// Read the `--data-dir` directories and `--data-file` archives
+///
+/// Entries already in `seen_hashes` are skipped. A labelled source attributes
+/// all of its entries to that user. Archived entries keep the cost they were
+/// priced at when exported.
+fn load_extra_sources(
+    options: &CommonOptions,
+    pricing_fetcher: &PricingFetcher,
+    seen_hashes: &DashSet<String>,
+) -> Result<Vec<LoadedUsageEntry>> {
+    let mut entries = Vec::new();
+    let mut add = |mut entry: LoadedUsageEntry, label: &Option<String>| {
+        let hash = entry.unique_hash();
+        if hash.is_empty() || seen_hashes.insert(hash) {
+            if label.is_some() {
+                entry.user = label.clone();
+            }
+            entries.push(entry);
+        }
+    };
+
+    for dir in &options.data_dirs {
+        let files = find_jsonl_files_in(&[projects_dir(&dir.path)?])?;
+        for entry in load_usage_entries_from(&files, options, pricing_fetcher)? {
+            add(entry, &dir.label);
+        }
+    }
+
+    for file in &options.data_files {
+        for entry in archive::read_archive(&file.path)? {
+            let project = entry.project.as_deref().unwrap_or("unknown");
+            if project_matches(options, project) && in_date_range(options, &entry) {
+                add(entry, &file.label);
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory for transcripts laid out like `~/.claude/projects`
+    fn projects_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rccusage-projects-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// An assistant transcript line in `session` with a request ID and a cost of $1
+    fn session_line(id: &str, session: Option<&str>) -> String {
+        let mut line = serde_json::json!({
+            "timestamp": "2026-10-18T09:00:00Z",
+            "requestId": format!("req_{id}"),
+            "costUSD": 1.0,
+            "message": {
+                "id": format!("msg_{id}"),
+                "model": "claude-sonnet-4-5-20250929",
+                "usage": { "input_tokens": 100, "output_tokens": 200 },
+            },
+        });
+        if let Some(session) = session {
+            line["sessionId"] = session.into();
+        }
+        format!("{}\n", line)
+    }
+
+    fn usage_line(id: &str) -> String {
+        session_line(id, Some("session-1"))
+    }
+
+    /// Lines streamed from `offset` and the offset returned
+    fn stream_from(path: &Path, offset: u64) -> (Vec<String>, u64) {
+        let mut lines = Vec::new();
+        let offset =
+            stream_jsonl_file_from(path, offset, |line| lines.push(line.to_string())).unwrap();
+        (lines, offset)
+    }
+
+    #[test]
+    fn stream_from_leaves_a_partial_line_for_later() {
+        let dir = projects_dir("partial");
+        let path = dir.join("session.jsonl");
+
+        std::fs::write(&path, "first\nsec").unwrap();
+        let (lines, offset) = stream_from(&path, 0);
+        assert_eq!(lines, ["first"]);
+        assert_eq!(offset, 6);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"ond\n").unwrap();
+        let (lines, offset) = stream_from(&path, offset);
+        assert_eq!(lines, ["second"]);
+        assert_eq!(offset, 13);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stream_from_restarts_a_truncated_file() {
+        let dir = projects_dir("truncated");
+        let path = dir.join("session.jsonl");
+
+        std::fs::write(&path, "first\nsecond\n").unwrap();
+        let (_, offset) = stream_from(&path, 0);
+        assert_eq!(offset, 13);
+
+        // Replaced by a shorter file
+        std::fs::write(&path, "new\n").unwrap();
+        let (lines, offset) = stream_from(&path, offset);
+        assert_eq!(lines, ["new"]);
+        assert_eq!(offset, 4);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn incremental_loader_reads_appended_lines_and_new_files() {
+        let dir = projects_dir("incremental");
+        let project = dir.join("-home-dev-alpha");
+        std::fs::create_dir_all(&project).unwrap();
+        let first = project.join("session-1.jsonl");
+        let second = project.join("session-2.jsonl");
+
+        let mut loader =
+            IncrementalLoader::new(CommonOptions::default(), PricingFetcher::new(true));
+        let request_ids = |entries: Vec<LoadedUsageEntry>| -> Vec<String> {
+            entries
+                .into_iter()
+                .filter_map(|e| e.request_id.map(|r| r.0))
+                .collect()
+        };
+
+        // A line still being written is not read yet
+        let line = usage_line("2");
+        std::fs::write(&first, format!("{}{}", usage_line("1"), &line[..40])).unwrap();
+        let entries = loader
+            .load_changed(std::slice::from_ref(&first))
+            .await
+            .unwrap();
+        assert_eq!(request_ids(entries), ["req_1"]);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&first)
+            .unwrap();
+        std::io::Write::write_all(&mut file, &line.as_bytes()[40..]).unwrap();
+        std::fs::write(&second, usage_line("3")).unwrap();
+
+        let files = [first.clone(), second];
+        let entries = loader.load_changed(&files).await.unwrap();
+        assert_eq!(request_ids(entries), ["req_2", "req_3"]);
+
+        // Nothing new, and lines already seen aren't read twice
+        assert!(loader.load_changed(&files).await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn transcript_source_finds_subagents_and_their_session() {
+        let main = TranscriptSource::from_path(Path::new(
+            "/home/me/.claude/projects/-home-dev-alpha/session-1.jsonl",
+        ));
+        assert_eq!(main.project, "-home-dev-alpha");
+        assert!(!main.is_agent);
+        assert_eq!(main.parent_session, None);
+
+        let nested = TranscriptSource::from_path(Path::new(
+            "/home/me/.claude/projects/-home-dev-alpha/session-1/subagents/agent-a1.jsonl",
+        ));
+        assert_eq!(nested.project, "-home-dev-alpha");
+        assert!(nested.is_agent);
+        assert_eq!(nested.parent_session, Some(SessionId::new("session-1")));
+
+        // Beside the session files, the parent comes from the lines themselves
+        let flat = TranscriptSource::from_path(Path::new(
+            "/home/me/.claude/projects/-home-dev-alpha/agent-a1.jsonl",
+        ));
+        assert!(flat.is_agent);
+        assert_eq!(flat.parent_session, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn subagent_usage_counts_toward_the_parent_session() {
+        let dir = projects_dir("subagents");
+        let project = dir.join("projects").join("-home-dev-alpha");
+        let subagents = project.join("session-1").join("subagents");
+        std::fs::create_dir_all(&subagents).unwrap();
+
+        let main = project.join("session-1.jsonl");
+        let nested = subagents.join("agent-a1.jsonl");
+        let flat = project.join("agent-b2.jsonl");
+        std::fs::write(&main, session_line("1", Some("session-1"))).unwrap();
+        std::fs::write(&nested, session_line("2", None)).unwrap();
+        std::fs::write(&flat, session_line("3", Some("session-1"))).unwrap();
+
+        let entries = load_usage_entries_from(
+            &[main, nested, flat],
+            &CommonOptions::default(),
+            &PricingFetcher::new(true),
+        )
+        .unwrap();
+        assert_eq!(entries.iter().filter(|e| e.is_sidechain).count(), 2);
+
+        let sessions = crate::aggregation::aggregate_sessions(entries, SortOrder::Asc);
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.session_id, SessionId::new("session-1"));
+        assert_eq!(session.main_thread.request_count, 1);
+        assert_eq!(session.main_thread.cost, Decimal::ONE);
+        assert_eq!(session.subagents.request_count, 2);
+        assert_eq!(session.subagents.cost, Decimal::TWO);
+        assert_eq!(session.subagents.output_tokens, 400);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
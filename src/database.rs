@@ -0,0 +1,504 @@
+//! Local SQLite history of usage entries, behind the `sqlite` feature.
+//!
+//! Claude Code prunes old transcripts, so entries are copied into a database
+//! that keeps them. Ingesting is incremental: transcript files whose size and
+//! modification time haven't changed since the last ingest are skipped, and
+//! entries already stored are ignored by their message and request IDs.
+//!
+//! Besides the `entries` table the database has these aggregates for ad-hoc
+//! SQL:
+//!
+//! - `daily_usage`: one row per UTC date
+//! - `session_usage`: one row per session and project
+//! - `block_usage`: one row per 5-hour billing block, rebuilt after each ingest
+
+use crate::aggregation::identify_session_blocks;
+use crate::data_loader::load_usage_entries_from;
+use crate::pricing::PricingFetcher;
+use crate::types::*;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OptionalExtension};
+use rust_decimal::prelude::*;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Schema version stored in `PRAGMA user_version`
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entries (
+    id INTEGER PRIMARY KEY,
+    key TEXT NOT NULL UNIQUE,
+    timestamp TEXT NOT NULL,
+    date TEXT NOT NULL,
+    model TEXT NOT NULL,
+    input_tokens INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    cache_creation_tokens INTEGER NOT NULL,
+    cache_read_tokens INTEGER NOT NULL,
+    cost REAL NOT NULL,
+    session_id TEXT,
+    request_id TEXT,
+    message_id TEXT,
+    project TEXT,
+    version TEXT,
+    is_sidechain INTEGER NOT NULL DEFAULT 0,
+    machine TEXT,
+    user TEXT
+);
+CREATE INDEX IF NOT EXISTS entries_timestamp ON entries (timestamp);
+CREATE INDEX IF NOT EXISTS entries_session ON entries (session_id);
+
+CREATE TABLE IF NOT EXISTS files (
+    path TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    modified INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS block_usage (
+    start_time TEXT PRIMARY KEY,
+    end_time TEXT NOT NULL,
+    last_timestamp TEXT,
+    requests INTEGER NOT NULL,
+    input_tokens INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    cache_creation_tokens INTEGER NOT NULL,
+    cache_read_tokens INTEGER NOT NULL,
+    total_tokens INTEGER NOT NULL,
+    cost REAL NOT NULL,
+    models TEXT NOT NULL
+);
+
+CREATE VIEW IF NOT EXISTS daily_usage AS
+SELECT
+    date,
+    COUNT(DISTINCT session_id) AS sessions,
+    COUNT(*) AS requests,
+    SUM(input_tokens) AS input_tokens,
+    SUM(output_tokens) AS output_tokens,
+    SUM(cache_creation_tokens) AS cache_creation_tokens,
+    SUM(cache_read_tokens) AS cache_read_tokens,
+    SUM(input_tokens + output_tokens + cache_creation_tokens + cache_read_tokens) AS total_tokens,
+    SUM(cost) AS cost,
+    GROUP_CONCAT(DISTINCT model) AS models
+FROM entries
+GROUP BY date;
+
+CREATE VIEW IF NOT EXISTS session_usage AS
+SELECT
+    session_id,
+    project,
+    MIN(timestamp) AS first_timestamp,
+    MAX(timestamp) AS last_timestamp,
+    COUNT(*) AS requests,
+    SUM(input_tokens) AS input_tokens,
+    SUM(output_tokens) AS output_tokens,
+    SUM(cache_creation_tokens) AS cache_creation_tokens,
+    SUM(cache_read_tokens) AS cache_read_tokens,
+    SUM(input_tokens + output_tokens + cache_creation_tokens + cache_read_tokens) AS total_tokens,
+    SUM(cost) AS cost,
+    GROUP_CONCAT(DISTINCT model) AS models
+FROM entries
+GROUP BY session_id, project;
+";
+
+/// Counts from one ingest
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestSummary {
+    /// Transcript files parsed because they were new or had changed
+    pub files_read: usize,
+
+    /// Transcript files skipped because they were unchanged
+    pub files_skipped: usize,
+
+    /// Entries stored by this ingest
+    pub new_entries: usize,
+
+    /// Entries in the database afterwards
+    pub total_entries: usize,
+}
+
+/// Columns and rows returned by [`UsageDatabase::query`]
+///
+/// Serializes as a list of objects keyed by column name, in column order.
+#[derive(Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+impl Serialize for QueryResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Row<'a>(&'a [String], &'a [serde_json::Value]);
+
+        impl Serialize for Row<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.0.len()))?;
+                for (column, value) in self.0.iter().zip(self.1) {
+                    map.serialize_entry(column, value)?;
+                }
+                map.end()
+            }
+        }
+
+        let mut seq = serializer.serialize_seq(Some(self.rows.len()))?;
+        for row in &self.rows {
+            seq.serialize_element(&Row(&self.columns, row))?;
+        }
+        seq.end()
+    }
+}
+
+/// A usage history database
+pub struct UsageDatabase {
+    conn: Connection,
+}
+
+impl UsageDatabase {
+    /// `~/.config/ccusage/usage.db`, or `~/.ccusage/usage.db` without a config directory
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .map(|d| d.join("ccusage"))
+            .or_else(|| dirs::home_dir().map(|d| d.join(".ccusage")))
+            .unwrap_or_default()
+            .join("usage.db")
+    }
+
+    /// Open the database at `path`, creating it and its schema if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open database: {}", path.display()))?;
+
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            bail!(
+                "Database {} has schema version {}, newer than supported version {}",
+                path.display(),
+                version,
+                SCHEMA_VERSION
+            );
+        }
+
+        conn.execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to create schema in {}", path.display()))?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Parse and store the transcript files that changed since the last ingest
+    ///
+    /// Entries are priced with `options.mode`; date, project and user filters
+    /// are ignored so the database keeps everything. Entries from a labelled
+    /// source are attributed to `user`. Block aggregates are rebuilt when
+    /// anything new was stored.
+    pub fn ingest_files(
+        &mut self,
+        files: &[PathBuf],
+        user: Option<&str>,
+        options: &CommonOptions,
+        pricing_fetcher: &PricingFetcher,
+    ) -> Result<IngestSummary> {
+        let options = CommonOptions {
+            since: None,
+            until: None,
+            project: None,
+            user: None,
+            ..options.clone()
+        };
+
+        let mut summary = IngestSummary::default();
+        let mut changed = Vec::new();
+        for path in files {
+            let Some((size, modified)) = file_stamp(path) else {
+                continue;
+            };
+            if self.file_stamp(path)? == Some((size, modified)) {
+                summary.files_skipped += 1;
+            } else {
+                changed.push((path.clone(), size, modified));
+            }
+        }
+
+        let tx = self.conn.transaction()?;
+        for (path, size, modified) in &changed {
+            let mut entries =
+                load_usage_entries_from(std::slice::from_ref(path), &options, pricing_fetcher)?;
+            if let Some(user) = user {
+                entries
+                    .iter_mut()
+                    .for_each(|e| e.user = Some(user.to_string()));
+            }
+
+            summary.new_entries += insert_entries(&tx, &entries)?;
+            summary.files_read += 1;
+
+            tx.execute(
+                "INSERT OR REPLACE INTO files (path, size, modified) VALUES (?1, ?2, ?3)",
+                params![path.to_string_lossy(), size, modified],
+            )?;
+        }
+        tx.commit()?;
+
+        if summary.new_entries > 0 {
+            self.refresh_blocks()?;
+        }
+        summary.total_entries = self.entry_count()?;
+
+        Ok(summary)
+    }
+
+    /// Store entries that aren't stored yet, returning how many were new
+    ///
+    /// Block aggregates are rebuilt when anything new was stored.
+    pub fn ingest(&mut self, entries: &[LoadedUsageEntry]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let inserted = insert_entries(&tx, entries)?;
+        tx.commit()?;
+
+        if inserted > 0 {
+            self.refresh_blocks()?;
+        }
+        Ok(inserted)
+    }
+
+    /// Number of stored entries
+    pub fn entry_count(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Every stored entry, sorted by timestamp
+    ///
+    /// Costs are read back from floating point, so they may differ from the
+    /// originally computed cost in the last decimal places.
+    pub fn entries(&self) -> Result<Vec<LoadedUsageEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, model, input_tokens, output_tokens, cache_creation_tokens,
+                    cache_read_tokens, cost, session_id, request_id, message_id, project,
+                    version, is_sidechain, machine, user
+             FROM entries ORDER BY timestamp",
+        )?;
+
+        let entries = stmt
+            .query_map([], |row| {
+                let timestamp: String = row.get(0)?;
+                Ok(LoadedUsageEntry {
+                    timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                        .map(|t| t.with_timezone(&Utc))
+                        .unwrap_or_default(),
+                    model: ModelName(row.get(1)?),
+                    tokens: TokenCounts {
+                        input_tokens: row.get::<_, i64>(2)? as u64,
+                        output_tokens: row.get::<_, i64>(3)? as u64,
+                        cache_creation_input_tokens: row.get::<_, i64>(4)? as u64,
+                        cache_read_input_tokens: row.get::<_, i64>(5)? as u64,
+                    },
+                    cost: Decimal::from_f64(row.get(6)?).unwrap_or_default(),
+                    session_id: row.get::<_, Option<String>>(7)?.map(SessionId),
+                    request_id: row.get::<_, Option<String>>(8)?.map(RequestId),
+                    message_id: row.get::<_, Option<String>>(9)?.map(MessageId),
+                    project: row.get(10)?,
+                    version: row.get(11)?,
+                    cwd: None,
+                    git_branch: None,
+                    is_sidechain: row.get(12)?,
+                    machine: row.get(13)?,
+                    user: row.get(14)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Run one read-only SQL statement and collect its result rows
+    ///
+    /// Statements that would write to the database, such as `DELETE`, `DROP`
+    /// or `UPDATE`, are rejected before they run.
+    pub fn query(&self, sql: &str) -> Result<QueryResult> {
+        let mut stmt = self.conn.prepare(sql).context("Invalid SQL")?;
+        if !stmt.readonly() {
+            bail!("Only read-only statements can be queried; the database is updated by ingest");
+        }
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+        let mut rows = Vec::new();
+        let mut result = stmt.query([])?;
+        while let Some(row) = result.next()? {
+            let values = (0..columns.len())
+                .map(|i| row.get_ref(i).map(json_value))
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows.push(values);
+        }
+
+        Ok(QueryResult { columns, rows })
+    }
+
+    /// Size and modification time recorded for `path` by the last ingest
+    fn file_stamp(&self, path: &Path) -> Result<Option<(i64, i64)>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT size, modified FROM files WHERE path = ?1",
+                params![path.to_string_lossy()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    /// Recompute `block_usage` from every stored entry
+    fn refresh_blocks(&mut self) -> Result<()> {
+        let blocks = identify_session_blocks(self.entries()?, None);
+
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM block_usage", [])?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO block_usage (start_time, end_time, last_timestamp, requests,
+                    input_tokens, output_tokens, cache_creation_tokens, cache_read_tokens,
+                    total_tokens, cost, models)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for block in blocks.iter().filter(|b| b.is_gap != Some(true)) {
+                let tokens = &block.token_counts;
+                insert.execute(params![
+                    block.start_time.to_rfc3339(),
+                    block.end_time.to_rfc3339(),
+                    block.actual_end_time.map(|t| t.to_rfc3339()),
+                    block.requests.count as i64,
+                    tokens.input_tokens as i64,
+                    tokens.output_tokens as i64,
+                    tokens.cache_creation_input_tokens as i64,
+                    tokens.cache_read_input_tokens as i64,
+                    tokens.total() as i64,
+                    block.cost_usd.to_f64().unwrap_or(0.0),
+                    block.models.join(","),
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+/// Insert entries with `INSERT OR IGNORE`, returning how many were new
+fn insert_entries(conn: &Connection, entries: &[LoadedUsageEntry]) -> Result<usize> {
+    let mut insert = conn.prepare_cached(
+        "INSERT OR IGNORE INTO entries (key, timestamp, date, model, input_tokens, output_tokens,
+            cache_creation_tokens, cache_read_tokens, cost, session_id, request_id, message_id,
+            project, version, is_sidechain, machine, user)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+    )?;
+
+    let mut inserted = 0;
+    for entry in entries {
+        inserted += insert.execute(params![
+            entry.record_key(),
+            entry.timestamp.to_rfc3339(),
+            entry.timestamp.date_naive().to_string(),
+            entry.model.as_str(),
+            entry.tokens.input_tokens as i64,
+            entry.tokens.output_tokens as i64,
+            entry.tokens.cache_creation_input_tokens as i64,
+            entry.tokens.cache_read_input_tokens as i64,
+            entry.cost.to_f64().unwrap_or(0.0),
+            entry.session_id.as_ref().map(|s| s.0.as_str()),
+            entry.request_id.as_ref().map(|r| r.0.as_str()),
+            entry.message_id.as_ref().map(|m| m.0.as_str()),
+            entry.project,
+            entry.version,
+            entry.is_sidechain,
+            entry.machine,
+            entry.user,
+        ])?;
+    }
+
+    Ok(inserted)
+}
+
+/// Size and whole-second modification time of a file
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((metadata.len() as i64, modified as i64))
+}
+
+fn json_value(value: ValueRef<'_>) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => f.into(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned().into(),
+        ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(name: &str) -> (UsageDatabase, PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("rccusage-db-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        (UsageDatabase::open(&path).unwrap(), path)
+    }
+
+    #[test]
+    fn query_returns_columns_and_rows() {
+        let (db, path) = temp_db("select");
+
+        let result = db
+            .query("SELECT COUNT(*) AS entries, 'x' AS name FROM entries")
+            .unwrap();
+        assert_eq!(result.columns, vec!["entries", "name"]);
+        assert_eq!(result.rows.len(), 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn query_rejects_statements_that_write() {
+        let (db, path) = temp_db("write");
+
+        for sql in [
+            "DELETE FROM entries",
+            "DROP TABLE entries",
+            "UPDATE entries SET cost = 0",
+            "INSERT INTO files (path, size, modified) VALUES ('x', 1, 1)",
+            "PRAGMA user_version = 0",
+        ] {
+            let err = db.query(sql).unwrap_err();
+            assert!(err.to_string().contains("read-only"), "{}: {}", sql, err);
+        }
+
+        // The schema is untouched
+        assert!(db.query("SELECT * FROM entries").is_ok());
+        let version: i64 = db
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,311 @@
+//! Local history of usage entries that outlives Claude Code's transcript cleanup.
+//!
+//! Claude Code deletes transcripts older than `cleanupPeriodDays`, so all-time
+//! reports shrink over time. `archive sync` copies entries from the local data
+//! directories into a [`HistoryStore`]: a directory of archive segments, one per
+//! sync that found new entries. Segments are only ever added, never rewritten.
+//! [`UsageLoader`](crate::UsageLoader) reads the store alongside the live
+//! transcripts, so entries whose transcripts are gone still count.
+//!
+//! Segment names carry the UTC dates of their first and last entry, as in
+//! `usage-20261018T140600123Z-20260903-20261018.jsonl`, so a report for a date
+//! range only reads the segments that overlap it.
+
+use crate::archive::{self, default_machine_label};
+use crate::types::LoadedUsageEntry;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Environment variable overriding the store directory
+pub const HISTORY_DIR_ENV: &str = "CCUSAGE_HISTORY_DIR";
+
+/// `CCUSAGE_HISTORY_DIR`, or `~/.config/ccusage/history` (`~/.ccusage/history`
+/// without a config directory)
+pub fn default_history_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(HISTORY_DIR_ENV).filter(|d| !d.is_empty()) {
+        return PathBuf::from(dir);
+    }
+
+    dirs::config_dir()
+        .map(|d| d.join("ccusage"))
+        .or_else(|| dirs::home_dir().map(|d| d.join(".ccusage")))
+        .unwrap_or_default()
+        .join("history")
+}
+
+/// Counts from one [`HistoryStore::sync`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSummary {
+    pub store: PathBuf,
+
+    /// Segment written by this sync; `None` when nothing was new
+    pub segment: Option<PathBuf>,
+
+    pub new_entries: usize,
+    pub total_entries: usize,
+}
+
+/// Append-only store of usage entries
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    dir: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The store at [`default_history_dir`]
+    pub fn open_default() -> Self {
+        Self::new(default_history_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Segment files, oldest first; empty when the store doesn't exist yet
+    pub fn segments(&self) -> Result<Vec<PathBuf>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut segments: Vec<PathBuf> = std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read history: {}", self.dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect();
+        segments.sort();
+
+        Ok(segments)
+    }
+
+    /// Every stored entry once, sorted by timestamp
+    ///
+    /// Unreadable segments are skipped with a warning rather than failing
+    /// every report.
+    pub fn read(&self) -> Result<Vec<LoadedUsageEntry>> {
+        self.read_range(None, None)
+    }
+
+    /// Stored entries from segments overlapping `since..=until` (UTC dates)
+    ///
+    /// Segments are skipped by the date range in their name without being
+    /// opened; segments named without one are always read. Entries of the
+    /// segments that are read aren't filtered, so the result can extend past
+    /// the range.
+    pub fn read_range(
+        &self,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> Result<Vec<LoadedUsageEntry>> {
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+
+        for segment in self.segments()? {
+            if let Some((first, last)) = segment_dates(&segment) {
+                if since.is_some_and(|since| last < since)
+                    || until.is_some_and(|until| first > until)
+                {
+                    continue;
+                }
+            }
+
+            match archive::read_entries(&segment) {
+                Ok((_, segment_entries)) => entries.extend(
+                    segment_entries
+                        .into_iter()
+                        .filter(|e| seen.insert(e.record_key())),
+                ),
+                Err(err) => warn!("Skipping history segment: {:#}", err),
+            }
+        }
+
+        entries.sort_by_key(|e| e.timestamp);
+        Ok(entries)
+    }
+
+    /// Append the entries not stored yet as a new segment
+    ///
+    /// The segment is written under a temporary name and renamed into place, so
+    /// an interrupted sync never leaves a partial segment behind.
+    pub fn sync(&self, entries: &[LoadedUsageEntry]) -> Result<SyncSummary> {
+        let stored = self.read()?;
+        let mut seen: HashSet<String> = stored.iter().map(|e| e.record_key()).collect();
+
+        let new_entries: Vec<LoadedUsageEntry> = entries
+            .iter()
+            .filter(|e| seen.insert(e.record_key()))
+            .cloned()
+            .collect();
+
+        let segment = if new_entries.is_empty() {
+            None
+        } else {
+            std::fs::create_dir_all(&self.dir)
+                .with_context(|| format!("Failed to create history: {}", self.dir.display()))?;
+
+            let name = segment_name(&new_entries);
+            let segment = self.dir.join(format!("{name}.jsonl"));
+            let partial = self.dir.join(format!("{name}.partial"));
+
+            archive::write_entries(&partial, &new_entries, &default_machine_label(), None)?;
+            std::fs::rename(&partial, &segment)
+                .with_context(|| format!("Failed to write history: {}", segment.display()))?;
+
+            Some(segment)
+        };
+
+        Ok(SyncSummary {
+            store: self.dir.clone(),
+            segment,
+            new_entries: new_entries.len(),
+            total_entries: stored.len() + new_entries.len(),
+        })
+    }
+}
+
+/// Name for a new segment: sync time, then the dates of its first and last entry
+fn segment_name(entries: &[LoadedUsageEntry]) -> String {
+    let date = |entry: Option<&LoadedUsageEntry>| {
+        entry.map_or_else(String::new, |e| e.timestamp.format("%Y%m%d").to_string())
+    };
+
+    format!(
+        "usage-{}-{}-{}",
+        Utc::now().format("%Y%m%dT%H%M%S%3fZ"),
+        date(entries.iter().min_by_key(|e| e.timestamp)),
+        date(entries.iter().max_by_key(|e| e.timestamp))
+    )
+}
+
+/// Dates of the first and last entry, from a segment name written by [`segment_name`]
+fn segment_dates(path: &Path) -> Option<(NaiveDate, NaiveDate)> {
+    let stem = path.file_stem()?.to_str()?;
+    let mut parts = stem.strip_prefix("usage-")?.split('-').skip(1);
+    let mut date = || NaiveDate::parse_from_str(parts.next()?, "%Y%m%d").ok();
+
+    Some((date()?, date()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MessageId, ModelName, RequestId, SessionId, TokenCounts};
+    use chrono::{DateTime, TimeZone};
+    use rust_decimal::Decimal;
+
+    fn entry(time: DateTime<Utc>, id: &str) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: time,
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: 100,
+                ..TokenCounts::default()
+            },
+            cost: Decimal::ONE,
+            session_id: Some(SessionId::new("session-1")),
+            request_id: Some(RequestId::new(format!("req-{id}"))),
+            message_id: Some(MessageId::new(format!("msg-{id}"))),
+            project: None,
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 9, day, 12, 0, 0).unwrap()
+    }
+
+    fn date(day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2026, 9, day)
+    }
+
+    fn temp_store(name: &str) -> HistoryStore {
+        let dir =
+            std::env::temp_dir().join(format!("rccusage-history-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        HistoryStore::new(dir)
+    }
+
+    #[test]
+    fn segment_names_carry_their_date_range() {
+        let store = temp_store("names");
+
+        let summary = store
+            .sync(&[entry(day(9), "b"), entry(day(3), "a")])
+            .unwrap();
+        let segment = summary.segment.unwrap();
+
+        let name = segment.file_name().unwrap().to_str().unwrap();
+        assert!(name.ends_with("-20260903-20260909.jsonl"), "{}", name);
+        assert_eq!(
+            segment_dates(&segment),
+            Some((date(3).unwrap(), date(9).unwrap()))
+        );
+        assert_eq!(
+            segment_dates(Path::new("usage-20261018T140600123Z.jsonl")),
+            None
+        );
+
+        let _ = std::fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn read_range_skips_segments_outside_the_range() {
+        let store = temp_store("range");
+        store
+            .sync(&[entry(day(1), "a"), entry(day(2), "b")])
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        store
+            .sync(&[entry(day(10), "c"), entry(day(12), "d")])
+            .unwrap();
+
+        let ids = |entries: Vec<LoadedUsageEntry>| -> Vec<String> {
+            entries
+                .into_iter()
+                .filter_map(|e| e.message_id.map(|id| id.0))
+                .collect()
+        };
+
+        assert_eq!(store.read().unwrap().len(), 4);
+        assert_eq!(
+            ids(store.read_range(date(5), None).unwrap()),
+            vec!["msg-c", "msg-d"]
+        );
+        assert_eq!(
+            ids(store.read_range(None, date(2)).unwrap()),
+            vec!["msg-a", "msg-b"]
+        );
+        assert_eq!(ids(store.read_range(date(12), date(20)).unwrap()).len(), 2);
+        assert!(store.read_range(date(3), date(9)).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn segments_without_dates_are_always_read() {
+        let store = temp_store("legacy");
+        let segment = store.sync(&[entry(day(1), "a")]).unwrap().segment.unwrap();
+        std::fs::rename(
+            &segment,
+            store.dir().join("usage-20260901T000000000Z.jsonl"),
+        )
+        .unwrap();
+
+        assert_eq!(store.read_range(date(20), None).unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(store.dir());
+    }
+}
@@ -0,0 +1,67 @@
+//! Fast usage analysis for Claude Code transcripts.
+//!
+//! `rccusage` reads the JSONL transcripts Claude Code writes under
+//! `~/.claude/projects` (or `~/.config/claude/projects`), prices each request and
+//! aggregates the results into daily, weekly, monthly, session and 5-hour block
+//! reports. The `rccusage` binary is a thin command-line layer over this crate.
+//!
+//! The main entry points are:
+//!
+//! - [`UsageLoader`] to find, parse, price and deduplicate usage entries
+//! - [`aggregation`] to roll entries up into report rows
+//! - [`analytics`] for derived analyses such as cache efficiency
+//! - [`PricingFetcher`] and [`ModelPricing`] for per-model token prices
+//! - [`output`] to render rows as tables or JSON
+//! - [`live::LiveMonitor`] to follow transcripts as they are written
+//! - [`archive`] to export entries and combine usage from other machines
+//! - [`history`] to keep entries after Claude Code deletes old transcripts
+//! - `columnar` (with the `parquet` feature) to export entries as Parquet or Arrow datasets
+//! - `database` (with the `sqlite` feature) to keep entries in a local SQLite history
+//! - `notifications` (with the default `notifications` feature) to send webhook alerts on thresholds
+//! - `otlp` (with the `otlp` feature) to send usage to an OpenTelemetry collector
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rccusage::aggregation::aggregate_daily;
+//! use rccusage::output::table::render_daily_table;
+//! use rccusage::types::SortOrder;
+//! use rccusage::UsageLoader;
+//!
+//! # #[tokio::main(flavor = "multi_thread")]
+//! # async fn main() -> anyhow::Result<()> {
+//! let entries = UsageLoader::new()
+//!     .offline(true)
+//!     .project("-home-me-my-app")
+//!     .load()
+//!     .await?;
+//!
+//! let daily = aggregate_daily(entries, SortOrder::Asc);
+//! print!("{}", render_daily_table(&daily, false, false));
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod aggregation;
+pub mod analytics;
+pub mod archive;
+#[cfg(feature = "parquet")]
+pub mod columnar;
+pub mod config;
+pub mod data_loader;
+#[cfg(feature = "sqlite")]
+pub mod database;
+pub mod history;
+pub mod live;
+pub mod logger;
+#[cfg(feature = "notifications")]
+pub mod notifications;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod output;
+pub mod pricing;
+pub mod types;
+pub mod utils;
+
+pub use data_loader::UsageLoader;
+pub use pricing::{ModelPricing, PricingFetcher};
@@ -1,13 +1,39 @@
-use crate::data_loader::get_claude_data_dirs;
+//! Watching transcripts and redrawing reports as they change.
+
+use crate::aggregation::IncrementalAggregate;
+use crate::data_loader::{get_claude_data_dirs, IncrementalLoader};
+use crate::logger;
+#[cfg(feature = "notifications")]
+use crate::notifications::Notifier;
+use crate::pricing::PricingFetcher;
+use crate::types::{CommonOptions, LoadedUsageEntry};
 use anyhow::{Context, Result};
-use notify::{Event, EventKind, RecursiveMode, Watcher};
+use chrono::Local;
+use colored::*;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use itertools::Itertools;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::future::Future;
+use std::io::{self, Write};
 use std::path::PathBuf;
 use std::sync::mpsc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tracing::info;
+#[cfg(feature = "notifications")]
+use tracing::warn;
+
+/// Default interval between timed refreshes in watch mode
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Live monitoring mode for watching file changes
 pub struct LiveMonitor {
     dirs: Vec<PathBuf>,
+    refresh_interval: Duration,
+    #[cfg(feature = "notifications")]
+    notifier: Option<Notifier>,
 }
 
 impl LiveMonitor {
@@ -15,14 +41,30 @@ impl LiveMonitor {
     pub fn new() -> Self {
         Self {
             dirs: get_claude_data_dirs(),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            #[cfg(feature = "notifications")]
+            notifier: None,
         }
     }
 
-    /// Start watching for file changes and run callback on changes
-    pub fn watch<F>(&self, mut on_change: F) -> Result<()>
-    where
-        F: FnMut() -> Result<()>,
-    {
+    /// Set how often the report is refreshed when no files change
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// Post webhook alerts while watching, whenever new usage arrives
+    #[cfg(feature = "notifications")]
+    pub fn with_notifier(mut self, notifier: Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Start a file watcher over the Claude data directories
+    ///
+    /// Returns the watcher (which must be kept alive for events to flow) and a
+    /// receiver that yields the path of every JSONL file that is created or modified.
+    pub fn subscribe(&self) -> Result<(RecommendedWatcher, mpsc::Receiver<PathBuf>)> {
         // Create a channel to receive file system events
         let (tx, rx) = mpsc::channel();
 
@@ -32,10 +74,9 @@ impl LiveMonitor {
                 // Only care about modifications and creations to JSONL files
                 match event.kind {
                     EventKind::Create(_) | EventKind::Modify(_) => {
-                        for path in &event.paths {
+                        for path in event.paths {
                             if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                                let _ = tx.send(());
-                                break;
+                                let _ = tx.send(path);
                             }
                         }
                     }
@@ -52,31 +93,132 @@ impl LiveMonitor {
                 .with_context(|| format!("Failed to watch directory: {}", dir.display()))?;
         }
 
-        println!("Watching for changes in Claude data directories...");
-        println!("Press Ctrl+C to stop.");
+        Ok((watcher, rx))
+    }
+
+    /// Watch for file changes and redraw the rendered report
+    ///
+    /// All usage is loaded once into an incremental aggregate. After that only lines
+    /// appended to changed files are parsed, and the report is re-rendered from the
+    /// aggregate on every batch of file events and at least once per refresh interval.
+    /// Frames are drawn on the alternate screen by overwriting the previous frame in
+    /// place, so the terminal does not flicker between refreshes. With a notifier,
+    /// alert thresholds are checked after every batch of new entries.
+    pub async fn watch<F>(&self, options: &CommonOptions, mut render: F) -> Result<()>
+    where
+        F: FnMut(&IncrementalAggregate) -> Result<String>,
+    {
+        let (_watcher, rx) = self.subscribe()?;
+
+        let pricing_fetcher = PricingFetcher::new(options.offline);
+        let mut loader = IncrementalLoader::new(options.clone(), pricing_fetcher);
+        let mut aggregate = IncrementalAggregate::default();
+
+        info!("Loading usage data...");
+        aggregate.extend(loader.load_all().await?);
+        #[cfg(feature = "notifications")]
+        self.notify(&aggregate).await;
+
+        let _screen = AlternateScreen::enter()?;
 
-        // Initial run
-        on_change()?;
+        let mut last_refresh = Instant::now();
+        draw_frame(&render(&aggregate)?, self.refresh_interval)?;
+
+        let mut ticker = tokio::time::interval(Duration::from_millis(100));
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
 
-        // Watch for changes
         loop {
-            match rx.recv_timeout(Duration::from_secs(1)) {
-                Ok(_) => {
-                    // Debounce multiple events
-                    std::thread::sleep(Duration::from_millis(100));
+            tokio::select! {
+                _ = &mut ctrl_c => break,
+                _ = ticker.tick() => {
+                    // Collect every file touched since the last tick
+                    let changed: Vec<PathBuf> = rx.try_iter().unique().collect();
+
+                    if !changed.is_empty() {
+                        let entries = loader.load_changed(&changed).await?;
+                        if !entries.is_empty() {
+                            aggregate.extend(entries);
 
-                    // Drain any additional events
-                    while rx.try_recv().is_ok() {}
+                            #[cfg(feature = "notifications")]
+                            self.notify(&aggregate).await;
+                        }
+                    }
 
-                    // Clear screen and run callback
-                    print!("\x1B[2J\x1B[1;1H"); // Clear screen
-                    on_change()?;
+                    if !changed.is_empty() || last_refresh.elapsed() >= self.refresh_interval {
+                        last_refresh = Instant::now();
+                        draw_frame(&render(&aggregate)?, self.refresh_interval)?;
+                    }
                 }
-                Err(mpsc::RecvTimeoutError::Timeout) => {
-                    // No events, continue watching
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check alert thresholds after new entries arrive
+    ///
+    /// Failures are only logged; a webhook that is down must not stop the report.
+    #[cfg(feature = "notifications")]
+    async fn notify(&self, aggregate: &IncrementalAggregate) {
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+
+        match notifier.check(aggregate, chrono::Utc::now()).await {
+            Ok(summary) => {
+                for failure in summary.failures {
+                    warn!(
+                        "Failed to send alert {} to {}: {}",
+                        failure.key, failure.webhook, failure.error
+                    );
                 }
-                Err(mpsc::RecvTimeoutError::Disconnected) => {
-                    break;
+            }
+            Err(err) => warn!("Failed to check alerts: {:#}", err),
+        }
+    }
+}
+
+impl LiveMonitor {
+    /// Watch for file changes and hand new entries to `on_entries` until Ctrl+C
+    ///
+    /// `on_entries` is first called with every entry already written, then with the
+    /// entries appended since the previous call, on every batch of file events and
+    /// at least once per refresh interval (with an empty batch when nothing changed).
+    /// Nothing is drawn, so the caller can log progress to the terminal.
+    pub async fn follow<F, Fut>(&self, options: &CommonOptions, mut on_entries: F) -> Result<()>
+    where
+        F: FnMut(Vec<LoadedUsageEntry>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let (_watcher, rx) = self.subscribe()?;
+
+        let pricing_fetcher = PricingFetcher::new(options.offline);
+        let mut loader = IncrementalLoader::new(options.clone(), pricing_fetcher);
+
+        info!("Loading usage data...");
+        on_entries(loader.load_all().await?).await?;
+
+        let mut last_call = Instant::now();
+        let mut ticker = tokio::time::interval(Duration::from_millis(100));
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            tokio::select! {
+                _ = &mut ctrl_c => break,
+                _ = ticker.tick() => {
+                    let changed: Vec<PathBuf> = rx.try_iter().unique().collect();
+
+                    if !changed.is_empty() || last_call.elapsed() >= self.refresh_interval {
+                        last_call = Instant::now();
+                        let entries = if changed.is_empty() {
+                            Vec::new()
+                        } else {
+                            loader.load_changed(&changed).await?
+                        };
+                        on_entries(entries).await?;
+                    }
                 }
             }
         }
@@ -84,3 +226,60 @@ impl LiveMonitor {
         Ok(())
     }
 }
+
+impl Default for LiveMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Guard that keeps the terminal on the alternate screen until dropped
+struct AlternateScreen;
+
+impl AlternateScreen {
+    fn enter() -> Result<Self> {
+        execute!(io::stdout(), EnterAlternateScreen, Hide)
+            .context("Failed to enter alternate screen")?;
+        logger::set_console_suspended(true);
+        Ok(Self)
+    }
+}
+
+impl Drop for AlternateScreen {
+    fn drop(&mut self) {
+        logger::set_console_suspended(false);
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+    }
+}
+
+/// Overwrite the previous frame with a status line followed by the report
+fn draw_frame(report: &str, refresh_interval: Duration) -> Result<()> {
+    let mut out = io::stdout().lock();
+    let status = format!(
+        "Watching Claude data directories · refresh every {}s · updated {} · Ctrl+C to exit",
+        refresh_interval.as_secs(),
+        Local::now().format("%H:%M:%S")
+    );
+
+    queue!(
+        out,
+        MoveTo(0, 0),
+        Print(status.dimmed()),
+        Clear(ClearType::UntilNewLine)
+    )?;
+    queue!(out, Print("\r\n"), Clear(ClearType::UntilNewLine))?;
+
+    for line in report.lines() {
+        queue!(
+            out,
+            Print("\r\n"),
+            Print(line),
+            Clear(ClearType::UntilNewLine)
+        )?;
+    }
+
+    queue!(out, Clear(ClearType::FromCursorDown))?;
+    out.flush()?;
+
+    Ok(())
+}
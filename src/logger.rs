@@ -1,7 +1,19 @@
+//! Logging setup controlled by the `LOG_LEVEL` environment variable.
+
 use log::LevelFilter;
 use std::env;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
+/// Set while a full-screen view owns the terminal, so log lines don't corrupt frames
+static CONSOLE_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+/// Suspend or resume writing log lines to the terminal
+pub fn set_console_suspended(suspended: bool) {
+    CONSOLE_SUSPENDED.store(suspended, Ordering::SeqCst);
+}
+
 /// Initialize logging based on LOG_LEVEL environment variable
 /// LOG_LEVEL values:
 /// - 0 = silent
@@ -43,6 +55,14 @@ pub fn init_logger() {
         .with_target(false)
         .with_thread_ids(false)
         .with_thread_names(false)
+        .with_writer(|| -> Box<dyn io::Write> {
+            // Log to stderr so JSON output on stdout stays parseable
+            if CONSOLE_SUSPENDED.load(Ordering::SeqCst) {
+                Box::new(io::sink())
+            } else {
+                Box::new(io::stderr())
+            }
+        })
         .finish();
 
     tracing::subscriber::set_global_default(subscriber)
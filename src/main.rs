@@ -1,26 +1,23 @@
-mod aggregation;
 mod commands;
-mod config;
-mod data_loader;
-mod live;
-mod logger;
-mod output;
-mod pricing;
-mod types;
-mod utils;
+mod dashboard;
 
 use anyhow::Result;
 use clap::Parser;
+use rccusage::{config, logger};
+use tracing::warn;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging based on LOG_LEVEL env var
     logger::init_logger();
 
-    // Load config file if present (currently unused, reserved for future use)
-    let _config = config::Config::load().unwrap_or_default();
+    // Load config file if present; a broken one is reported and ignored
+    let config = config::Config::load().unwrap_or_else(|e| {
+        warn!("Ignoring config file, using defaults: {:#}", e);
+        config::Config::default()
+    });
 
     // Parse CLI arguments and run command
     let cli = commands::Cli::parse();
-    cli.run().await
+    cli.run(&config).await
 }
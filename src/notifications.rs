@@ -0,0 +1,960 @@
+//! Webhook alerts when usage crosses a threshold, behind the default `notifications` feature.
+//!
+//! Alerts are evaluated from the running totals of an
+//! [`IncrementalAggregate`] by [`evaluate`]:
+//!
+//! - `blockLimit` when the active 5-hour block reaches one of the configured
+//!   percentages of the token limit
+//! - `dailyBudget` when the current UTC day's cost exceeds `budget.daily`
+//! - `anomaly` for days and sessions of the last two days that the `anomalies`
+//!   command would report
+//!
+//! Every alert has a stable key such as `block:2026-10-18T10:00:00+00:00:80` or
+//! `daily:2026-10-18`. A state file records which webhooks each key was delivered
+//! to, so an alert fires once per webhook however often `check` runs, and a
+//! webhook that failed is retried on the next check.
+
+use crate::aggregation::IncrementalAggregate;
+use crate::analytics::anomalies::{
+    detect_anomalies_in, AnomalyKind, AnomalyMethod, AnomalyOptions,
+};
+use crate::archive::default_machine_label;
+use crate::config::{Config, WebhookConfig, WebhookFormat};
+use crate::output::table::format_tokens_compact;
+use crate::output::template::Template;
+use crate::types::{DailyDate, SortOrder};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the state file
+pub const STATE_FILE_ENV: &str = "CCUSAGE_NOTIFY_STATE";
+
+/// Placeholders available in webhook templates
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &["title", "message", "kind", "severity", "key"];
+
+/// Anomalies older than this are not alerted on
+const ANOMALY_WINDOW_HOURS: i64 = 48;
+
+/// Delivered alerts are forgotten after this long; every alert key refers to a
+/// block, day or anomaly that is well outside its window by then
+const STATE_RETENTION_DAYS: i64 = 14;
+
+/// Longest wait for one webhook to answer
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// `CCUSAGE_NOTIFY_STATE`, or `~/.config/ccusage/notifications.json`
+/// (`~/.ccusage/notifications.json` without a config directory)
+pub fn default_state_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(STATE_FILE_ENV).filter(|p| !p.is_empty()) {
+        return PathBuf::from(path);
+    }
+
+    dirs::config_dir()
+        .map(|d| d.join("ccusage"))
+        .or_else(|| dirs::home_dir().map(|d| d.join(".ccusage")))
+        .unwrap_or_default()
+        .join("notifications.json")
+}
+
+/// Threshold an alert was raised for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertKind {
+    BlockLimit,
+    DailyBudget,
+    Anomaly,
+}
+
+impl AlertKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::BlockLimit => "blockLimit",
+            Self::DailyBudget => "dailyBudget",
+            Self::Anomaly => "anomaly",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// A crossed threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub kind: AlertKind,
+
+    /// Identifies the alert across checks
+    pub key: String,
+
+    pub severity: Severity,
+    pub title: String,
+    pub message: String,
+
+    /// Start of the block, day or anomalous session
+    pub time: DateTime<Utc>,
+
+    /// Tokens for blocks, USD otherwise
+    pub value: f64,
+
+    /// Token limit, daily budget or anomaly baseline
+    pub threshold: f64,
+}
+
+/// Thresholds checked by [`evaluate`]
+#[derive(Debug, Clone, Default)]
+pub struct AlertRules {
+    /// Token limit of a block; no block alerts without one
+    pub token_limit: Option<u64>,
+
+    /// Percentages of the token limit to alert at
+    pub block_percents: Vec<f64>,
+
+    pub daily_budget: Option<Decimal>,
+
+    /// Anomaly detection settings; no anomaly alerts when `None`
+    pub anomalies: Option<AnomalyOptions>,
+}
+
+impl AlertRules {
+    /// Rules from the `notifications` and `budget` sections of the config
+    ///
+    /// Anomalies use the `anomalies` command's defaults.
+    pub fn from_config(config: &Config) -> Self {
+        let notifications = &config.notifications;
+
+        Self {
+            token_limit: notifications
+                .token_limit
+                .or(config.statusline.token_limit)
+                .filter(|&limit| limit > 0),
+            block_percents: notifications.block_percents.clone(),
+            daily_budget: config.budget.daily,
+            anomalies: notifications.anomalies.then(|| AnomalyOptions {
+                method: AnomalyMethod::Mad,
+                threshold: AnomalyMethod::Mad.default_threshold(),
+                history: 30,
+                min_cost: Decimal::ONE,
+            }),
+        }
+    }
+
+    /// Whether no alert can ever fire
+    pub fn is_empty(&self) -> bool {
+        (self.token_limit.is_none() || self.block_percents.is_empty())
+            && self.daily_budget.is_none()
+            && self.anomalies.is_none()
+    }
+}
+
+/// Alerts whose thresholds are crossed as of `now`
+///
+/// Works from the running totals of an [`IncrementalAggregate`], so watch mode
+/// can check after every batch without keeping or rescanning raw entries.
+pub fn evaluate(
+    aggregate: &IncrementalAggregate,
+    rules: &AlertRules,
+    now: DateTime<Utc>,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    alerts.extend(block_alert(aggregate, rules, now));
+    alerts.extend(daily_alert(aggregate, rules, now));
+
+    if let Some(options) = rules.anomalies {
+        let since = now - Duration::hours(ANOMALY_WINDOW_HOURS);
+        let days = aggregate.daily(SortOrder::Asc);
+        alerts.extend(
+            detect_anomalies_in(&days, aggregate.sessions(SortOrder::Asc), options)
+                .into_iter()
+                .filter(|a| a.time >= since)
+                .map(|a| {
+                    let (title, subject) = match a.kind {
+                        AnomalyKind::Day => ("Unusual daily cost", a.key.clone()),
+                        AnomalyKind::Session => (
+                            "Unusual session cost",
+                            match &a.project {
+                                Some(project) => format!("Session {} in {}", a.key, project),
+                                None => format!("Session {}", a.key),
+                            },
+                        ),
+                    };
+                    let kind = match a.kind {
+                        AnomalyKind::Day => "day",
+                        AnomalyKind::Session => "session",
+                    };
+
+                    Alert {
+                        kind: AlertKind::Anomaly,
+                        key: format!("anomaly:{}:{}", kind, a.key),
+                        severity: Severity::Warning,
+                        title: title.to_string(),
+                        message: format!(
+                            "{} cost ${:.2} against a baseline of ${:.2} (score {:.1}).",
+                            subject, a.cost, a.baseline, a.score
+                        ),
+                        time: a.time,
+                        value: a.cost.to_f64().unwrap_or(0.0),
+                        threshold: a.baseline.to_f64().unwrap_or(0.0),
+                    }
+                }),
+        );
+    }
+
+    alerts
+}
+
+/// The highest block percentage the active block has reached
+fn block_alert(
+    aggregate: &IncrementalAggregate,
+    rules: &AlertRules,
+    now: DateTime<Utc>,
+) -> Option<Alert> {
+    let limit = rules.token_limit?;
+
+    // Only the latest block can still be active
+    let block = aggregate
+        .block_tracker()
+        .accumulators()
+        .last()
+        .filter(|b| b.is_active(now))?
+        .to_session_block(now, None);
+
+    let tokens = block.total_tokens();
+    let percent = tokens as f64 / limit as f64 * 100.0;
+    let threshold = rules
+        .block_percents
+        .iter()
+        .copied()
+        .filter(|&p| percent >= p)
+        .max_by(f64::total_cmp)?;
+
+    Some(Alert {
+        kind: AlertKind::BlockLimit,
+        key: format!("block:{}:{}", block.id, threshold),
+        severity: if threshold >= 100.0 {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        },
+        title: format!("Block at {}% of token limit", threshold),
+        message: format!(
+            "The block started {} has used {} of {} tokens ({:.1}%) and cost ${:.2}; it resets at {}.",
+            block.start_time.format("%Y-%m-%d %H:%M UTC"),
+            format_tokens_compact(tokens),
+            format_tokens_compact(limit),
+            percent,
+            block.cost_usd,
+            block.end_time.format("%H:%M UTC"),
+        ),
+        time: block.start_time,
+        value: tokens as f64,
+        threshold: limit as f64,
+    })
+}
+
+/// The current UTC day's cost, when over the daily budget
+fn daily_alert(
+    aggregate: &IncrementalAggregate,
+    rules: &AlertRules,
+    now: DateTime<Utc>,
+) -> Option<Alert> {
+    let budget = rules.daily_budget?;
+    let today = now.date_naive();
+
+    let cost = aggregate.day(DailyDate(today))?.cost();
+    if cost <= budget {
+        return None;
+    }
+
+    Some(Alert {
+        kind: AlertKind::DailyBudget,
+        key: format!("daily:{}", today),
+        severity: Severity::Critical,
+        title: "Daily budget exceeded".to_string(),
+        message: format!(
+            "Spend on {} is ${:.2}, over the ${:.2} daily budget.",
+            today, cost, budget
+        ),
+        time: today.and_hms_opt(0, 0, 0)?.and_utc(),
+        value: cost.to_f64().unwrap_or(0.0),
+        threshold: budget.to_f64().unwrap_or(0.0),
+    })
+}
+
+/// Where and how alerts are posted
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    url: String,
+    format: WebhookFormat,
+    template: Template,
+    headers: Vec<(String, String)>,
+}
+
+impl Webhook {
+    /// Webhook posting `format` payloads with the format's default text
+    pub fn new(url: impl Into<String>, format: WebhookFormat) -> Self {
+        Self {
+            url: url.into(),
+            format,
+            template: Template::parse(default_template(format), TEMPLATE_PLACEHOLDERS)
+                .expect("default template is valid"),
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &WebhookConfig) -> Result<Self> {
+        let mut webhook = Self::new(&config.url, config.format);
+
+        if let Some(template) = &config.template {
+            webhook.template = Template::parse(template, TEMPLATE_PLACEHOLDERS)
+                .with_context(|| format!("Invalid template for webhook {}", webhook.label()))?;
+        }
+        webhook.headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+
+        Ok(webhook)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Scheme, host and port of the URL, safe to log: Slack and Discord webhook
+    /// URLs carry their secret in the path
+    pub fn label(&self) -> String {
+        match reqwest::Url::parse(&self.url) {
+            Ok(url) => url.origin().ascii_serialization(),
+            Err(_) => "invalid URL".to_string(),
+        }
+    }
+
+    /// Body posted for an alert
+    pub fn payload(&self, alert: &Alert) -> Value {
+        let text = self.template.render(|name| match name {
+            "title" => alert.title.clone(),
+            "message" => alert.message.clone(),
+            "kind" => alert.kind.as_str().to_string(),
+            "severity" => alert.severity.as_str().to_string(),
+            "key" => alert.key.clone(),
+            _ => String::new(),
+        });
+
+        match self.format {
+            WebhookFormat::Json => {
+                let mut payload = serde_json::to_value(alert).unwrap_or_else(|_| json!({}));
+                if let Some(object) = payload.as_object_mut() {
+                    object.insert("text".to_string(), json!(text));
+                    object.insert("host".to_string(), json!(default_machine_label()));
+                    object.insert("source".to_string(), json!("rccusage"));
+                }
+                payload
+            }
+            WebhookFormat::Slack => json!({ "text": text }),
+            WebhookFormat::Discord => json!({ "username": "rccusage", "content": text }),
+        }
+    }
+}
+
+fn default_template(format: WebhookFormat) -> &'static str {
+    match format {
+        WebhookFormat::Json => "{title}: {message}",
+        WebhookFormat::Slack => "*{title}*\n{message}",
+        WebhookFormat::Discord => "**{title}**\n{message}",
+    }
+}
+
+/// An alert a webhook did not accept
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryFailure {
+    pub key: String,
+
+    /// [`Webhook::label`] of the webhook
+    pub webhook: String,
+
+    pub error: String,
+}
+
+/// Outcome of one [`Notifier::check`]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckSummary {
+    /// Alerts that were not yet delivered to every webhook
+    pub alerts: Vec<Alert>,
+
+    /// Posts the webhooks accepted
+    pub delivered: usize,
+
+    pub failures: Vec<DeliveryFailure>,
+}
+
+/// Which webhooks each alert was delivered to
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationState {
+    alerts: BTreeMap<String, AlertRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertRecord {
+    first_fired: DateTime<Utc>,
+
+    /// Webhook URLs
+    delivered: BTreeSet<String>,
+}
+
+impl NotificationState {
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read notification state: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse notification state: {}", path.display()))
+    }
+
+    /// Write under a temporary name and rename into place, so an interrupted
+    /// save never loses the alerts already delivered
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?).with_context(|| {
+            format!("Failed to write notification state: {}", partial.display())
+        })?;
+        std::fs::rename(&partial, path)
+            .with_context(|| format!("Failed to write notification state: {}", path.display()))?;
+
+        Ok(())
+    }
+
+    fn is_delivered(&self, key: &str, webhook: &Webhook) -> bool {
+        self.alerts
+            .get(key)
+            .is_some_and(|record| record.delivered.contains(webhook.url()))
+    }
+}
+
+/// Evaluates alert rules and posts new alerts to webhooks
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    client: reqwest::Client,
+    rules: AlertRules,
+    webhooks: Vec<Webhook>,
+    state_path: PathBuf,
+}
+
+impl Notifier {
+    /// Notifier keeping its state at [`default_state_path`]
+    pub fn new(rules: AlertRules, webhooks: Vec<Webhook>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            rules,
+            webhooks,
+            state_path: default_state_path(),
+        }
+    }
+
+    /// Notifier for the config's webhooks and rules
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let webhooks = config
+            .notifications
+            .webhooks
+            .iter()
+            .map(Webhook::from_config)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self::new(AlertRules::from_config(config), webhooks))
+    }
+
+    /// Keep delivery state in this file instead
+    pub fn state_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.state_path = path.into();
+        self
+    }
+
+    pub fn rules(&self) -> &AlertRules {
+        &self.rules
+    }
+
+    pub fn webhooks(&self) -> &[Webhook] {
+        &self.webhooks
+    }
+
+    /// Alerts that would be posted by [`check`](Self::check), without posting them
+    pub fn pending(
+        &self,
+        aggregate: &IncrementalAggregate,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Alert>> {
+        let alerts = evaluate(aggregate, &self.rules, now);
+        if alerts.is_empty() {
+            return Ok(alerts);
+        }
+
+        let state = NotificationState::load(&self.state_path)?;
+        Ok(alerts
+            .into_iter()
+            .filter(|alert| {
+                self.webhooks.is_empty()
+                    || self
+                        .webhooks
+                        .iter()
+                        .any(|webhook| !state.is_delivered(&alert.key, webhook))
+            })
+            .collect())
+    }
+
+    /// Post every crossed threshold to the webhooks that have not received it
+    ///
+    /// Deliveries are recorded as they succeed, so failed ones are retried by the
+    /// next check without repeating the others. The state file is only read when
+    /// a threshold is crossed and only written after a delivery.
+    pub async fn check(
+        &self,
+        aggregate: &IncrementalAggregate,
+        now: DateTime<Utc>,
+    ) -> Result<CheckSummary> {
+        if self.webhooks.is_empty() {
+            bail!("No webhooks configured; add notifications.webhooks to the config file");
+        }
+
+        let mut summary = CheckSummary::default();
+        let alerts = evaluate(aggregate, &self.rules, now);
+        if alerts.is_empty() {
+            return Ok(summary);
+        }
+
+        let mut state = NotificationState::load(&self.state_path)?;
+        for alert in alerts {
+            let targets: Vec<&Webhook> = self
+                .webhooks
+                .iter()
+                .filter(|webhook| !state.is_delivered(&alert.key, webhook))
+                .collect();
+            if targets.is_empty() {
+                continue;
+            }
+
+            for webhook in targets {
+                match self.post(webhook, &alert).await {
+                    Ok(()) => {
+                        summary.delivered += 1;
+                        state
+                            .alerts
+                            .entry(alert.key.clone())
+                            .or_insert_with(|| AlertRecord {
+                                first_fired: now,
+                                delivered: BTreeSet::new(),
+                            })
+                            .delivered
+                            .insert(webhook.url().to_string());
+                    }
+                    Err(err) => summary.failures.push(DeliveryFailure {
+                        key: alert.key.clone(),
+                        webhook: webhook.label(),
+                        error: format!("{:#}", err),
+                    }),
+                }
+            }
+            summary.alerts.push(alert);
+        }
+
+        if summary.delivered > 0 {
+            let retain_after = now - Duration::days(STATE_RETENTION_DAYS);
+            state
+                .alerts
+                .retain(|_, record| record.first_fired >= retain_after);
+            state.save(&self.state_path)?;
+        }
+
+        Ok(summary)
+    }
+
+    async fn post(&self, webhook: &Webhook, alert: &Alert) -> Result<()> {
+        let mut request = self
+            .client
+            .post(webhook.url())
+            .json(&webhook.payload(alert));
+        for (name, value) in &webhook.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|err| err.without_url())
+            .with_context(|| format!("Failed to reach webhook at {}", webhook.label()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            bail!(
+                "Webhook at {} returned {}: {}",
+                webhook.label(),
+                status,
+                text.trim()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{LoadedUsageEntry, ModelName, SessionId, TokenCounts};
+    use chrono::TimeZone;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    fn entry(time: DateTime<Utc>, tokens: u64, cost: Decimal, session: &str) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: time,
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: tokens,
+                ..TokenCounts::default()
+            },
+            cost,
+            session_id: Some(SessionId::new(session)),
+            request_id: None,
+            message_id: None,
+            project: Some("-home-dev-alpha".to_string()),
+            version: None,
+            cwd: None,
+            git_branch: None,
+            is_sidechain: false,
+            machine: None,
+            user: None,
+        }
+    }
+
+    fn aggregate(entries: Vec<LoadedUsageEntry>) -> IncrementalAggregate {
+        let mut aggregate = IncrementalAggregate::default();
+        aggregate.extend(entries);
+        aggregate
+    }
+
+    fn rules() -> AlertRules {
+        AlertRules {
+            token_limit: Some(1_000),
+            block_percents: vec![50.0, 80.0, 100.0],
+            ..AlertRules::default()
+        }
+    }
+
+    /// A file path under the temp directory that doesn't exist yet
+    fn state_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rccusage-notify-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// Start a stand-in webhook answering every post with `status`, yielding bodies
+    fn webhook_server(status: u16) -> (String, mpsc::Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+
+                let _ = tx.send(serde_json::from_slice(&body).unwrap());
+            }
+        });
+
+        (url, rx)
+    }
+
+    #[test]
+    fn block_alert_uses_highest_crossed_percent() {
+        let usage = aggregate(vec![
+            entry(now() - Duration::minutes(40), 500, Decimal::ONE, "s1"),
+            entry(now() - Duration::minutes(10), 350, Decimal::ONE, "s1"),
+        ]);
+
+        let alerts = evaluate(&usage, &rules(), now());
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::BlockLimit);
+        assert_eq!(alerts[0].key, "block:2026-10-18T11:00:00+00:00:80");
+        assert_eq!(alerts[0].severity, Severity::Warning);
+        assert_eq!(alerts[0].value, 850.0);
+    }
+
+    #[test]
+    fn no_block_alert_once_block_is_inactive() {
+        let usage = aggregate(vec![entry(
+            now() - Duration::hours(2),
+            5_000,
+            Decimal::ONE,
+            "s1",
+        )]);
+
+        assert!(evaluate(&usage, &rules(), now()).is_empty());
+    }
+
+    #[test]
+    fn daily_alert_only_over_budget() {
+        let rules = AlertRules {
+            daily_budget: Some(Decimal::new(5, 0)),
+            ..AlertRules::default()
+        };
+        let yesterday = now() - Duration::days(1);
+
+        let under = aggregate(vec![
+            entry(now(), 10, Decimal::new(5, 0), "s1"),
+            entry(yesterday, 10, Decimal::new(20, 0), "s0"),
+        ]);
+        assert!(evaluate(&under, &rules, now()).is_empty());
+
+        let over = aggregate(vec![
+            entry(now() - Duration::hours(3), 10, Decimal::new(3, 0), "s1"),
+            entry(now(), 10, Decimal::new(3, 0), "s1"),
+        ]);
+        let alerts = evaluate(&over, &rules, now());
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].key, "daily:2026-10-18");
+        assert_eq!(alerts[0].severity, Severity::Critical);
+        assert_eq!(alerts[0].value, 6.0);
+    }
+
+    #[test]
+    fn anomaly_alerts_only_for_recent_outliers() {
+        let rules = AlertRules {
+            anomalies: Some(AnomalyOptions {
+                method: AnomalyMethod::Mad,
+                threshold: 3.5,
+                history: 30,
+                min_cost: Decimal::ONE,
+            }),
+            ..AlertRules::default()
+        };
+
+        // Twenty ordinary days, an old spike, and a spike today
+        let mut entries: Vec<LoadedUsageEntry> = (1..=20)
+            .map(|day| {
+                let cost = Decimal::new(200 + (day % 5) * 10, 2);
+                entry(now() - Duration::days(day), 10, cost, &format!("s{}", day))
+            })
+            .collect();
+        entries.push(entry(
+            now() - Duration::days(10) + Duration::hours(1),
+            10,
+            Decimal::new(40, 0),
+            "old-spike",
+        ));
+        entries.push(entry(now(), 10, Decimal::new(60, 0), "spike"));
+
+        let keys: Vec<String> = evaluate(&aggregate(entries), &rules, now())
+            .into_iter()
+            .map(|a| a.key)
+            .collect();
+        assert!(
+            keys.contains(&"anomaly:day:2026-10-18".to_string()),
+            "{:?}",
+            keys
+        );
+        assert!(
+            keys.contains(&"anomaly:session:spike".to_string()),
+            "{:?}",
+            keys
+        );
+        assert!(!keys
+            .iter()
+            .any(|k| k.contains("old-spike") || k.contains("10-08")));
+    }
+
+    #[test]
+    fn payloads_follow_webhook_format() {
+        let alert = Alert {
+            kind: AlertKind::DailyBudget,
+            key: "daily:2026-10-18".to_string(),
+            severity: Severity::Critical,
+            title: "Daily budget exceeded".to_string(),
+            message: "Spend is $6.00.".to_string(),
+            time: now(),
+            value: 6.0,
+            threshold: 5.0,
+        };
+
+        let slack = Webhook::new(
+            "https://hooks.slack.com/services/T/B/X",
+            WebhookFormat::Slack,
+        );
+        assert_eq!(
+            slack.payload(&alert),
+            json!({ "text": "*Daily budget exceeded*\nSpend is $6.00." })
+        );
+        assert_eq!(slack.label(), "https://hooks.slack.com");
+
+        let discord = Webhook::new(
+            "https://discord.com/api/webhooks/1/x",
+            WebhookFormat::Discord,
+        );
+        assert_eq!(
+            discord.payload(&alert)["content"],
+            "**Daily budget exceeded**\nSpend is $6.00."
+        );
+
+        let json = Webhook::from_config(&WebhookConfig {
+            url: "http://localhost:9000/alerts".to_string(),
+            format: WebhookFormat::Json,
+            template: Some("[{severity}] {kind} {key}".to_string()),
+            headers: Default::default(),
+        })
+        .unwrap();
+        let payload = json.payload(&alert);
+        assert_eq!(payload["text"], "[critical] dailyBudget daily:2026-10-18");
+        assert_eq!(payload["kind"], "dailyBudget");
+        assert_eq!(payload["threshold"], 5.0);
+        assert_eq!(payload["source"], "rccusage");
+        assert_eq!(json.label(), "http://localhost:9000");
+
+        assert!(Webhook::from_config(&WebhookConfig {
+            url: "http://localhost".to_string(),
+            format: WebhookFormat::Json,
+            template: Some("{nope}".to_string()),
+            headers: Default::default(),
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn check_delivers_each_alert_once_and_retries_failures() {
+        let (good_url, good) = webhook_server(204);
+        let (bad_url, bad) = webhook_server(500);
+        let path = state_path("retry");
+        let notifier = Notifier::new(
+            rules(),
+            vec![
+                Webhook::new(&good_url, WebhookFormat::Json),
+                Webhook::new(&bad_url, WebhookFormat::Slack),
+            ],
+        )
+        .state_path(&path);
+        let usage = aggregate(vec![entry(
+            now() - Duration::minutes(5),
+            600,
+            Decimal::ONE,
+            "s1",
+        )]);
+
+        let first = notifier.check(&usage, now()).await.unwrap();
+        assert_eq!(first.alerts.len(), 1);
+        assert_eq!(first.delivered, 1);
+        assert_eq!(first.failures.len(), 1);
+        assert!(first.failures[0].error.contains("500"));
+        assert_eq!(
+            good.recv().unwrap()["key"],
+            "block:2026-10-18T11:00:00+00:00:50"
+        );
+        assert!(bad.recv().unwrap()["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("*Block at 50%"));
+
+        // Only the failed webhook is tried again
+        let second = notifier.check(&usage, now()).await.unwrap();
+        assert_eq!(second.delivered, 0);
+        assert_eq!(second.failures.len(), 1);
+        bad.recv().unwrap();
+        assert!(good
+            .recv_timeout(std::time::Duration::from_millis(200))
+            .is_err());
+
+        // A higher threshold is a new alert
+        let more = aggregate(vec![entry(
+            now() - Duration::minutes(5),
+            1_200,
+            Decimal::ONE,
+            "s1",
+        )]);
+        let third = notifier.check(&more, now()).await.unwrap();
+        assert_eq!(third.delivered, 1);
+        assert_eq!(good.recv().unwrap()["severity"], "critical");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn check_leaves_state_alone_without_alerts() {
+        let path = state_path("quiet");
+        let notifier = Notifier::new(
+            rules(),
+            vec![Webhook::new("http://127.0.0.1:9/hook", WebhookFormat::Json)],
+        )
+        .state_path(&path);
+
+        let summary = notifier
+            .check(&IncrementalAggregate::default(), now())
+            .await
+            .unwrap();
+        assert!(summary.alerts.is_empty());
+        assert!(!path.exists());
+    }
+}
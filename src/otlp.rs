@@ -0,0 +1,677 @@
+//! Export of usage to an OpenTelemetry collector over OTLP/HTTP, behind the `otlp` feature.
+//!
+//! Each request becomes data points of two delta sums and, optionally, one log
+//! record, all stamped with the request's own timestamp so backfilled history
+//! lands at the right time:
+//!
+//! - `ccusage.token.usage` (`{token}`), one point per token type with a `type`
+//!   attribute of `input`, `output`, `cacheCreation` or `cacheRead`
+//! - `ccusage.cost.usage` (`USD`)
+//! - log records with `event.name` `ccusage.api_request` carrying the tokens
+//!   and cost as attributes
+//!
+//! Every point and record has `model`, `project`, `session.id`, `user.name` and
+//! `host.name` attributes. Payloads use the OTLP JSON encoding and are posted to
+//! `/v1/metrics` and `/v1/logs` under the endpoint.
+
+use crate::archive::default_machine_label;
+use crate::types::LoadedUsageEntry;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Endpoint of a collector running locally with the default OTLP/HTTP port
+pub const DEFAULT_ENDPOINT: &str = "http://localhost:4318";
+
+/// Most requests sent in one export call
+const BATCH_SIZE: usize = 500;
+
+/// `AGGREGATION_TEMPORALITY_DELTA` in the OTLP protobuf
+const TEMPORALITY_DELTA: u8 = 1;
+
+/// `SEVERITY_NUMBER_INFO` in the OTLP protobuf
+const SEVERITY_INFO: u8 = 9;
+
+/// Longest wait for the collector to answer one export call
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Kind of telemetry sent for each request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpSignal {
+    /// Token and cost sums
+    Metrics,
+    /// One log record per request
+    Logs,
+}
+
+impl OtlpSignal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Metrics => "metrics",
+            Self::Logs => "logs",
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Self::Metrics => "v1/metrics",
+            Self::Logs => "v1/logs",
+        }
+    }
+
+    fn request(self, entries: &[LoadedUsageEntry], host: &str) -> Value {
+        match self {
+            Self::Metrics => metrics_request(entries, host),
+            Self::Logs => logs_request(entries, host),
+        }
+    }
+}
+
+/// Posts usage entries to an OTLP/HTTP collector
+#[derive(Clone)]
+pub struct OtlpExporter {
+    client: reqwest::Client,
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    signals: Vec<OtlpSignal>,
+    host: String,
+}
+
+impl OtlpExporter {
+    /// Exporter sending metrics and logs to the collector at `endpoint`
+    ///
+    /// Signal paths are appended to `endpoint`, as for
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            headers: Vec::new(),
+            signals: vec![OtlpSignal::Metrics, OtlpSignal::Logs],
+            host: default_machine_label(),
+        }
+    }
+
+    /// Send this header with every request, e.g. an API key
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only send these signals
+    pub fn signals(mut self, signals: Vec<OtlpSignal>) -> Self {
+        self.signals = signals;
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Signals sent by [`export`](Self::export)
+    pub fn selected_signals(&self) -> &[OtlpSignal] {
+        &self.signals
+    }
+
+    /// Send entries as every selected signal in turn, returning how many were
+    /// exported
+    ///
+    /// Stops at the first batch the collector rejects.
+    pub async fn export(&self, entries: &[LoadedUsageEntry]) -> Result<usize> {
+        for signal in &self.signals {
+            self.export_signal(*signal, entries).await?;
+        }
+
+        Ok(entries.len())
+    }
+
+    /// Send entries as one signal in batches, returning how many were exported
+    ///
+    /// Stops at the first batch the collector rejects; the error tells how
+    /// many leading entries were accepted before it, so only the rest need to
+    /// be sent again.
+    pub async fn export_signal(
+        &self,
+        signal: OtlpSignal,
+        entries: &[LoadedUsageEntry],
+    ) -> Result<usize, PartialExport> {
+        let mut exported = 0;
+        for batch in entries.chunks(BATCH_SIZE) {
+            self.post(signal, &signal.request(batch, &self.host))
+                .await
+                .map_err(|error| PartialExport { exported, error })?;
+            exported += batch.len();
+        }
+
+        Ok(exported)
+    }
+
+    async fn post(&self, signal: OtlpSignal, body: &Value) -> Result<()> {
+        let url = format!("{}/{}", self.endpoint, signal.path());
+
+        let mut request = self.client.post(&url).json(body);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach OTLP collector at {}", url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            bail!(
+                "OTLP collector at {} returned {}: {}",
+                url,
+                status,
+                text.trim()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// An export the collector rejected part way through
+#[derive(Debug)]
+pub struct PartialExport {
+    /// Leading entries the collector accepted before the failure
+    pub exported: usize,
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for PartialExport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#} ({} requests exported)", self.error, self.exported)
+    }
+}
+
+impl std::error::Error for PartialExport {}
+
+/// `ExportMetricsServiceRequest` for the entries, in OTLP JSON
+pub fn metrics_request(entries: &[LoadedUsageEntry], host: &str) -> Value {
+    let token_points: Vec<Value> = entries
+        .iter()
+        .flat_map(|entry| {
+            let time = unix_nanos(entry.timestamp);
+            [
+                ("input", entry.tokens.input_tokens),
+                ("output", entry.tokens.output_tokens),
+                ("cacheCreation", entry.tokens.cache_creation_input_tokens),
+                ("cacheRead", entry.tokens.cache_read_input_tokens),
+            ]
+            .into_iter()
+            .filter(|&(_, tokens)| tokens > 0)
+            .map(move |(kind, tokens)| {
+                let mut attributes = entry_attributes(entry, host);
+                attributes.push(attribute("type", string_value(kind)));
+                json!({
+                    "attributes": attributes,
+                    "startTimeUnixNano": time,
+                    "timeUnixNano": time,
+                    "asInt": tokens.to_string(),
+                })
+            })
+        })
+        .collect();
+
+    let cost_points: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let time = unix_nanos(entry.timestamp);
+            json!({
+                "attributes": entry_attributes(entry, host),
+                "startTimeUnixNano": time,
+                "timeUnixNano": time,
+                "asDouble": entry.cost.to_f64().unwrap_or(0.0),
+            })
+        })
+        .collect();
+
+    let sum = |data_points: Vec<Value>| {
+        json!({
+            "aggregationTemporality": TEMPORALITY_DELTA,
+            "isMonotonic": true,
+            "dataPoints": data_points,
+        })
+    };
+
+    json!({
+        "resourceMetrics": [{
+            "resource": resource(),
+            "scopeMetrics": [{
+                "scope": scope(),
+                "metrics": [
+                    {
+                        "name": "ccusage.token.usage",
+                        "description": "Tokens used by Claude Code requests",
+                        "unit": "{token}",
+                        "sum": sum(token_points),
+                    },
+                    {
+                        "name": "ccusage.cost.usage",
+                        "description": "Cost of Claude Code requests",
+                        "unit": "USD",
+                        "sum": sum(cost_points),
+                    },
+                ],
+            }],
+        }],
+    })
+}
+
+/// `ExportLogsServiceRequest` for the entries, in OTLP JSON
+pub fn logs_request(entries: &[LoadedUsageEntry], host: &str) -> Value {
+    let observed = unix_nanos(Utc::now());
+
+    let records: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let mut attributes = vec![attribute("event.name", string_value("ccusage.api_request"))];
+            attributes.extend(entry_attributes(entry, host));
+
+            let optional = [
+                (
+                    "request.id",
+                    entry.request_id.as_ref().map(|r| r.0.as_str()),
+                ),
+                (
+                    "message.id",
+                    entry.message_id.as_ref().map(|m| m.0.as_str()),
+                ),
+                ("cwd", entry.cwd.as_deref()),
+                ("git.branch", entry.git_branch.as_deref()),
+                ("app.version", entry.version.as_deref()),
+            ];
+            attributes.extend(
+                optional
+                    .into_iter()
+                    .filter_map(|(key, value)| Some(attribute(key, string_value(value?)))),
+            );
+
+            attributes.extend([
+                attribute("input_tokens", int_value(entry.tokens.input_tokens)),
+                attribute("output_tokens", int_value(entry.tokens.output_tokens)),
+                attribute(
+                    "cache_creation_tokens",
+                    int_value(entry.tokens.cache_creation_input_tokens),
+                ),
+                attribute(
+                    "cache_read_tokens",
+                    int_value(entry.tokens.cache_read_input_tokens),
+                ),
+                attribute(
+                    "cost_usd",
+                    json!({ "doubleValue": entry.cost.to_f64().unwrap_or(0.0) }),
+                ),
+                attribute("is_sidechain", json!({ "boolValue": entry.is_sidechain })),
+            ]);
+
+            json!({
+                "timeUnixNano": unix_nanos(entry.timestamp),
+                "observedTimeUnixNano": observed,
+                "severityNumber": SEVERITY_INFO,
+                "severityText": "INFO",
+                "body": string_value("ccusage.api_request"),
+                "attributes": attributes,
+            })
+        })
+        .collect();
+
+    json!({
+        "resourceLogs": [{
+            "resource": resource(),
+            "scopeLogs": [{
+                "scope": scope(),
+                "logRecords": records,
+            }],
+        }],
+    })
+}
+
+/// Attributes shared by every data point and log record of an entry
+fn entry_attributes(entry: &LoadedUsageEntry, host: &str) -> Vec<Value> {
+    vec![
+        attribute("model", string_value(entry.model.as_str())),
+        attribute(
+            "project",
+            string_value(entry.project.as_deref().unwrap_or("unknown")),
+        ),
+        attribute(
+            "session.id",
+            string_value(
+                entry
+                    .session_id
+                    .as_ref()
+                    .map_or("unknown", |s| s.0.as_str()),
+            ),
+        ),
+        attribute("user.name", string_value(entry.user_label())),
+        attribute(
+            "host.name",
+            string_value(entry.machine.as_deref().unwrap_or(host)),
+        ),
+    ]
+}
+
+fn resource() -> Value {
+    json!({
+        "attributes": [
+            attribute("service.name", string_value("rccusage")),
+            attribute("service.version", string_value(env!("CARGO_PKG_VERSION"))),
+        ],
+    })
+}
+
+fn scope() -> Value {
+    json!({ "name": "rccusage", "version": env!("CARGO_PKG_VERSION") })
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+fn string_value(value: &str) -> Value {
+    json!({ "stringValue": value })
+}
+
+/// 64-bit integers are strings in OTLP JSON
+fn int_value(value: u64) -> Value {
+    json!({ "intValue": value.to_string() })
+}
+
+fn unix_nanos(time: DateTime<Utc>) -> String {
+    time.timestamp_nanos_opt().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MessageId, ModelName, RequestId, SessionId, TokenCounts};
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// A request received by [`collector`]
+    struct Received {
+        path: String,
+        headers: HashMap<String, String>,
+        body: Value,
+    }
+
+    /// Start a stand-in collector answering every request with `status`
+    fn collector(status: u16) -> (String, mpsc::Receiver<Received>) {
+        collector_answering(move |_| status)
+    }
+
+    /// Start a stand-in collector answering the nth request (from 0) with `status(n)`
+    fn collector_answering(
+        status: impl Fn(usize) -> u16 + Send + 'static,
+    ) -> (String, mpsc::Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for (n, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap().to_string();
+
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(':') {
+                        Some((name, value)) => {
+                            headers.insert(name.to_lowercase(), value.trim().to_string());
+                        }
+                        None => break,
+                    }
+                }
+
+                let length: usize = headers["content-length"].parse().unwrap();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Status\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                    status(n)
+                )
+                .unwrap();
+
+                let _ = tx.send(Received {
+                    path,
+                    headers,
+                    body: serde_json::from_slice(&body).unwrap(),
+                });
+            }
+        });
+
+        (endpoint, rx)
+    }
+
+    fn entry(minute: u32, machine: Option<&str>) -> LoadedUsageEntry {
+        LoadedUsageEntry {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 18, 9, minute, 0).unwrap(),
+            model: ModelName::new("claude-sonnet-4-5-20250929"),
+            tokens: TokenCounts {
+                input_tokens: 100,
+                output_tokens: 200,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 4000,
+            },
+            cost: Decimal::new(125, 3),
+            session_id: Some(SessionId::new("session-1")),
+            request_id: Some(RequestId::new(format!("req_{}", minute))),
+            message_id: Some(MessageId::new(format!("msg_{}", minute))),
+            project: Some("-home-dev-alpha".to_string()),
+            version: Some("2.0.1".to_string()),
+            cwd: Some("/home/dev/alpha".to_string()),
+            git_branch: Some("main".to_string()),
+            is_sidechain: false,
+            machine: machine.map(str::to_string),
+            user: Some("alice".to_string()),
+        }
+    }
+
+    /// Attributes of an OTLP JSON object as key to value
+    fn attributes(item: &Value) -> HashMap<String, Value> {
+        item["attributes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| (a["key"].as_str().unwrap().to_string(), a["value"].clone()))
+            .collect()
+    }
+
+    fn string_attribute(item: &Value, key: &str) -> String {
+        attributes(item)[key]["stringValue"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn exports_metrics_and_logs_to_collector() {
+        let (endpoint, requests) = collector(200);
+        let exporter = OtlpExporter::new(format!("{}/", endpoint)).header("x-api-key", "secret");
+        let entries = vec![entry(0, None), entry(5, Some("laptop"))];
+
+        assert_eq!(exporter.export(&entries).await.unwrap(), 2);
+
+        let metrics = requests.recv().unwrap();
+        assert_eq!(metrics.path, "/v1/metrics");
+        assert_eq!(metrics.headers["x-api-key"], "secret");
+        assert_eq!(metrics.headers["content-type"], "application/json");
+
+        let resource_metrics = &metrics.body["resourceMetrics"][0];
+        let resource = &resource_metrics["resource"];
+        assert_eq!(string_attribute(resource, "service.name"), "rccusage");
+        assert_eq!(
+            string_attribute(resource, "service.version"),
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(
+            resource_metrics["scopeMetrics"][0]["scope"]["name"],
+            "rccusage"
+        );
+
+        let metrics_list = resource_metrics["scopeMetrics"][0]["metrics"]
+            .as_array()
+            .unwrap();
+        assert_eq!(metrics_list.len(), 2);
+
+        let tokens = &metrics_list[0];
+        assert_eq!(tokens["name"], "ccusage.token.usage");
+        assert_eq!(tokens["unit"], "{token}");
+        assert_eq!(tokens["sum"]["aggregationTemporality"], 1);
+        assert_eq!(tokens["sum"]["isMonotonic"], true);
+
+        // Cache creation is zero, so each entry has three token points
+        let token_points = tokens["sum"]["dataPoints"].as_array().unwrap();
+        assert_eq!(token_points.len(), 6);
+        let types: Vec<String> = token_points[..3]
+            .iter()
+            .map(|p| string_attribute(p, "type"))
+            .collect();
+        assert_eq!(types, ["input", "output", "cacheRead"]);
+        assert_eq!(token_points[1]["asInt"], "200");
+        assert_eq!(
+            token_points[0]["timeUnixNano"],
+            unix_nanos(entries[0].timestamp)
+        );
+
+        let first = &token_points[0];
+        assert_eq!(
+            string_attribute(first, "model"),
+            "claude-sonnet-4-5-20250929"
+        );
+        assert_eq!(string_attribute(first, "project"), "-home-dev-alpha");
+        assert_eq!(string_attribute(first, "session.id"), "session-1");
+        assert_eq!(string_attribute(first, "user.name"), "alice");
+        assert_eq!(string_attribute(first, "host.name"), exporter.host);
+        assert_eq!(string_attribute(&token_points[3], "host.name"), "laptop");
+
+        let cost = &metrics_list[1];
+        assert_eq!(cost["name"], "ccusage.cost.usage");
+        assert_eq!(cost["unit"], "USD");
+        let cost_points = cost["sum"]["dataPoints"].as_array().unwrap();
+        assert_eq!(cost_points.len(), 2);
+        assert_eq!(cost_points[0]["asDouble"], 0.125);
+
+        let logs = requests.recv().unwrap();
+        assert_eq!(logs.path, "/v1/logs");
+        assert_eq!(logs.headers["x-api-key"], "secret");
+
+        let records = logs.body["resourceLogs"][0]["scopeLogs"][0]["logRecords"]
+            .as_array()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+
+        let record = &records[0];
+        assert_eq!(record["severityText"], "INFO");
+        assert_eq!(record["body"]["stringValue"], "ccusage.api_request");
+        let record_attributes = attributes(record);
+        assert_eq!(
+            record_attributes["event.name"]["stringValue"],
+            "ccusage.api_request"
+        );
+        assert_eq!(record_attributes["request.id"]["stringValue"], "req_0");
+        assert_eq!(record_attributes["git.branch"]["stringValue"], "main");
+        assert_eq!(record_attributes["cache_read_tokens"]["intValue"], "4000");
+        assert_eq!(record_attributes["cost_usd"]["doubleValue"], 0.125);
+        assert_eq!(record_attributes["user.name"]["stringValue"], "alice");
+    }
+
+    #[tokio::test]
+    async fn sends_only_selected_signals() {
+        let (endpoint, requests) = collector(200);
+        let exporter = OtlpExporter::new(endpoint).signals(vec![OtlpSignal::Logs]);
+
+        exporter.export(&[entry(0, None)]).await.unwrap();
+
+        assert_eq!(requests.recv().unwrap().path, "/v1/logs");
+        assert!(requests
+            .recv_timeout(std::time::Duration::from_millis(200))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn fails_when_collector_rejects_export() {
+        let (endpoint, _requests) = collector(503);
+        let exporter = OtlpExporter::new(endpoint);
+
+        let err = exporter.export(&[entry(0, None)]).await.unwrap_err();
+        assert!(err.to_string().contains("503"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn posts_large_exports_in_batches() {
+        let (endpoint, requests) = collector(200);
+        let exporter = OtlpExporter::new(endpoint);
+        let entries: Vec<LoadedUsageEntry> = (0..BATCH_SIZE + 1)
+            .map(|i| entry((i % 60) as u32, None))
+            .collect();
+
+        assert_eq!(exporter.export(&entries).await.unwrap(), BATCH_SIZE + 1);
+
+        let records: Vec<(String, usize)> = (0..4)
+            .map(|_| {
+                let received = requests.recv().unwrap();
+                let count = match received.path.as_str() {
+                    "/v1/logs" => received.body["resourceLogs"][0]["scopeLogs"][0]["logRecords"]
+                        .as_array()
+                        .unwrap()
+                        .len(),
+                    _ => received.body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"][1]
+                        ["sum"]["dataPoints"]
+                        .as_array()
+                        .unwrap()
+                        .len(),
+                };
+                (received.path, count)
+            })
+            .collect();
+        assert_eq!(
+            records,
+            [
+                ("/v1/metrics".to_string(), BATCH_SIZE),
+                ("/v1/metrics".to_string(), 1),
+                ("/v1/logs".to_string(), BATCH_SIZE),
+                ("/v1/logs".to_string(), 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_entries_accepted_before_a_failed_batch() {
+        // The first batch goes through, the second is rejected
+        let (endpoint, _requests) = collector_answering(|n| if n == 0 { 200 } else { 503 });
+        let exporter = OtlpExporter::new(endpoint);
+        let entries: Vec<LoadedUsageEntry> = (0..BATCH_SIZE + 1)
+            .map(|i| entry((i % 60) as u32, None))
+            .collect();
+
+        let partial = exporter
+            .export_signal(OtlpSignal::Metrics, &entries)
+            .await
+            .unwrap_err();
+        assert_eq!(partial.exported, BATCH_SIZE);
+        assert!(partial.to_string().contains("503"), "{}", partial);
+    }
+}
@@ -0,0 +1,354 @@
+use super::table::{format_cost, format_tokens_compact, get_terminal_width};
+use crate::types::ModelBreakdown;
+use colored::*;
+use rust_decimal::prelude::*;
+
+/// Sparkline levels, lowest first
+const UNICODE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_LEVELS: [char; 8] = ['_', '.', '-', '~', '=', '+', '*', '#'];
+
+/// Narrowest bar area before the chart stops shrinking with the terminal
+const MIN_BAR_WIDTH: usize = 10;
+
+/// Characters available for drawing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    /// Unicode unless the locale names a non-UTF-8 encoding (such as `LANG=C`)
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty());
+
+        Self::for_locale(locale.as_deref())
+    }
+
+    /// Charset for the first set locale variable, if any
+    fn for_locale(locale: Option<&str>) -> Self {
+        match locale {
+            Some(value) => {
+                let value = value.to_lowercase();
+                if value.contains("utf-8") || value.contains("utf8") {
+                    Self::Unicode
+                } else {
+                    Self::Ascii
+                }
+            }
+            None => Self::Unicode,
+        }
+    }
+}
+
+/// Model family a bar segment is stacked by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    Opus,
+    Sonnet,
+    Haiku,
+    Other,
+}
+
+impl ModelFamily {
+    /// Stacking order, most expensive tier first
+    pub const ALL: [ModelFamily; 4] = [Self::Opus, Self::Sonnet, Self::Haiku, Self::Other];
+
+    pub fn of(model: &str) -> Self {
+        let model = model.to_lowercase();
+        if model.contains("opus") {
+            Self::Opus
+        } else if model.contains("sonnet") {
+            Self::Sonnet
+        } else if model.contains("haiku") {
+            Self::Haiku
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Opus => "Opus",
+            Self::Sonnet => "Sonnet",
+            Self::Haiku => "Haiku",
+            Self::Other => "Other",
+        }
+    }
+
+    /// Distinct glyphs keep the stacks readable without color
+    fn glyph(self, charset: Charset) -> char {
+        match (self, charset) {
+            (Self::Opus, Charset::Unicode) => '█',
+            (Self::Sonnet, Charset::Unicode) => '▓',
+            (Self::Haiku, Charset::Unicode) => '▒',
+            (Self::Other, Charset::Unicode) => '░',
+            (Self::Opus, Charset::Ascii) => '#',
+            (Self::Sonnet, Charset::Ascii) => '=',
+            (Self::Haiku, Charset::Ascii) => '-',
+            (Self::Other, Charset::Ascii) => '.',
+        }
+    }
+
+    fn paint(self, text: String) -> ColoredString {
+        match self {
+            Self::Opus => text.magenta(),
+            Self::Sonnet => text.blue(),
+            Self::Haiku => text.green(),
+            Self::Other => text.yellow(),
+        }
+    }
+}
+
+/// One period on a usage chart
+pub struct ChartRow<'a> {
+    pub label: String,
+    pub total_tokens: u64,
+    pub model_breakdowns: &'a [ModelBreakdown],
+}
+
+impl ChartRow<'_> {
+    fn family_cost(&self, family: ModelFamily) -> Decimal {
+        self.model_breakdowns
+            .iter()
+            .filter(|b| ModelFamily::of(b.model_name.as_str()) == family)
+            .map(|b| b.cost)
+            .sum()
+    }
+
+    fn total_cost(&self) -> Decimal {
+        self.model_breakdowns.iter().map(|b| b.cost).sum()
+    }
+
+    /// Width of each family's segment in a bar of `bar_width` for `max_cost`
+    ///
+    /// Segment ends are rounded from cumulative cost so the stack adds up to the bar.
+    fn segments(&self, max_cost: Decimal, bar_width: usize) -> Vec<(ModelFamily, usize)> {
+        let mut segments = Vec::new();
+        if max_cost <= Decimal::ZERO {
+            return segments;
+        }
+
+        let mut cumulative = Decimal::ZERO;
+        let mut drawn = 0;
+        for family in ModelFamily::ALL {
+            cumulative += self.family_cost(family);
+            let end = (cumulative / max_cost * Decimal::from(bar_width))
+                .round()
+                .to_usize()
+                .unwrap_or(0)
+                .min(bar_width);
+            if end > drawn {
+                segments.push((family, end - drawn));
+                drawn = end;
+            }
+        }
+
+        segments
+    }
+}
+
+/// Render cost per period as bars stacked by model family, followed by a token sparkline
+///
+/// Bars are scaled to the most expensive period and sized to the terminal width.
+pub fn render_usage_chart(rows: &[ChartRow], charset: Charset) -> String {
+    let width = get_terminal_width();
+    let separator = match charset {
+        Charset::Unicode => '│',
+        Charset::Ascii => '|',
+    };
+
+    let costs: Vec<String> = rows.iter().map(|r| format_cost(r.total_cost())).collect();
+    let tokens: Vec<String> = rows
+        .iter()
+        .map(|r| format_tokens_compact(r.total_tokens))
+        .collect();
+
+    let label_width = rows
+        .iter()
+        .map(|r| r.label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let cost_width = costs.iter().map(String::len).max().unwrap_or(0);
+    let tokens_width = tokens.iter().map(String::len).max().unwrap_or(0);
+    let bar_width = width
+        .saturating_sub(label_width + cost_width + tokens_width + 6)
+        .max(MIN_BAR_WIDTH);
+
+    let max_cost = rows
+        .iter()
+        .map(ChartRow::total_cost)
+        .max()
+        .unwrap_or(Decimal::ZERO);
+
+    // Legend lists only the families that appear
+    let legend = ModelFamily::ALL
+        .iter()
+        .filter(|family| rows.iter().any(|r| r.family_cost(**family) > Decimal::ZERO))
+        .map(|family| {
+            format!(
+                "{} {}",
+                family.paint(family.glyph(charset).to_string()),
+                family.label()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    let mut output = format!("{}  {}\n\n", "Cost by model family".bold(), legend);
+
+    for ((row, cost), tokens) in rows.iter().zip(&costs).zip(&tokens) {
+        let mut bar = String::new();
+        let mut drawn = 0;
+        for (family, width) in row.segments(max_cost, bar_width) {
+            let segment = family.glyph(charset).to_string().repeat(width);
+            bar.push_str(&family.paint(segment).to_string());
+            drawn += width;
+        }
+
+        output.push_str(&format!(
+            "{:<label_width$} {}{}{} {} {:>tokens_width$}\n",
+            row.label,
+            separator,
+            bar,
+            " ".repeat(bar_width - drawn),
+            format!("{:>cost_width$}", cost).green(),
+            tokens,
+        ));
+    }
+
+    if let (Some(first), Some(last)) = (rows.first(), rows.last()) {
+        let spark_width = width
+            .saturating_sub(first.label.chars().count() + last.label.chars().count() + 40)
+            .max(MIN_BAR_WIDTH);
+        let totals: Vec<u64> = rows.iter().map(|r| r.total_tokens).collect();
+        let min = totals.iter().min().copied().unwrap_or(0);
+        let max = totals.iter().max().copied().unwrap_or(0);
+
+        output.push_str(&format!(
+            "\n{} {} {} {}  (min {}, max {})\n",
+            "Tokens".bold(),
+            first.label,
+            sparkline(&totals, spark_width, charset).cyan(),
+            last.label,
+            format_tokens_compact(min),
+            format_tokens_compact(max),
+        ));
+    }
+
+    output
+}
+
+/// One character per value, summing neighbours when there are more values than `width`
+fn sparkline(values: &[u64], width: usize, charset: Charset) -> String {
+    let levels = match charset {
+        Charset::Unicode => UNICODE_LEVELS,
+        Charset::Ascii => ASCII_LEVELS,
+    };
+
+    let chunk = values.len().div_ceil(width.max(1)).max(1);
+    let buckets: Vec<u64> = values.chunks(chunk).map(|c| c.iter().sum()).collect();
+    let max = buckets.iter().max().copied().unwrap_or(0);
+
+    buckets
+        .iter()
+        .map(|&value| {
+            if max == 0 {
+                levels[0]
+            } else {
+                let level = (value as f64 / max as f64 * (levels.len() - 1) as f64).round();
+                levels[level as usize]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ModelName, RequestStats};
+
+    fn breakdown(model: &str, cost: Decimal) -> ModelBreakdown {
+        ModelBreakdown {
+            model_name: ModelName::new(model),
+            input_tokens: 0,
+            output_tokens: 0,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 0,
+            cost,
+            requests: RequestStats::default(),
+        }
+    }
+
+    #[test]
+    fn sparkline_scales_to_the_largest_bucket() {
+        let unicode = sparkline(&[0, 1, 2, 3, 4, 5, 6, 7], 8, Charset::Unicode);
+        assert_eq!(unicode, "▁▂▃▄▅▆▇█");
+        assert_eq!(sparkline(&[0, 7], 8, Charset::Ascii), "_#");
+        assert_eq!(sparkline(&[0, 0, 0], 8, Charset::Ascii), "___");
+        assert_eq!(sparkline(&[], 8, Charset::Ascii), "");
+    }
+
+    #[test]
+    fn sparkline_sums_neighbours_beyond_its_width() {
+        // Ten values in four characters: chunks of three, the last one short
+        let values = [1, 1, 1, 0, 0, 0, 3, 3, 3, 9];
+        assert_eq!(sparkline(&values, 4, Charset::Ascii), "-_##");
+    }
+
+    #[test]
+    fn stacked_segments_add_up_to_the_bar() {
+        // Thirds don't divide ten columns evenly
+        let breakdowns = [
+            breakdown("claude-opus-4-1", Decimal::ONE),
+            breakdown("claude-sonnet-4-5", Decimal::ONE),
+            breakdown("claude-haiku-4-5", Decimal::ONE),
+        ];
+        let row = ChartRow {
+            label: "2026-10-18".to_string(),
+            total_tokens: 0,
+            model_breakdowns: &breakdowns,
+        };
+
+        let segments = row.segments(Decimal::from(3), 10);
+        assert_eq!(
+            segments,
+            [
+                (ModelFamily::Opus, 3),
+                (ModelFamily::Sonnet, 4),
+                (ModelFamily::Haiku, 3),
+            ]
+        );
+
+        // Half the most expensive row, and a family too small to draw
+        let breakdowns = [
+            breakdown("claude-sonnet-4-5", Decimal::new(299, 2)),
+            breakdown("gpt-4", Decimal::new(1, 2)),
+        ];
+        let row = ChartRow {
+            label: "2026-10-19".to_string(),
+            total_tokens: 0,
+            model_breakdowns: &breakdowns,
+        };
+        assert_eq!(
+            row.segments(Decimal::from(6), 10),
+            [(ModelFamily::Sonnet, 5)]
+        );
+        assert!(row.segments(Decimal::ZERO, 10).is_empty());
+    }
+
+    #[test]
+    fn charset_follows_the_locale_encoding() {
+        assert_eq!(Charset::for_locale(None), Charset::Unicode);
+        assert_eq!(Charset::for_locale(Some("en_US.UTF-8")), Charset::Unicode);
+        assert_eq!(Charset::for_locale(Some("de_DE.utf8")), Charset::Unicode);
+        assert_eq!(Charset::for_locale(Some("C")), Charset::Ascii);
+        assert_eq!(
+            Charset::for_locale(Some("en_US.ISO-8859-1")),
+            Charset::Ascii
+        );
+    }
+}
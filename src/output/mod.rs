@@ -1,11 +1,15 @@
+//! Rendering of report rows as JSON, tables and templates.
+
+pub mod chart;
 pub mod table;
+pub mod template;
 
 use anyhow::{Context, Result};
 use serde::Serialize;
 use std::process::{Command, Stdio};
 
-/// Output data as JSON, optionally filtered through jq
-pub fn output_json<T: Serialize>(data: &T, jq_expression: Option<&str>) -> Result<()> {
+/// Render data as pretty JSON, optionally filtered through jq
+pub fn render_json<T: Serialize>(data: &T, jq_expression: Option<&str>) -> Result<String> {
     let json = serde_json::to_string_pretty(data)?;
 
     if let Some(expr) = jq_expression {
@@ -13,7 +17,7 @@ pub fn output_json<T: Serialize>(data: &T, jq_expression: Option<&str>) -> Resul
         let mut jq = Command::new("jq")
             .arg(expr)
             .stdin(Stdio::piped())
-            .stdout(Stdio::inherit())
+            .stdout(Stdio::piped())
             .spawn()
             .context("Failed to spawn jq process")?;
 
@@ -22,11 +26,10 @@ pub fn output_json<T: Serialize>(data: &T, jq_expression: Option<&str>) -> Resul
             stdin.write_all(json.as_bytes())?;
         }
 
-        jq.wait().context("Failed to wait for jq")?;
+        let output = jq.wait_with_output().context("Failed to wait for jq")?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
     } else {
         // Direct output
-        println!("{}", json);
+        Ok(format!("{}\n", json))
     }
-
-    Ok(())
 }
@@ -1,22 +1,32 @@
+use crate::analytics::anomalies::{Anomaly, AnomalyKind};
+use crate::analytics::cache::{CacheStats, CacheUsage};
+use crate::analytics::compare::ComparisonRow;
+use crate::analytics::forecast::{Forecast, ForecastMethod};
+use crate::analytics::heatmap::{Heatmap, HeatmapMetric};
+use crate::analytics::plans::PlanValue;
+use crate::analytics::simulate::{PlanComparison, SimulatedUsage};
+use crate::analytics::team::{TeamSummary, UserSummary};
+use crate::analytics::timeline::{TimelineGranularity, TimelinePoint};
 use crate::types::*;
-use anyhow::Result;
+use chrono::{DateTime, Duration, Local, Utc};
 use colored::*;
 use comfy_table::{
     modifiers::UTF8_ROUND_CORNERS, presets::UTF8_BORDERS_ONLY, presets::UTF8_FULL, Cell, Color,
     ContentArrangement, Table,
 };
+use itertools::Itertools;
 use rust_decimal::prelude::*;
 use terminal_size::{terminal_size, Width};
 
 /// Get terminal width
-fn get_terminal_width() -> usize {
+pub fn get_terminal_width() -> usize {
     terminal_size()
         .map(|(Width(w), _)| w as usize)
         .unwrap_or(120) // Default width if can't detect
 }
 
 /// Format cost as currency
-fn format_cost(cost: Decimal) -> String {
+pub fn format_cost(cost: Decimal) -> String {
     if cost >= Decimal::from(1000) {
         format!("${:.0}", cost)
     } else if cost >= Decimal::from(100) {
@@ -27,7 +37,7 @@ fn format_cost(cost: Decimal) -> String {
 }
 
 /// Format tokens with K/M/B suffix
-fn format_tokens_compact(tokens: u64) -> String {
+pub fn format_tokens_compact(tokens: u64) -> String {
     if tokens >= 1_000_000_000 {
         format!("{:.1}B", tokens as f64 / 1_000_000_000.0)
     } else if tokens >= 1_000_000 {
@@ -41,8 +51,14 @@ fn format_tokens_compact(tokens: u64) -> String {
     }
 }
 
+/// Format a duration as "2h 13m"
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
 /// Abbreviate model name for compact display
-fn abbreviate_model_name(name: &str) -> String {
+pub fn abbreviate_model_name(name: &str) -> String {
     // Common patterns to abbreviate
     let name = name
         .replace("claude-", "")
@@ -64,8 +80,68 @@ fn abbreviate_model_name(name: &str) -> String {
     }
 }
 
-/// Output daily usage as compact table
-pub fn output_daily_table(data: &[DailyUsage], force_compact: bool) -> Result<()> {
+/// Label for a per-model sub-row under a period row
+fn breakdown_label(breakdown: &ModelBreakdown) -> String {
+    format!(
+        "  └ {}",
+        abbreviate_model_name(breakdown.model_name.as_str())
+    )
+}
+
+/// Add indented per-model rows with the same columns as the period rows
+///
+/// Compact tables combine token columns into one, so the sub-rows do too.
+/// With `request_columns`, the cost is followed by request count and max request cost.
+fn add_breakdown_rows(
+    table: &mut Table,
+    breakdowns: &[ModelBreakdown],
+    compact_mode: bool,
+    cache_in_compact: bool,
+    request_columns: bool,
+    trailing_columns: usize,
+) {
+    for breakdown in breakdowns {
+        let cache = breakdown.cache_creation_tokens + breakdown.cache_read_tokens;
+
+        let mut row = vec![Cell::new(breakdown_label(breakdown)).fg(Color::DarkGrey)];
+
+        if compact_mode {
+            let mut tokens_str = format!(
+                "{}↑ {}↓",
+                format_tokens_compact(breakdown.input_tokens),
+                format_tokens_compact(breakdown.output_tokens)
+            );
+            if cache_in_compact {
+                tokens_str.push_str(&format!(" {}◆", format_tokens_compact(cache)));
+            }
+            row.push(Cell::new(tokens_str).fg(Color::DarkGrey));
+        } else {
+            let total = breakdown.input_tokens + breakdown.output_tokens + cache;
+            row.extend([
+                Cell::new(format_tokens_compact(breakdown.input_tokens)).fg(Color::DarkGrey),
+                Cell::new(format_tokens_compact(breakdown.output_tokens)).fg(Color::DarkGrey),
+                Cell::new(format_tokens_compact(cache)).fg(Color::DarkGrey),
+                Cell::new(format_tokens_compact(total)).fg(Color::DarkGrey),
+            ]);
+        }
+
+        row.push(Cell::new(format_cost(breakdown.cost)).fg(Color::DarkGrey));
+        if request_columns {
+            row.extend([
+                Cell::new(breakdown.requests.count).fg(Color::DarkGrey),
+                Cell::new(format_cost(breakdown.requests.max_cost)).fg(Color::DarkGrey),
+            ]);
+        }
+        row.extend((0..trailing_columns).map(|_| Cell::new("")));
+
+        table.add_row(row);
+    }
+}
+
+/// Render daily usage as compact table
+///
+/// With `breakdown`, each day is followed by one indented row per model.
+pub fn render_daily_table(data: &[DailyUsage], force_compact: bool, breakdown: bool) -> String {
     let width = get_terminal_width();
     let compact_mode = force_compact || width < 120;
 
@@ -101,6 +177,8 @@ pub fn output_daily_table(data: &[DailyUsage], force_compact: bool) -> Result<()
             Cell::new("Cache").fg(Color::Blue),
             Cell::new("Total").fg(Color::Blue),
             Cell::new("Cost").fg(Color::Green),
+            Cell::new("Reqs").fg(Color::Blue),
+            Cell::new("Max Req").fg(Color::Blue),
             Cell::new("Models").fg(Color::Blue),
         ]);
     }
@@ -109,6 +187,8 @@ pub fn output_daily_table(data: &[DailyUsage], force_compact: bool) -> Result<()
     let mut total_output = 0u64;
     let mut total_cache = 0u64;
     let mut total_cost = Decimal::ZERO;
+    let mut total_requests = 0u64;
+    let mut max_request_cost = Decimal::ZERO;
 
     for usage in data {
         let total_tokens = usage.total_tokens();
@@ -118,6 +198,8 @@ pub fn output_daily_table(data: &[DailyUsage], force_compact: bool) -> Result<()
         total_output += usage.output_tokens;
         total_cache += cache_tokens;
         total_cost += usage.total_cost;
+        total_requests += usage.requests.count;
+        max_request_cost = max_request_cost.max(usage.requests.max_cost);
 
         // Format models list compactly
         let models_str = usage
@@ -151,9 +233,22 @@ pub fn output_daily_table(data: &[DailyUsage], force_compact: bool) -> Result<()
                 Cell::new(format_tokens_compact(cache_tokens)).fg(Color::Grey),
                 Cell::new(format_tokens_compact(total_tokens)).fg(Color::Yellow),
                 Cell::new(format_cost(usage.total_cost)).fg(Color::Green),
+                Cell::new(usage.requests.count),
+                Cell::new(format_cost(usage.requests.max_cost)),
                 Cell::new(models_str).fg(Color::Cyan),
             ]);
         }
+
+        if breakdown {
+            add_breakdown_rows(
+                &mut table,
+                &usage.model_breakdowns,
+                compact_mode,
+                true,
+                !compact_mode,
+                1,
+            );
+        }
     }
 
     // Add totals row
@@ -181,16 +276,75 @@ pub fn output_daily_table(data: &[DailyUsage], force_compact: bool) -> Result<()
             Cell::new(format_tokens_compact(total_cache)).fg(Color::Yellow),
             Cell::new(format_tokens_compact(total_all)).fg(Color::Yellow),
             Cell::new(format_cost(total_cost)).fg(Color::Green),
+            Cell::new(total_requests).fg(Color::Yellow),
+            Cell::new(format_cost(max_request_cost)).fg(Color::Yellow),
             Cell::new(""),
         ]);
     }
 
-    println!("{}", table);
-    Ok(())
+    format!("{}\n", table)
 }
 
-/// Output monthly usage as table
-pub fn output_monthly_table(data: &[MonthlyUsage], force_compact: bool) -> Result<()> {
+/// Columns shared by the monthly and weekly tables
+struct PeriodRow<'a> {
+    label: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_tokens: u64,
+    total_cost: Decimal,
+    model_breakdowns: &'a [ModelBreakdown],
+}
+
+impl PeriodRow<'_> {
+    fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_tokens
+    }
+}
+
+/// Render monthly usage as table
+///
+/// With `breakdown`, each month is followed by one indented row per model.
+pub fn render_monthly_table(data: &[MonthlyUsage], force_compact: bool, breakdown: bool) -> String {
+    let rows = data
+        .iter()
+        .map(|m| PeriodRow {
+            label: m.date.to_string(),
+            input_tokens: m.input_tokens,
+            output_tokens: m.output_tokens,
+            cache_tokens: m.cache_creation_tokens + m.cache_read_tokens,
+            total_cost: m.total_cost,
+            model_breakdowns: &m.model_breakdowns,
+        })
+        .collect::<Vec<_>>();
+
+    render_period_table("Month", &rows, force_compact, breakdown)
+}
+
+/// Render weekly usage as table
+///
+/// With `breakdown`, each week is followed by one indented row per model.
+pub fn render_weekly_table(data: &[WeeklyUsage], force_compact: bool, breakdown: bool) -> String {
+    let rows = data
+        .iter()
+        .map(|w| PeriodRow {
+            label: w.date.to_string(),
+            input_tokens: w.input_tokens,
+            output_tokens: w.output_tokens,
+            cache_tokens: w.cache_creation_tokens + w.cache_read_tokens,
+            total_cost: w.total_cost,
+            model_breakdowns: &w.model_breakdowns,
+        })
+        .collect::<Vec<_>>();
+
+    render_period_table("Week", &rows, force_compact, breakdown)
+}
+
+fn render_period_table(
+    period_header: &str,
+    data: &[PeriodRow],
+    force_compact: bool,
+    breakdown: bool,
+) -> String {
     let width = get_terminal_width();
     let compact_mode = force_compact || width < 100;
 
@@ -208,13 +362,13 @@ pub fn output_monthly_table(data: &[MonthlyUsage], force_compact: bool) -> Resul
 
     if compact_mode {
         table.set_header(vec![
-            Cell::new("Month").fg(Color::Blue),
+            Cell::new(period_header).fg(Color::Blue),
             Cell::new("Tokens").fg(Color::Blue),
             Cell::new("Cost").fg(Color::Blue),
         ]);
     } else {
         table.set_header(vec![
-            Cell::new("Month").fg(Color::Blue),
+            Cell::new(period_header).fg(Color::Blue),
             Cell::new("Input").fg(Color::Blue),
             Cell::new("Output").fg(Color::Blue),
             Cell::new("Cache").fg(Color::Blue),
@@ -228,7 +382,6 @@ pub fn output_monthly_table(data: &[MonthlyUsage], force_compact: bool) -> Resul
 
     for usage in data {
         let tokens = usage.total_tokens();
-        let cache = usage.cache_creation_tokens + usage.cache_read_tokens;
         total_cost += usage.total_cost;
         total_tokens += tokens;
 
@@ -240,20 +393,31 @@ pub fn output_monthly_table(data: &[MonthlyUsage], force_compact: bool) -> Resul
             );
 
             table.add_row(vec![
-                Cell::new(usage.date.to_string()),
+                Cell::new(&usage.label),
                 Cell::new(tokens_str),
                 Cell::new(format_cost(usage.total_cost)).fg(Color::Green),
             ]);
         } else {
             table.add_row(vec![
-                Cell::new(usage.date.to_string()),
+                Cell::new(&usage.label),
                 Cell::new(format_tokens_compact(usage.input_tokens)),
                 Cell::new(format_tokens_compact(usage.output_tokens)),
-                Cell::new(format_tokens_compact(cache)).fg(Color::Grey),
+                Cell::new(format_tokens_compact(usage.cache_tokens)).fg(Color::Grey),
                 Cell::new(format_tokens_compact(tokens)).fg(Color::Yellow),
                 Cell::new(format_cost(usage.total_cost)).fg(Color::Green),
             ]);
         }
+
+        if breakdown {
+            add_breakdown_rows(
+                &mut table,
+                usage.model_breakdowns,
+                compact_mode,
+                false,
+                false,
+                0,
+            );
+        }
     }
 
     // Add total row
@@ -274,34 +438,11 @@ pub fn output_monthly_table(data: &[MonthlyUsage], force_compact: bool) -> Resul
         ]);
     }
 
-    println!("{}", table);
-    Ok(())
-}
-
-/// Output weekly usage as table
-pub fn output_weekly_table(data: &[WeeklyUsage], force_compact: bool) -> Result<()> {
-    // Similar to monthly but with week formatting
-    output_monthly_table(
-        &data
-            .iter()
-            .map(|w| MonthlyUsage {
-                date: MonthlyDate::from_datetime(chrono::Utc::now()), // Placeholder
-                input_tokens: w.input_tokens,
-                output_tokens: w.output_tokens,
-                cache_creation_tokens: w.cache_creation_tokens,
-                cache_read_tokens: w.cache_read_tokens,
-                total_cost: w.total_cost,
-                models_used: w.models_used.clone(),
-                model_breakdowns: w.model_breakdowns.clone(),
-                project: w.project.clone(),
-            })
-            .collect::<Vec<_>>(),
-        force_compact,
-    )
+    format!("{}\n", table)
 }
 
-/// Output session usage as table
-pub fn output_session_table(data: &[SessionUsage], force_compact: bool) -> Result<()> {
+/// Render session usage as table
+pub fn render_session_table(data: &[SessionUsage], force_compact: bool) -> String {
     let width = get_terminal_width();
     let compact_mode = force_compact || width < 120;
 
@@ -320,27 +461,36 @@ pub fn output_session_table(data: &[SessionUsage], force_compact: bool) -> Resul
     if compact_mode {
         table.set_header(vec![
             Cell::new("Session").fg(Color::Blue),
-            Cell::new("Msgs").fg(Color::Blue),
+            Cell::new("Reqs").fg(Color::Blue),
             Cell::new("Cost").fg(Color::Blue),
             Cell::new("Last").fg(Color::Blue),
         ]);
     } else {
         table.set_header(vec![
             Cell::new("Session").fg(Color::Blue),
-            Cell::new("Msgs").fg(Color::Blue),
+            Cell::new("Reqs").fg(Color::Blue),
             Cell::new("Tkns").fg(Color::Blue),
             Cell::new("Cost").fg(Color::Green),
+            Cell::new("Max Req").fg(Color::Blue),
+            Cell::new("Subagents").fg(Color::Blue),
+            Cell::new("Active").fg(Color::Blue),
             Cell::new("First").fg(Color::Blue),
             Cell::new("Last").fg(Color::Blue),
         ]);
     }
 
     let mut total_cost = Decimal::ZERO;
-    let mut total_messages = 0u64;
+    let mut total_requests = 0u64;
+    let mut max_request_cost = Decimal::ZERO;
+    let mut total_active = 0i64;
+    let mut subagent_cost = Decimal::ZERO;
 
     for session in data {
         total_cost += session.total_cost;
-        total_messages += 1; // Count sessions instead of messages for now
+        total_active += session.active_seconds;
+        subagent_cost += session.subagents.cost;
+        total_requests += session.requests.count;
+        max_request_cost = max_request_cost.max(session.requests.max_cost);
 
         // Truncate long session IDs
         let session_id_str = session.session_id.0.clone();
@@ -355,17 +505,26 @@ pub fn output_session_table(data: &[SessionUsage], force_compact: bool) -> Resul
         if compact_mode {
             table.add_row(vec![
                 Cell::new(session_id).fg(Color::Cyan),
-                Cell::new("1"), // TODO: add message count to SessionUsage
+                Cell::new(session.requests.count),
                 Cell::new(format_cost(session.total_cost)).fg(Color::Green),
                 Cell::new(session.last_activity.format("%m/%d").to_string()),
             ]);
         } else {
             table.add_row(vec![
                 Cell::new(session_id).fg(Color::Cyan),
-                Cell::new("1"), // TODO: add message count to SessionUsage
+                Cell::new(session.requests.count),
                 Cell::new(format_tokens_compact(session.total_tokens())),
                 Cell::new(format_cost(session.total_cost)).fg(Color::Green),
-                Cell::new(session.last_activity.format("%Y-%m-%d").to_string()),
+                Cell::new(format_cost(session.requests.max_cost)),
+                Cell::new(format_subagent_share(session)).fg(Color::Magenta),
+                Cell::new(format_duration(Duration::seconds(session.active_seconds))),
+                Cell::new(
+                    session
+                        .first_timestamp
+                        .date_naive()
+                        .format("%Y-%m-%d")
+                        .to_string(),
+                ),
                 Cell::new(session.last_activity.format("%Y-%m-%d").to_string()),
             ]);
         }
@@ -375,31 +534,195 @@ pub fn output_session_table(data: &[SessionUsage], force_compact: bool) -> Resul
     if compact_mode {
         table.add_row(vec![
             Cell::new("TOTAL").fg(Color::Yellow),
-            Cell::new(total_messages.to_string()).fg(Color::Yellow),
+            Cell::new(total_requests).fg(Color::Yellow),
             Cell::new(format_cost(total_cost)).fg(Color::Green),
             Cell::new(""),
         ]);
     } else {
         table.add_row(vec![
             Cell::new("TOTAL").fg(Color::Yellow),
-            Cell::new(total_messages.to_string()).fg(Color::Yellow),
+            Cell::new(total_requests).fg(Color::Yellow),
             Cell::new(""),
             Cell::new(format_cost(total_cost)).fg(Color::Green),
+            Cell::new(format_cost(max_request_cost)).fg(Color::Yellow),
+            Cell::new(format_cost(subagent_cost)).fg(Color::Yellow),
+            Cell::new(format_duration(Duration::seconds(total_active))).fg(Color::Yellow),
             Cell::new(""),
             Cell::new(""),
         ]);
     }
 
-    println!("{}", table);
-    Ok(())
+    format!("{}\n", table)
+}
+
+/// Subagent cost with its share of the session, or "-" when no subagents ran
+fn format_subagent_share(session: &SessionUsage) -> String {
+    if session.subagents.request_count == 0 {
+        return "-".to_string();
+    }
+
+    let share = if session.total_cost > Decimal::ZERO {
+        (session.subagents.cost / session.total_cost * Decimal::from(100))
+            .to_f64()
+            .unwrap_or(0.0)
+    } else {
+        0.0
+    };
+
+    format!("{} ({:.0}%)", format_cost(session.subagents.cost), share)
+}
+
+/// Render one session's summary followed by its hourly or per-message timeline
+///
+/// Idle gaps longer than `idle_threshold` are shown as separator rows.
+pub fn render_session_timeline(
+    session: &SessionUsage,
+    points: &[TimelinePoint],
+    granularity: TimelineGranularity,
+    idle_threshold: Duration,
+    force_compact: bool,
+) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let local_time =
+        |t: &DateTime<Utc>, format: &str| t.with_timezone(&Local).format(format).to_string();
+
+    let messages_per_hour = session
+        .messages_per_hour
+        .map_or_else(|| "-".to_string(), |rate| format!("{:.1}", rate));
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{} {} ({})\n",
+        "Session:".bold(),
+        session.session_id.0.cyan(),
+        session.project_path.0.display()
+    ));
+    output.push_str(&format!(
+        "{} {} → {}  {} {}  {} {} (gaps over {}m excluded)\n",
+        "Period:".bold(),
+        local_time(&session.first_timestamp, "%Y-%m-%d %H:%M"),
+        local_time(&session.last_timestamp, "%Y-%m-%d %H:%M"),
+        "Duration:".bold(),
+        format_duration(Duration::seconds(session.duration_seconds)),
+        "Active:".bold(),
+        format_duration(Duration::seconds(session.active_seconds)),
+        idle_threshold.num_minutes(),
+    ));
+    output.push_str(&format!(
+        "{} {}  {} {}/h  {} {}  {} {}\n",
+        "Requests:".bold(),
+        session.requests.count,
+        "Rate:".bold(),
+        messages_per_hour,
+        "Tokens:".bold(),
+        format_tokens_compact(session.total_tokens()),
+        "Cost:".bold(),
+        format_cost(session.total_cost).green()
+    ));
+    output.push_str(&format!(
+        "{} {} reqs, {}, {}  {} {} reqs, {}, {}\n\n",
+        "Main:".bold(),
+        session.main_thread.request_count,
+        format_tokens_compact(session.main_thread.total_tokens()),
+        format_cost(session.main_thread.cost),
+        "Subagents:".bold(),
+        session.subagents.request_count,
+        format_tokens_compact(session.subagents.total_tokens()),
+        format_subagent_share(session),
+    ));
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let (time_header, time_format) = match granularity {
+        TimelineGranularity::Hour => ("Hour", "%m/%d %H:00"),
+        TimelineGranularity::Message => ("Time", "%m/%d %H:%M:%S"),
+    };
+
+    if compact_mode {
+        table.set_header(vec![
+            Cell::new(time_header).fg(Color::Blue),
+            Cell::new("Reqs").fg(Color::Blue),
+            Cell::new("Tokens").fg(Color::Blue),
+            Cell::new("Cost").fg(Color::Green),
+        ]);
+    } else {
+        table.set_header(vec![
+            Cell::new(time_header).fg(Color::Blue),
+            Cell::new("Reqs").fg(Color::Blue),
+            Cell::new("In").fg(Color::Blue),
+            Cell::new("Out").fg(Color::Blue),
+            Cell::new("Cache").fg(Color::Blue),
+            Cell::new("Total").fg(Color::Blue),
+            Cell::new("Cost").fg(Color::Green),
+            Cell::new("Models").fg(Color::Blue),
+        ]);
+    }
+
+    let columns = if compact_mode { 4 } else { 8 };
+
+    for point in points {
+        if let Some(idle) = point.idle_before_seconds {
+            let mut row = vec![Cell::new(format!(
+                "  ⋯ idle {}",
+                format_duration(Duration::seconds(idle))
+            ))
+            .fg(Color::DarkGrey)];
+            row.extend((1..columns).map(|_| Cell::new("")));
+            table.add_row(row);
+        }
+
+        let time = local_time(&point.time, time_format);
+        let cache = point.cache_creation_tokens + point.cache_read_tokens;
+
+        if compact_mode {
+            table.add_row(vec![
+                Cell::new(time),
+                Cell::new(point.requests),
+                Cell::new(format_tokens_compact(point.total_tokens())),
+                Cell::new(format_cost(point.cost)).fg(Color::Green),
+            ]);
+        } else {
+            let models = point
+                .models
+                .iter()
+                .map(|m| abbreviate_model_name(m.as_str()))
+                .join(", ");
+
+            table.add_row(vec![
+                Cell::new(time),
+                Cell::new(point.requests),
+                Cell::new(format_tokens_compact(point.input_tokens)),
+                Cell::new(format_tokens_compact(point.output_tokens)),
+                Cell::new(format_tokens_compact(cache)).fg(Color::Grey),
+                Cell::new(format_tokens_compact(point.total_tokens())).fg(Color::Yellow),
+                Cell::new(format_cost(point.cost)).fg(Color::Green),
+                Cell::new(models).fg(Color::Cyan),
+            ]);
+        }
+    }
+
+    output.push_str(&format!("{}\n", table));
+    output
 }
 
-/// Output blocks usage as table
-pub fn output_blocks_table(
+/// Render blocks usage as table
+pub fn render_blocks_table(
     data: &[SessionBlock],
     token_limit: Option<u64>,
     force_compact: bool,
-) -> Result<()> {
+) -> String {
     let width = get_terminal_width();
     let compact_mode = force_compact || width < 100;
 
@@ -420,6 +743,8 @@ pub fn output_blocks_table(
         Cell::new("Period").fg(Color::Blue),
         Cell::new("Usage").fg(Color::Blue),
         Cell::new("Cost").fg(Color::Green),
+        Cell::new("Reqs").fg(Color::Blue),
+        Cell::new("Max Req").fg(Color::Blue),
         Cell::new("Status").fg(Color::Blue),
     ]);
 
@@ -448,10 +773,768 @@ pub fn output_blocks_table(
             Cell::new(period),
             Cell::new(usage_pct),
             Cell::new(format_cost(block.cost_usd)).fg(Color::Green),
+            Cell::new(block.requests.count),
+            Cell::new(format_cost(block.requests.max_cost)),
             Cell::new(status),
         ]);
     }
 
-    println!("{}", table);
-    Ok(())
+    format!("{}\n", table)
+}
+
+/// Render prompt cache statistics as table
+pub fn render_cache_table(
+    data: &[CacheUsage],
+    totals: &CacheStats,
+    group_header: &str,
+    force_compact: bool,
+) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 120;
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    if compact_mode {
+        table.set_header(vec![
+            Cell::new(group_header).fg(Color::Blue),
+            Cell::new("Hit").fg(Color::Blue),
+            Cell::new("Saved").fg(Color::Green),
+            Cell::new("Payback").fg(Color::Blue),
+        ]);
+    } else {
+        table.set_header(vec![
+            Cell::new(group_header).fg(Color::Blue),
+            Cell::new("Hit").fg(Color::Blue),
+            Cell::new("Write").fg(Color::Blue),
+            Cell::new("Read").fg(Color::Blue),
+            Cell::new("Cost").fg(Color::Green),
+            Cell::new("Uncached").fg(Color::Blue),
+            Cell::new("Saved").fg(Color::Green),
+            Cell::new("Payback").fg(Color::Blue),
+        ]);
+    }
+
+    let mut add_row = |label: Cell, stats: &CacheStats| {
+        let hit = format!("{:.1}%", stats.hit_ratio * 100.0);
+        let saved_color = if stats.net_savings < Decimal::ZERO {
+            Color::Red
+        } else {
+            Color::Green
+        };
+        let payback = stats
+            .payback
+            .map_or_else(|| "-".to_string(), |p| format!("{:.1}x", p));
+
+        if compact_mode {
+            table.add_row(vec![
+                label,
+                Cell::new(hit),
+                Cell::new(format_cost(stats.net_savings)).fg(saved_color),
+                Cell::new(payback),
+            ]);
+        } else {
+            table.add_row(vec![
+                label,
+                Cell::new(hit),
+                Cell::new(format_tokens_compact(stats.cache_creation_tokens)).fg(Color::Grey),
+                Cell::new(format_tokens_compact(stats.cache_read_tokens)).fg(Color::Grey),
+                Cell::new(format_cost(stats.cost)).fg(Color::Green),
+                Cell::new(format_cost(stats.uncached_cost)),
+                Cell::new(format_cost(stats.net_savings)).fg(saved_color),
+                Cell::new(payback),
+            ]);
+        }
+    };
+
+    for usage in data {
+        // Truncate long session IDs
+        let group = if usage.group.len() > 30 && compact_mode {
+            format!("{}...", &usage.group[..27])
+        } else if usage.group.len() > 50 {
+            format!("{}...", &usage.group[..47])
+        } else {
+            usage.group.clone()
+        };
+
+        add_row(Cell::new(group).fg(Color::Cyan), &usage.stats);
+    }
+
+    add_row(Cell::new("TOTAL").fg(Color::Yellow), totals);
+
+    format!("{}\n", table)
+}
+
+/// Format a signed cost change with an up/down indicator
+fn format_cost_change(difference: Decimal) -> (String, Color) {
+    if difference > Decimal::ZERO {
+        (format!("▲ {}", format_cost(difference)), Color::Red)
+    } else if difference < Decimal::ZERO {
+        (format!("▼ {}", format_cost(difference.abs())), Color::Green)
+    } else {
+        ("-".to_string(), Color::Grey)
+    }
+}
+
+/// Render actual vs simulated costs as table
+pub fn render_simulation_table(
+    data: &[SimulatedUsage],
+    totals: &SimulatedUsage,
+    group_header: &str,
+    force_compact: bool,
+) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut header = vec![
+        Cell::new(group_header).fg(Color::Blue),
+        Cell::new("Actual").fg(Color::Green),
+        Cell::new("Simulated").fg(Color::Green),
+        Cell::new("Change").fg(Color::Blue),
+    ];
+    if !compact_mode {
+        header.push(Cell::new("%").fg(Color::Blue));
+        header.push(Cell::new("Models").fg(Color::Blue));
+    }
+    table.set_header(header);
+
+    let mut add_row = |label: Cell, usage: &SimulatedUsage| {
+        let (change, color) = format_cost_change(usage.difference);
+        let mut row = vec![
+            label,
+            Cell::new(format_cost(usage.actual_cost)).fg(Color::Green),
+            Cell::new(format_cost(usage.simulated_cost)).fg(Color::Green),
+            Cell::new(change).fg(color),
+        ];
+
+        if !compact_mode {
+            let percent = usage
+                .change_percent
+                .map_or_else(|| "-".to_string(), |p| format!("{:+.1}%", p));
+            let models = usage
+                .simulated_models
+                .iter()
+                .map(|m| abbreviate_model_name(m.as_str()))
+                .unique()
+                .join(", ");
+
+            row.push(Cell::new(percent).fg(color));
+            row.push(Cell::new(models).fg(Color::Cyan));
+        }
+
+        table.add_row(row);
+    };
+
+    for usage in data {
+        add_row(Cell::new(&usage.group).fg(Color::Cyan), usage);
+    }
+
+    add_row(Cell::new("TOTAL").fg(Color::Yellow), totals);
+
+    format!("{}\n", table)
+}
+
+/// Render cost and token changes between two periods as table
+pub fn render_comparison_table(
+    data: &[ComparisonRow],
+    totals: &ComparisonRow,
+    group_header: &str,
+    force_compact: bool,
+) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut header = vec![
+        Cell::new(group_header).fg(Color::Blue),
+        Cell::new("Previous").fg(Color::Green),
+        Cell::new("Current").fg(Color::Green),
+        Cell::new("Change").fg(Color::Blue),
+        Cell::new("%").fg(Color::Blue),
+    ];
+    if !compact_mode {
+        header.push(Cell::new("Prev Tokens").fg(Color::Blue));
+        header.push(Cell::new("Tokens").fg(Color::Blue));
+        header.push(Cell::new("Token %").fg(Color::Blue));
+    }
+    table.set_header(header);
+
+    let format_percent =
+        |percent: Option<f64>| percent.map_or_else(|| "new".to_string(), |p| format!("{:+.1}%", p));
+
+    let mut add_row = |label: Cell, row: &ComparisonRow| {
+        let (change, color) = format_cost_change(row.cost_change);
+        let percent = if row.cost_change.is_zero() {
+            "-".to_string()
+        } else {
+            format_percent(row.cost_change_percent)
+        };
+        let mut cells = vec![
+            label,
+            Cell::new(format_cost(row.previous_cost)).fg(Color::Green),
+            Cell::new(format_cost(row.current_cost)).fg(Color::Green),
+            Cell::new(change).fg(color),
+            Cell::new(percent).fg(color),
+        ];
+
+        if !compact_mode {
+            let (arrow, token_color) = match row.token_change.signum() {
+                1 => ("▲ ", Color::Red),
+                -1 => ("▼ ", Color::Green),
+                _ => ("", Color::Grey),
+            };
+            let token_percent = if row.token_change == 0 {
+                "-".to_string()
+            } else {
+                format!("{}{}", arrow, format_percent(row.token_change_percent))
+            };
+
+            cells.push(Cell::new(format_tokens_compact(row.previous_tokens)));
+            cells.push(Cell::new(format_tokens_compact(row.current_tokens)));
+            cells.push(Cell::new(token_percent).fg(token_color));
+        }
+
+        table.add_row(cells);
+    };
+
+    for row in data {
+        add_row(Cell::new(&row.group).fg(Color::Cyan), row);
+    }
+
+    add_row(Cell::new("TOTAL").fg(Color::Yellow), totals);
+
+    format!("{}\n", table)
+}
+
+/// Render month-end and quarter-end projections with budget status
+pub fn render_forecast_table(forecast: &Forecast, force_compact: bool) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let method = match forecast.method {
+        ForecastMethod::MovingAverage => "moving average",
+        ForecastMethod::Linear => "linear trend",
+        ForecastMethod::Weekday => "weekday averages",
+    };
+
+    let mut output = format!(
+        "{} {} ({} over {} days)  {} {} ± {}\n\n",
+        "Forecast as of".bold(),
+        forecast.as_of,
+        method,
+        forecast.history_days,
+        "Next day:".bold(),
+        format_cost(forecast.next_day).green(),
+        format_cost(forecast.daily_std_dev),
+    );
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut header = vec![
+        Cell::new("Period").fg(Color::Blue),
+        Cell::new("Spent").fg(Color::Green),
+        Cell::new("Projected").fg(Color::Green),
+    ];
+    if !compact_mode {
+        header.push(Cell::new("Days Left").fg(Color::Blue));
+        header.push(Cell::new("95% Range").fg(Color::Blue));
+    }
+    header.push(Cell::new("Budget").fg(Color::Blue));
+    header.push(Cell::new("Status").fg(Color::Blue));
+    table.set_header(header);
+
+    for period in &forecast.periods {
+        let mut row = vec![
+            Cell::new(&period.label).fg(Color::Cyan),
+            Cell::new(format_cost(period.spent)).fg(Color::Green),
+            Cell::new(format_cost(period.projected)).fg(Color::Green),
+        ];
+        if !compact_mode {
+            row.push(Cell::new(period.remaining_days));
+            row.push(Cell::new(format!(
+                "{} – {}",
+                format_cost(period.low),
+                format_cost(period.high)
+            )));
+        }
+
+        let (status, color) = match period.budget {
+            None => ("-".to_string(), Color::Grey),
+            Some(budget) if period.over_budget => (
+                format!("⚠ over by {}", format_cost(period.projected - budget)),
+                Color::Red,
+            ),
+            Some(budget) if period.high > budget => ("at risk".to_string(), Color::Yellow),
+            Some(_) => ("✓ within".to_string(), Color::Green),
+        };
+        row.push(Cell::new(
+            period.budget.map_or_else(|| "-".to_string(), format_cost),
+        ));
+        row.push(Cell::new(status).fg(color));
+
+        table.add_row(row);
+    }
+
+    output.push_str(&format!("{}\n", table));
+    output
+}
+
+/// Render anomalous days and sessions with the sessions and models behind them
+pub fn render_anomalies_table(anomalies: &[Anomaly], force_compact: bool) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut header = vec![
+        Cell::new("Kind").fg(Color::Blue),
+        Cell::new("When").fg(Color::Blue),
+        Cell::new("Cost").fg(Color::Green),
+    ];
+    if !compact_mode {
+        header.push(Cell::new("Baseline").fg(Color::Blue));
+        header.push(Cell::new("Score").fg(Color::Blue));
+    }
+    header.push(Cell::new("Responsible").fg(Color::Blue));
+    table.set_header(header);
+
+    let format_share = |name: &str, share: f64| format!("{} {:.0}%", name, share);
+
+    for anomaly in anomalies {
+        let (kind, when) = match anomaly.kind {
+            AnomalyKind::Day => ("day", anomaly.key.clone()),
+            AnomalyKind::Session => (
+                "session",
+                anomaly
+                    .time
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+            ),
+        };
+
+        // Sessions by ID prefix (usable with `session --id`), then models
+        let mut responsible: Vec<String> = Vec::new();
+        if anomaly.kind == AnomalyKind::Session {
+            let project = anomaly.project.as_deref().unwrap_or("unknown");
+            responsible.push(format!(
+                "{} ({})",
+                anomaly.key.chars().take(8).collect::<String>(),
+                project
+            ));
+        }
+        responsible.extend(
+            anomaly
+                .sessions
+                .iter()
+                .take(if compact_mode { 1 } else { 3 })
+                .map(|s| format_share(&s.name.chars().take(8).collect::<String>(), s.share)),
+        );
+        responsible.push(
+            anomaly
+                .models
+                .iter()
+                .map(|m| format_share(&abbreviate_model_name(m.name.as_str()), m.share))
+                .join(", "),
+        );
+
+        let mut row = vec![
+            Cell::new(kind).fg(Color::Cyan),
+            Cell::new(when),
+            Cell::new(format_cost(anomaly.cost)).fg(Color::Red),
+        ];
+        if !compact_mode {
+            row.push(Cell::new(format_cost(anomaly.baseline)));
+            row.push(Cell::new(format!("{:.1}", anomaly.score)).fg(Color::Yellow));
+        }
+        row.push(Cell::new(responsible.join("\n")));
+
+        table.add_row(row);
+    }
+
+    format!("{}\n", table)
+}
+
+/// Render per-user totals with each user's model mix
+pub fn render_team_table(team: &TeamSummary, force_compact: bool) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut header = vec![
+        Cell::new("User").fg(Color::Blue),
+        Cell::new("Cost").fg(Color::Green),
+        Cell::new("Share").fg(Color::Blue),
+    ];
+    if !compact_mode {
+        header.push(Cell::new("Sessions").fg(Color::Blue));
+        header.push(Cell::new("Reqs").fg(Color::Blue));
+        header.push(Cell::new("Tokens").fg(Color::Blue));
+        header.push(Cell::new("Machines").fg(Color::Blue));
+        header.push(Cell::new("Last").fg(Color::Blue));
+    }
+    header.push(Cell::new("Model Mix").fg(Color::Blue));
+    table.set_header(header);
+
+    let mut add_row = |label: Cell, user: &UserSummary| {
+        let mix = user
+            .models
+            .iter()
+            .map(|m| {
+                format!(
+                    "{} {:.0}%",
+                    abbreviate_model_name(m.model.as_str()),
+                    m.cost_share
+                )
+            })
+            .join(", ");
+
+        let mut row = vec![
+            label,
+            Cell::new(format_cost(user.total_cost)).fg(Color::Green),
+            Cell::new(format!("{:.0}%", user.cost_share)),
+        ];
+        if !compact_mode {
+            row.push(Cell::new(user.sessions));
+            row.push(Cell::new(user.requests));
+            row.push(Cell::new(format_tokens_compact(user.total_tokens)));
+            row.push(Cell::new(user.machines.join(", ")));
+            row.push(Cell::new(
+                user.last_activity.map_or_else(String::new, |t| {
+                    t.with_timezone(&Local).format("%Y-%m-%d").to_string()
+                }),
+            ));
+        }
+        row.push(Cell::new(mix).fg(Color::Cyan));
+
+        table.add_row(row);
+    };
+
+    for user in &team.users {
+        add_row(Cell::new(&user.user).fg(Color::Cyan), user);
+    }
+
+    add_row(Cell::new("TOTAL").fg(Color::Yellow), &team.totals);
+
+    format!("{}\n", table)
+}
+
+/// Render the result of an SQL query, one column per result column
+pub fn render_query_table(columns: &[String], rows: &[Vec<String>], force_compact: bool) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(columns.iter().map(|c| Cell::new(c).fg(Color::Blue)));
+
+    for row in rows {
+        table.add_row(row.iter().map(Cell::new));
+    }
+
+    format!("{}\n", table)
+}
+
+/// Render subscription plan prices against pay-as-you-go costs
+pub fn render_plan_comparison_table(data: &[PlanComparison], days: i64) -> String {
+    let mut table = Table::new();
+
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.set_header(vec![
+        Cell::new("Plan").fg(Color::Blue),
+        Cell::new("Monthly").fg(Color::Blue),
+        Cell::new(format!("{} days", days)).fg(Color::Blue),
+        Cell::new("API - Plan").fg(Color::Green),
+        Cell::new("Simulated - Plan").fg(Color::Green),
+    ]);
+
+    for plan in data {
+        // Positive means the plan is cheaper than paying per token
+        let api_margin = plan.api_cost - plan.plan_cost;
+        let simulated_margin = plan.simulated_cost - plan.plan_cost;
+        let margin_color = |margin: Decimal| {
+            if margin >= Decimal::ZERO {
+                Color::Green
+            } else {
+                Color::Red
+            }
+        };
+
+        table.add_row(vec![
+            Cell::new(&plan.plan).fg(Color::Cyan),
+            Cell::new(format_cost(plan.monthly_cost)),
+            Cell::new(format_cost(plan.plan_cost)),
+            Cell::new(format_signed_cost(api_margin)).fg(margin_color(api_margin)),
+            Cell::new(format_signed_cost(simulated_margin)).fg(margin_color(simulated_margin)),
+        ]);
+    }
+
+    format!("{}\n", table)
+}
+
+/// Format a cost with an explicit sign
+fn format_signed_cost(cost: Decimal) -> String {
+    if cost < Decimal::ZERO {
+        format!("-{}", format_cost(cost.abs()))
+    } else {
+        format!("+{}", format_cost(cost))
+    }
+}
+
+/// Render subscription plan value per month as table
+pub fn render_plan_value_table(data: &[PlanValue], force_compact: bool) -> String {
+    let width = get_terminal_width();
+    let compact_mode = force_compact || width < 100;
+
+    let mut table = Table::new();
+
+    if compact_mode {
+        table.load_preset(UTF8_BORDERS_ONLY);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+    }
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    if compact_mode {
+        table.set_header(vec![
+            Cell::new("Month").fg(Color::Blue),
+            Cell::new("Plan").fg(Color::Blue),
+            Cell::new("ROI").fg(Color::Blue),
+            Cell::new("Break-even").fg(Color::Blue),
+        ]);
+    } else {
+        table.set_header(vec![
+            Cell::new("Month").fg(Color::Blue),
+            Cell::new("Plan").fg(Color::Blue),
+            Cell::new("Plan Cost").fg(Color::Blue),
+            Cell::new("API Cost").fg(Color::Green),
+            Cell::new("Net Value").fg(Color::Green),
+            Cell::new("ROI").fg(Color::Blue),
+            Cell::new("Break-even").fg(Color::Blue),
+        ]);
+    }
+
+    for value in data {
+        let roi_color = match value.roi {
+            Some(roi) if roi >= 1.0 => Color::Green,
+            Some(_) => Color::Red,
+            None => Color::Grey,
+        };
+        let roi = value
+            .roi
+            .map_or_else(|| "-".to_string(), |r| format!("{:.1}x", r));
+        let break_even = value
+            .break_even_day
+            .map_or_else(|| "not reached".to_string(), |d| format!("day {}", d));
+
+        if compact_mode {
+            table.add_row(vec![
+                Cell::new(value.month.to_string()),
+                Cell::new(&value.plan).fg(Color::Cyan),
+                Cell::new(roi).fg(roi_color),
+                Cell::new(break_even),
+            ]);
+        } else {
+            table.add_row(vec![
+                Cell::new(value.month.to_string()),
+                Cell::new(&value.plan).fg(Color::Cyan),
+                Cell::new(format_cost(value.monthly_cost)),
+                Cell::new(format_cost(value.api_cost)).fg(Color::Green),
+                Cell::new(format_signed_cost(value.net_value)).fg(roi_color),
+                Cell::new(roi).fg(roi_color),
+                Cell::new(break_even),
+            ]);
+        }
+    }
+
+    format!("{}\n", table)
+}
+
+/// Formatted heatmap value for one weekday, either a single hour or the whole day
+fn heatmap_value(
+    heatmap: &Heatmap,
+    metric: HeatmapMetric,
+    day: usize,
+    hour: Option<usize>,
+) -> String {
+    let hours = hour.map_or(0..24, |h| h..h + 1);
+    match metric {
+        HeatmapMetric::Cost => format_cost(heatmap.cost[day][hours].iter().sum()),
+        HeatmapMetric::Tokens => format_tokens_compact(heatmap.tokens[day][hours].iter().sum()),
+        HeatmapMetric::Requests => heatmap.requests[day][hours].iter().sum::<u64>().to_string(),
+    }
+}
+
+/// Shaded, colored cell of `width` characters for a value at `ratio` of the busiest cell
+fn heatmap_shade(ratio: f64, width: usize) -> ColoredString {
+    if ratio <= 0.0 {
+        "·".repeat(width).dimmed()
+    } else if ratio <= 0.25 {
+        "░".repeat(width).green()
+    } else if ratio <= 0.5 {
+        "▒".repeat(width).yellow()
+    } else if ratio <= 0.75 {
+        "▓".repeat(width).truecolor(255, 140, 0)
+    } else {
+        "█".repeat(width).red()
+    }
+}
+
+/// Render a weekday × hour grid shaded by `metric`, with daily totals and the peak hour
+pub fn render_heatmap(heatmap: &Heatmap, metric: HeatmapMetric, force_compact: bool) -> String {
+    let compact_mode = force_compact || get_terminal_width() < 100;
+    let cell_width = if compact_mode { 2 } else { 3 };
+
+    let values = heatmap.values(metric);
+    let max = values.iter().flatten().copied().fold(0.0, f64::max);
+
+    let metric_name = match metric {
+        HeatmapMetric::Cost => "cost",
+        HeatmapMetric::Tokens => "tokens",
+        HeatmapMetric::Requests => "requests",
+    };
+
+    let mut output = format!(
+        "{} ({}, {})\n\n",
+        "Usage by weekday and hour".bold(),
+        metric_name,
+        heatmap.timezone
+    );
+
+    // Hour labels; compact grids only label every third hour
+    output.push_str("     ");
+    for hour in 0..24 {
+        let label = if compact_mode && hour % 3 != 0 {
+            String::new()
+        } else {
+            format!("{:02}", hour)
+        };
+        output.push_str(&format!("{:<width$}", label, width = cell_width));
+    }
+    output.push_str(&format!(" {}\n", "Total".blue()));
+
+    for (day, weekday) in heatmap.weekdays.iter().enumerate() {
+        output.push_str(&format!("{:<5}", weekday));
+        for value in &values[day] {
+            let ratio = if max > 0.0 { value / max } else { 0.0 };
+            output.push_str(&format!("{} ", heatmap_shade(ratio, cell_width - 1)));
+        }
+        output.push_str(&format!(
+            " {}\n",
+            heatmap_value(heatmap, metric, day, None).green()
+        ));
+    }
+
+    output.push('\n');
+    let legend = [
+        ("none", 0.0),
+        ("≤25%", 0.25),
+        ("≤50%", 0.5),
+        ("≤75%", 0.75),
+        (">75% of peak", 1.0),
+    ]
+    .iter()
+    .map(|(label, ratio)| format!("{} {}", heatmap_shade(*ratio, 1), label))
+    .join("  ");
+    output.push_str(&format!("     {}\n", legend));
+
+    if let Some((day, hour)) = heatmap.peak(metric) {
+        let quietest = heatmap
+            .quietest_hours(metric)
+            .into_iter()
+            .take(3)
+            .map(|h| format!("{:02}:00", h))
+            .join(", ");
+
+        output.push_str(&format!(
+            "\n{} {} {:02}:00 ({})  {} {}\n",
+            "Peak:".bold(),
+            heatmap.weekdays[day],
+            hour,
+            heatmap_value(heatmap, metric, day, Some(hour)),
+            "Quietest hours:".bold(),
+            quietest
+        ));
+    }
+
+    output
 }
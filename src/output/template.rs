@@ -0,0 +1,87 @@
+use anyhow::{bail, Result};
+
+/// A parsed format template such as `"{model} {block_cost} ({time_left})"`
+///
+/// Placeholders are names in braces. `{{` and `}}` produce literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Placeholder(String),
+}
+
+impl Template {
+    /// Parse a template, rejecting placeholders not in `known`
+    pub fn parse(source: &str, known: &[&str]) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => bail!("Unclosed placeholder '{{{}' in template", name),
+                        }
+                    }
+
+                    let name = name.trim().to_string();
+                    if !known.contains(&name.as_str()) {
+                        bail!(
+                            "Unknown placeholder '{{{}}}'. Available: {}",
+                            name,
+                            known
+                                .iter()
+                                .map(|k| format!("{{{}}}", k))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                    }
+
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Placeholder(name));
+                }
+                '}' => bail!("Unmatched '}}' in template"),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Render the template, asking `value` for each placeholder
+    pub fn render<F>(&self, mut value: F) -> String
+    where
+        F: FnMut(&str) -> String,
+    {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Placeholder(name) => value(name),
+            })
+            .collect()
+    }
+}
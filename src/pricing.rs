@@ -1,14 +1,18 @@
+//! Per-model token prices and cost calculation.
+
 use crate::types::{ModelName, TokenCounts};
 use anyhow::Result;
 use lazy_static::lazy_static;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{debug, warn};
 
 /// Model pricing information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ModelPricing {
     pub input_price: Decimal,          // Price per 1M input tokens
     pub output_price: Decimal,         // Price per 1M output tokens
@@ -149,6 +153,15 @@ impl PricingFetcher {
         }
     }
 
+    /// Use these prices ahead of the bundled ones
+    ///
+    /// Keys match a model name exactly or as a prefix, so `claude-sonnet-4-5`
+    /// also prices `claude-sonnet-4-5-20250929`.
+    pub fn with_custom_pricing(mut self, pricing: HashMap<String, ModelPricing>) -> Self {
+        self.custom_pricing = Arc::new(pricing);
+        self
+    }
+
     /// Fuzzy match model names to find pricing
     /// Handles variations like claude-sonnet-4-5-YYYYMMDD -> claude-sonnet-4-5
     fn find_matching_model(model_name: &str) -> Option<&'static str> {
@@ -208,23 +221,35 @@ impl PricingFetcher {
         None
     }
 
-    /// Calculate cost for a given model and token counts
-    pub async fn calculate_cost(&self, model: &ModelName, tokens: &TokenCounts) -> Result<Decimal> {
-        // Check custom pricing first
+    /// Look up pricing from custom or bundled prices, without any network requests
+    pub fn pricing_for(&self, model: &ModelName) -> Option<ModelPricing> {
+        // Check custom pricing first, preferring the longest matching prefix
         if let Some(pricing) = self.custom_pricing.get(model.as_str()) {
-            return Ok(pricing.calculate_cost(tokens));
+            return Some(pricing.clone());
+        }
+        if let Some((_, pricing)) = self
+            .custom_pricing
+            .iter()
+            .filter(|(name, _)| model.as_str().starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+        {
+            return Some(pricing.clone());
         }
 
         // Try fuzzy matching to find a known model
-        if let Some(matched_model) = Self::find_matching_model(model.as_str()) {
-            if let Some(pricing) = MODEL_PRICING.get(matched_model) {
-                debug!(
-                    "Matched model '{}' to pricing for '{}'",
-                    model.as_str(),
-                    matched_model
-                );
-                return Ok(pricing.calculate_cost(tokens));
-            }
+        let matched_model = Self::find_matching_model(model.as_str())?;
+        debug!(
+            "Matched model '{}' to pricing for '{}'",
+            model.as_str(),
+            matched_model
+        );
+        MODEL_PRICING.get(matched_model).cloned()
+    }
+
+    /// Calculate cost for a given model and token counts
+    pub async fn calculate_cost(&self, model: &ModelName, tokens: &TokenCounts) -> Result<Decimal> {
+        if let Some(pricing) = self.pricing_for(model) {
+            return Ok(pricing.calculate_cost(tokens));
         }
 
         // Try online pricing if enabled
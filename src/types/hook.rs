@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// JSON payload Claude Code pipes to the `statusLine` command on stdin
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatuslineHookInput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript_path: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<HookModel>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<HookWorkspace>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HookModel {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HookWorkspace {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_dir: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+}
+
+impl StatuslineHookInput {
+    /// Directory the Claude Code project was started from
+    pub fn project_dir(&self) -> Option<&str> {
+        self.workspace
+            .as_ref()
+            .and_then(|w| w.project_dir.as_deref())
+            .or(self.cwd.as_deref())
+    }
+}
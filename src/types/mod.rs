@@ -1,11 +1,15 @@
+//! Core data types: raw transcript records, loaded entries and report rows.
+
 use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+pub mod hook;
 pub mod session;
 pub mod tokens;
 pub mod usage;
 
+pub use hook::*;
 pub use session::*;
 pub use tokens::*;
 pub use usage::*;
@@ -101,9 +105,13 @@ pub struct MonthlyDate {
 
 impl MonthlyDate {
     pub fn from_datetime(dt: DateTime<Utc>) -> Self {
+        Self::from_date(dt.date_naive())
+    }
+
+    pub fn from_date(date: NaiveDate) -> Self {
         Self {
-            year: dt.year(),
-            month: dt.month(),
+            year: date.year(),
+            month: date.month(),
         }
     }
 }
@@ -134,9 +142,10 @@ impl std::fmt::Display for WeeklyDate {
 }
 
 /// Cost calculation mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
 pub enum CostMode {
     /// Use pre-calculated costUSD when available, otherwise calculate from tokens
+    #[default]
     Auto,
     /// Always calculate costs from token counts using model pricing
     Calculate,
@@ -144,27 +153,16 @@ pub enum CostMode {
     Display,
 }
 
-impl Default for CostMode {
-    fn default() -> Self {
-        Self::Auto
-    }
-}
-
 /// Sort order for results
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
 pub enum SortOrder {
+    #[default]
     Asc,
     Desc,
 }
 
-impl Default for SortOrder {
-    fn default() -> Self {
-        Self::Asc
-    }
-}
-
 /// Common options for all commands
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CommonOptions {
     pub json: bool,
     pub mode: CostMode,
@@ -175,4 +173,45 @@ pub struct CommonOptions {
     pub project: Option<String>,
     pub jq: Option<String>,
     pub compact: bool,
+    /// Usage archives loaded alongside the local logs
+    pub data_files: Vec<LabeledPath>,
+    /// Claude data directories loaded alongside the local logs
+    pub data_dirs: Vec<LabeledPath>,
+    /// Only load entries belonging to this user
+    pub user: Option<String>,
+    /// Skip the history store written by `archive sync`
+    pub no_history: bool,
+}
+
+/// A path optionally tagged with a user label, written `LABEL=PATH`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabeledPath {
+    pub label: Option<String>,
+    pub path: PathBuf,
+}
+
+impl LabeledPath {
+    pub fn new(label: Option<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            label,
+            path: path.into(),
+        }
+    }
+}
+
+impl std::str::FromStr for LabeledPath {
+    type Err = String;
+
+    /// A prefix before `=` is a label unless it looks like part of a path
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((label, path))
+                if !label.is_empty() && !label.contains(['/', '\\']) && !path.is_empty() =>
+            {
+                Ok(Self::new(Some(label.to_string()), path))
+            }
+            _ if s.is_empty() => Err("Path must not be empty".to_string()),
+            _ => Ok(Self::new(None, s)),
+        }
+    }
 }
@@ -1,4 +1,6 @@
-use super::{ModelBreakdown, ModelName, ProjectPath, SessionId, TokenCounts};
+use super::{
+    LoadedUsageEntry, ModelBreakdown, ModelName, ProjectPath, RequestStats, SessionId, TokenCounts,
+};
 use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -30,6 +32,23 @@ pub struct SessionUsage {
     #[serde(rename = "lastActivity")]
     pub last_activity: NaiveDate,
 
+    #[serde(rename = "firstTimestamp")]
+    pub first_timestamp: DateTime<Utc>,
+
+    #[serde(rename = "lastTimestamp")]
+    pub last_timestamp: DateTime<Utc>,
+
+    /// Wall-clock time from first to last message
+    #[serde(rename = "durationSeconds")]
+    pub duration_seconds: i64,
+
+    /// Time between messages, excluding idle gaps
+    #[serde(rename = "activeSeconds")]
+    pub active_seconds: i64,
+
+    #[serde(rename = "messagesPerHour")]
+    pub messages_per_hour: Option<f64>,
+
     pub versions: Vec<String>,
 
     #[serde(rename = "modelsUsed")]
@@ -37,6 +56,17 @@ pub struct SessionUsage {
 
     #[serde(rename = "modelBreakdowns")]
     pub model_breakdowns: Vec<ModelBreakdown>,
+
+    #[serde(default)]
+    pub requests: RequestStats,
+
+    /// Usage from the main conversation
+    #[serde(rename = "mainThread", default)]
+    pub main_thread: ThreadUsage,
+
+    /// Usage from subagents (sidechains) started by the session
+    #[serde(default)]
+    pub subagents: ThreadUsage,
 }
 
 impl SessionUsage {
@@ -45,6 +75,42 @@ impl SessionUsage {
     }
 }
 
+/// Tokens and cost for one side of a session: main thread or subagents
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThreadUsage {
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+
+    #[serde(rename = "cacheCreationTokens")]
+    pub cache_creation_tokens: u64,
+
+    #[serde(rename = "cacheReadTokens")]
+    pub cache_read_tokens: u64,
+
+    pub cost: Decimal,
+
+    #[serde(rename = "requestCount")]
+    pub request_count: u64,
+}
+
+impl ThreadUsage {
+    pub fn add(&mut self, entry: &LoadedUsageEntry) {
+        self.input_tokens += entry.tokens.input_tokens;
+        self.output_tokens += entry.tokens.output_tokens;
+        self.cache_creation_tokens += entry.tokens.cache_creation_input_tokens;
+        self.cache_read_tokens += entry.tokens.cache_read_input_tokens;
+        self.cost += entry.cost;
+        self.request_count += 1;
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+}
+
 /// 5-hour billing block
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionBlock {
@@ -73,6 +139,9 @@ pub struct SessionBlock {
 
     pub models: Vec<String>,
 
+    #[serde(default)]
+    pub requests: RequestStats,
+
     #[serde(
         rename = "usageLimitResetTime",
         skip_serializing_if = "Option::is_none"
@@ -20,13 +20,6 @@ impl TokenCounts {
             + self.cache_creation_input_tokens
             + self.cache_read_input_tokens
     }
-
-    pub fn add(&mut self, other: &TokenCounts) {
-        self.input_tokens += other.input_tokens;
-        self.output_tokens += other.output_tokens;
-        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
-        self.cache_read_input_tokens += other.cache_read_input_tokens;
-    }
 }
 
 /// Aggregated token counts (camelCase field names for consistency with original)
@@ -72,3 +65,14 @@ impl From<&TokenCounts> for AggregatedTokenCounts {
         }
     }
 }
+
+impl From<&AggregatedTokenCounts> for TokenCounts {
+    fn from(tokens: &AggregatedTokenCounts) -> Self {
+        Self {
+            input_tokens: tokens.input_tokens,
+            output_tokens: tokens.output_tokens,
+            cache_creation_input_tokens: tokens.cache_creation_tokens,
+            cache_read_input_tokens: tokens.cache_read_tokens,
+        }
+    }
+}
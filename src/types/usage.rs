@@ -14,6 +14,9 @@ pub struct UsageData {
     #[serde(rename = "sessionId", skip_serializing_if = "Option::is_none")]
     pub session_id: Option<String>,
 
+    #[serde(rename = "gitBranch", skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+
     pub timestamp: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -29,6 +32,10 @@ pub struct UsageData {
 
     #[serde(rename = "isApiErrorMessage", skip_serializing_if = "Option::is_none")]
     pub is_api_error_message: Option<bool>,
+
+    /// Set on subagent (Task tool) messages
+    #[serde(rename = "isSidechain", skip_serializing_if = "Option::is_none")]
+    pub is_sidechain: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,7 +59,11 @@ pub struct ContentItem {
 }
 
 /// Loaded and processed usage entry
-#[derive(Debug, Clone)]
+///
+/// This is also the record format of usage archives, so fields are only ever added
+/// with serde defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LoadedUsageEntry {
     pub timestamp: DateTime<Utc>,
     pub model: ModelName,
@@ -63,9 +74,31 @@ pub struct LoadedUsageEntry {
     pub message_id: Option<MessageId>,
     pub project: Option<String>,
     pub version: Option<String>,
+    /// Working directory the request was made from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Git branch checked out in `cwd` at the time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Whether the entry came from a subagent rather than the main conversation
+    #[serde(default)]
+    pub is_sidechain: bool,
+    /// Label of the machine an archived entry was recorded on; `None` for local logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine: Option<String>,
+    /// User an archived entry belongs to; `None` for local logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
 }
 
 impl LoadedUsageEntry {
+    /// User the entry belongs to; local logs belong to [`local_user_label`](crate::archive::local_user_label)
+    pub fn user_label(&self) -> &str {
+        self.user
+            .as_deref()
+            .unwrap_or_else(|| crate::archive::local_user_label())
+    }
+
     /// Create a unique hash for deduplication
     pub fn unique_hash(&self) -> String {
         match (&self.message_id, &self.request_id) {
@@ -75,6 +108,24 @@ impl LoadedUsageEntry {
             (None, None) => String::new(),
         }
     }
+
+    /// Key identifying the entry across sources: the unique hash, or the
+    /// timestamp, session, model and tokens for entries without message or
+    /// request IDs
+    pub fn record_key(&self) -> String {
+        let hash = self.unique_hash();
+        if !hash.is_empty() {
+            return hash;
+        }
+
+        format!(
+            "{}|{}|{}|{}",
+            self.timestamp.to_rfc3339(),
+            self.session_id.as_ref().map_or("", |s| s.0.as_str()),
+            self.model.as_str(),
+            self.tokens.total()
+        )
+    }
 }
 
 /// Model breakdown for aggregated data
@@ -96,6 +147,42 @@ pub struct ModelBreakdown {
     pub cache_read_tokens: u64,
 
     pub cost: Decimal,
+
+    #[serde(default)]
+    pub requests: RequestStats,
+}
+
+/// Per-request statistics for a group of entries
+///
+/// Tokens count every token type of a request. Count, averages and maximums are exact;
+/// percentiles are nearest-rank values estimated to within 1%.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequestStats {
+    pub count: u64,
+
+    #[serde(rename = "avgTokens")]
+    pub avg_tokens: u64,
+
+    #[serde(rename = "p50Tokens")]
+    pub p50_tokens: u64,
+
+    #[serde(rename = "p95Tokens")]
+    pub p95_tokens: u64,
+
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: u64,
+
+    #[serde(rename = "avgCost")]
+    pub avg_cost: Decimal,
+
+    #[serde(rename = "p50Cost")]
+    pub p50_cost: Decimal,
+
+    #[serde(rename = "p95Cost")]
+    pub p95_cost: Decimal,
+
+    #[serde(rename = "maxCost")]
+    pub max_cost: Decimal,
 }
 
 /// Daily usage aggregation
@@ -124,6 +211,9 @@ pub struct DailyUsage {
     #[serde(rename = "modelBreakdowns")]
     pub model_breakdowns: Vec<ModelBreakdown>,
 
+    #[serde(default)]
+    pub requests: RequestStats,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub project: Option<String>,
 }
@@ -160,6 +250,9 @@ pub struct MonthlyUsage {
     #[serde(rename = "modelBreakdowns")]
     pub model_breakdowns: Vec<ModelBreakdown>,
 
+    #[serde(default)]
+    pub requests: RequestStats,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub project: Option<String>,
 }
@@ -196,6 +289,15 @@ pub struct WeeklyUsage {
     #[serde(rename = "modelBreakdowns")]
     pub model_breakdowns: Vec<ModelBreakdown>,
 
+    #[serde(default)]
+    pub requests: RequestStats,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub project: Option<String>,
 }
+
+impl WeeklyUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    }
+}
@@ -1,3 +1,5 @@
+//! Small filesystem helpers.
+
 use std::env;
 use std::path::PathBuf;
 